@@ -0,0 +1,40 @@
+//! Command line parsing shared by the days that take options.
+
+use std::error::Error;
+use std::ops::RangeInclusive;
+
+/// Parses a range of values given on the command line, like `0-99` or `-5--1`, or a single value,
+/// like `7`, which is a range of just that value. Ranges that are empty, like `5-3`, are an error.
+pub fn parse_range(arg: &str) -> Result<RangeInclusive<i64>, Box<dyn Error>> {
+    let arg = arg.trim();
+
+    // the first `-` after the first character separates the ends, since the first end may be
+    // negative
+    let range = match arg.char_indices().skip(1).find(|&(_, c)| c == '-') {
+        Some((i, _)) => arg[..i].trim().parse()?..=arg[i + 1..].trim().parse()?,
+        None => {
+            let value = arg.parse()?;
+            value..=value
+        }
+    };
+    if range.is_empty() {
+        return Err(format!("{} is an empty range.", arg).into());
+    }
+    Ok(range)
+}
+
+/// Parses a comma-separated list of values and ranges, like `1,3,5-9`, in the order they're
+/// given. There can't be more than `max` values in all, so that a typo like `0-9999999999` is an
+/// error instead of using up all of memory.
+pub fn parse_list(arg: &str, max: usize) -> Result<Vec<i64>, Box<dyn Error>> {
+    let mut values = Vec::new();
+    for item in arg.split(',') {
+        let range = parse_range(item)?;
+        let len = i128::from(*range.end()) - i128::from(*range.start()) + 1;
+        if len > (max - values.len()) as i128 {
+            return Err(format!("{} has more than {} values.", arg, max).into());
+        }
+        values.extend(range);
+    }
+    Ok(values)
+}
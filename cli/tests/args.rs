@@ -0,0 +1,47 @@
+use cli::{parse_list, parse_range};
+
+#[test]
+fn ranges() {
+    assert_eq!(parse_range("0-99").unwrap(), 0..=99);
+    assert_eq!(parse_range("-5--1").unwrap(), -5..=-1);
+    assert_eq!(parse_range("-5-3").unwrap(), -5..=3);
+    assert_eq!(parse_range(" 7 ").unwrap(), 7..=7);
+    assert_eq!(parse_range("-7").unwrap(), -7..=-7);
+    assert_eq!(parse_range("4-4").unwrap(), 4..=4);
+}
+
+#[test]
+fn range_errors() {
+    let error = |arg| parse_range(arg).unwrap_err().to_string();
+    assert_eq!(error("5-3"), "5-3 is an empty range.");
+    assert_eq!(error("3--5"), "3--5 is an empty range.");
+    assert!(parse_range("").is_err());
+    assert!(parse_range("-").is_err());
+    assert!(parse_range("1-").is_err());
+    assert!(parse_range("x-9").is_err());
+}
+
+#[test]
+fn lists() {
+    assert_eq!(parse_list("5-9", 10).unwrap(), vec![5, 6, 7, 8, 9]);
+    assert_eq!(parse_list("4,3, 2,1", 10).unwrap(), vec![4, 3, 2, 1]);
+    assert_eq!(
+        parse_list("0,-3--2,7-8", 10).unwrap(),
+        vec![0, -3, -2, 7, 8]
+    );
+    assert_eq!(parse_list("0-4,5-9", 10).unwrap().len(), 10);
+    assert!(parse_list("", 10).is_err());
+    assert!(parse_list("1,,2", 10).is_err());
+    assert!(parse_list("1,5-3", 10).is_err());
+}
+
+#[test]
+fn lists_are_limited() {
+    let error = |arg| parse_list(arg, 10).unwrap_err().to_string();
+    assert_eq!(error("0-10"), "0-10 has more than 10 values.");
+    assert_eq!(error("0-4,5-10"), "0-4,5-10 has more than 10 values.");
+    assert_eq!(
+        error("-9223372036854775808-9223372036854775807"),
+        "-9223372036854775808-9223372036854775807 has more than 10 values."
+    );
+}
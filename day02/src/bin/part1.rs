@@ -65,68 +65,20 @@
 //! fire. To do this, before running the program, replace position 1 with the value 12 and replace
 //! position 2 with the value 2. What value is left at position 0 after the program halts?
 
+use intcode::Intcode;
+use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::str;
+use std::io::BufReader;
 
-struct Nums<B> {
-    reader: B,
-    buf: Vec<u8>,
-}
-
-impl<B: BufRead> Nums<B> {
-    fn new(reader: B) -> Nums<B> {
-        Nums {
-            reader,
-            buf: vec![],
-        }
-    }
-}
-
-impl<B: BufRead> Iterator for Nums<B> {
-    type Item = io::Result<i32>;
-
-    fn next(&mut self) -> Option<io::Result<i32>> {
-        self.buf.clear();
-        match self.reader.read_until(b',', &mut self.buf) {
-            Ok(0) => None,
-            Ok(n) => {
-                if n > 1 && (self.buf[n - 1] == b',' || self.buf[n - 1] == b'\n') {
-                    self.buf.pop();
-                }
-
-                Some(Ok(str::from_utf8(&self.buf)
-                    .unwrap()
-                    .parse::<i32>()
-                    .unwrap()))
-            }
-            Err(e) => Some(Err(e)),
-        }
-    }
-}
-
-fn main() -> io::Result<()> {
+fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
+    let mut program = Intcode::load(reader)?;
+    program.code[1] = 12;
+    program.code[2] = 2;
+    program.run_batch(&[])?;
 
-    let mut program: Vec<i32> = Nums::new(reader).map(|i| i.unwrap()).collect();
-    program[1] = 12;
-    program[2] = 2;
-
-    for i in (0..program.len()).step_by(4) {
-        let op = program[i];
-        let idx1 = program[i + 1] as usize;
-        let idx2 = program[i + 2] as usize;
-        let ridx = program[i + 3] as usize;
-        match op {
-            1 => program[ridx] = program[idx1] + program[idx2],
-            2 => program[ridx] = program[idx1] * program[idx2],
-            99 => break,
-            _ => panic!("Bad op: {}", op),
-        };
-    }
-
-    println!("Result: {}", program[0]);
+    println!("Result: {}", program.code[0]);
 
     Ok(())
 }
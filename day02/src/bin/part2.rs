@@ -39,80 +39,275 @@
 //! Find the input noun and verb that cause the program to produce the output 19690720. What is 100
 //! * noun + verb? (For example, if noun=12 and verb=2, the answer would be 1202.)
 
+use cli::parse_range;
+use intcode::{Budget, Intcode, Symbolic, Var};
+use std::env;
+use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::str;
+use std::io::BufReader;
+use std::ops::RangeInclusive;
 
-struct Nums<B> {
-    reader: B,
-    buf: Vec<u8>,
+const TARGET: i64 = 19690720;
+
+/// How many instructions the program gets to halt in, for each combination of inputs. Day 2's
+/// programs take a few dozen.
+const MAX_INSTRUCTIONS: u64 = 1_000_000;
+const USAGE: &str =
+    "Usage: day02-part2 [--target <output>] [--output <address>] [--inputs <address>=<first>-<last>,...]";
+
+/// A memory cell that's patched before the program runs, and the values to try in it.
+struct Input {
+    address: usize,
+    values: RangeInclusive<i64>,
 }
 
-impl<B: BufRead> Nums<B> {
-    fn new(reader: B) -> Nums<B> {
-        Nums {
-            reader,
-            buf: vec![],
+impl Input {
+    /// Addresses 1 and 2 are the noun and verb from the puzzle.
+    fn name(&self) -> String {
+        match self.address {
+            1 => "noun".to_string(),
+            2 => "verb".to_string(),
+            address => format!("[{}]", address),
         }
     }
 }
 
-impl<B: BufRead> Iterator for Nums<B> {
-    type Item = io::Result<i32>;
+struct Options {
+    target: i64,
+    output: usize,
+    inputs: Vec<Input>,
+}
 
-    fn next(&mut self) -> Option<io::Result<i32>> {
-        self.buf.clear();
-        match self.reader.read_until(b',', &mut self.buf) {
-            Ok(0) => None,
-            Ok(n) => {
-                if n > 1 && (self.buf[n - 1] == b',' || self.buf[n - 1] == b'\n') {
-                    self.buf.pop();
-                }
+/// Parses a comma-separated list of inputs, like `1=0-99,2=0-99`.
+fn parse_inputs(arg: &str) -> Result<Vec<Input>, Box<dyn Error>> {
+    arg.split(',')
+        .map(|input| {
+            let mut parts = input.trim().splitn(2, '=');
+            let address = parts.next().ok_or(USAGE)?.parse()?;
+            let values = parse_range(parts.next().ok_or(USAGE)?)?;
+            Ok(Input { address, values })
+        })
+        .collect()
+}
+
+fn parse_options() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        target: TARGET,
+        output: 0,
+        inputs: parse_inputs("1=0-99,2=0-99")?,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(USAGE)?;
+        match arg.as_str() {
+            "--target" => options.target = value.parse()?,
+            "--output" => options.output = value.parse()?,
+            "--inputs" => options.inputs = parse_inputs(&value)?,
+            _ => return Err(USAGE.into()),
+        }
+    }
+    Ok(options)
+}
+
+/// `constant + coefficients[0] * inputs[0] + coefficients[1] * inputs[1] + ...`
+#[derive(Clone, Debug, PartialEq)]
+struct Affine {
+    constant: i64,
+    coefficients: Vec<i64>,
+}
+
+impl Affine {
+    fn eval(&self, values: &[i64]) -> Option<i64> {
+        self.coefficients
+            .iter()
+            .zip(values.iter())
+            .try_fold(self.constant, |sum, (c, v)| {
+                sum.checked_add(c.checked_mul(*v)?)
+            })
+    }
 
-                Some(Ok(str::from_utf8(&self.buf)
-                    .unwrap()
-                    .parse::<i32>()
-                    .unwrap()))
+    fn describe(&self, inputs: &[Input]) -> String {
+        let mut description = self.constant.to_string();
+        for (c, input) in self.coefficients.iter().zip(inputs.iter()) {
+            match c {
+                0 => continue,
+                1 => description += &format!(" + {}", input.name()),
+                c if *c < 0 => description += &format!(" - {} * {}", -c, input.name()),
+                c => description += &format!(" + {} * {}", c, input.name()),
             }
-            Err(e) => Some(Err(e)),
         }
+        description
     }
 }
 
-fn run(program: &Vec<i32>, noun: i32, verb: i32) -> io::Result<i32> {
+/// Runs the program symbolically, with each input marked as a var, and returns its output as an
+/// affine function of the inputs. Fails if the output isn't affine, or if it only is because of
+/// something the program assumed about the inputs along the way, ie, that one of them is small
+/// enough to be a valid address.
+fn analyze(program: &Intcode, inputs: &[Input], output: usize) -> Result<Affine, String> {
     let mut program = program.clone();
-    program[1] = noun;
-    program[2] = verb;
-
-    for i in (0..program.len()).step_by(4) {
-        let op = program[i];
-        let idx1 = program[i + 1] as usize;
-        let idx2 = program[i + 2] as usize;
-        let ridx = program[i + 3] as usize;
-        match op {
-            1 => program[ridx] = program[idx1] + program[idx2],
-            2 => program[ridx] = program[idx1] * program[idx2],
-            99 => break,
-            _ => panic!("Bad op: {}", op),
-        };
-    }
-
-    Ok(program[0])
+    program.budget = Some(Budget::Instructions(MAX_INSTRUCTIONS));
+    for input in inputs {
+        program.code[input.address] = *input.values.start();
+    }
+    let mut symbolic = Symbolic::new(program);
+    for input in inputs {
+        symbolic.var(input.address);
+    }
+    let (_, state) = symbolic.run(&[]);
+    if !state.is_halted() {
+        return Err(format!(
+            "The program stopped ({}) instead of halting.",
+            state
+        ));
+    }
+
+    let value = symbolic.value_assuming(output);
+    if !value.assumptions.is_empty() {
+        return Err(format!("The output is {}.", value));
+    }
+    let (constant, vars) = value.expr.as_linear().ok_or_else(|| {
+        format!(
+            "The output, {}, isn't an affine function of the inputs.",
+            value.expr
+        )
+    })?;
+    let coefficients = inputs
+        .iter()
+        .map(|input| *vars.get(&Var::Cell(input.address)).unwrap_or(&0))
+        .collect();
+    Ok(Affine {
+        constant,
+        coefficients,
+    })
+}
+
+/// Runs the program with the given values patched in, returning the output, or `None` if the
+/// program didn't halt within `MAX_INSTRUCTIONS`.
+fn run(program: &Intcode, inputs: &[Input], values: &[i64], output: usize) -> Option<i64> {
+    let mut program = program.clone();
+    program.budget = Some(Budget::Instructions(MAX_INSTRUCTIONS));
+    for (input, &value) in inputs.iter().zip(values.iter()) {
+        program.code[input.address] = value;
+    }
+    match program.run_batch(&[]) {
+        Ok((_, state)) if state.is_halted() => Some(program.code[output]),
+        _ => None,
+    }
 }
 
-fn main() -> io::Result<()> {
+/// Steps `values` to the next combination of input values, like an odometer, with the last input
+/// changing fastest. Returns false once every combination has been tried.
+fn next(values: &mut [i64], inputs: &[Input]) -> bool {
+    for (value, input) in values.iter_mut().zip(inputs.iter()).rev() {
+        if *value < *input.values.end() {
+            *value += 1;
+            return true;
+        }
+        *value = *input.values.start();
+    }
+    false
+}
+
+/// Solves `affine = target` for the last input with a nonzero coefficient, trying every
+/// combination of the inputs before it. Inputs after it don't matter, so they're left at their
+/// first value. Solutions are tried in the same order as the brute force search. Returns
+/// `Ok(None)` if no combination of inputs outputs `target`, or an error if the output overflows,
+/// in which case the program doesn't really compute `affine`.
+fn solve(affine: &Affine, inputs: &[Input], target: i64) -> Result<Option<Vec<i64>>, String> {
+    let overflow = || "The output overflows for some inputs.".to_string();
+    let mut values: Vec<_> = inputs.iter().map(|input| *input.values.start()).collect();
+    let solve_for = match affine.coefficients.iter().rposition(|&c| c != 0) {
+        Some(i) => i,
+        None if affine.constant == target && inputs.iter().all(|i| !i.values.is_empty()) => {
+            return Ok(Some(values))
+        }
+        None => return Ok(None),
+    };
+
+    loop {
+        values[solve_for] = 0;
+        let rest = affine.eval(&values).ok_or_else(overflow)?;
+        let remainder = target.checked_sub(rest).ok_or_else(overflow)?;
+        let coefficient = affine.coefficients[solve_for];
+        if let (Some(0), Some(value)) = (
+            remainder.checked_rem(coefficient),
+            remainder.checked_div(coefficient),
+        ) {
+            if inputs[solve_for].values.contains(&value) {
+                values[solve_for] = value;
+                return Ok(Some(values));
+            }
+        }
+        values[solve_for] = *inputs[solve_for].values.end();
+        if !next(&mut values[..=solve_for], &inputs[..=solve_for]) {
+            return Ok(None);
+        }
+    }
+}
+
+fn brute_force(program: &Intcode, options: &Options) -> Option<Vec<i64>> {
+    if options.inputs.iter().any(|input| input.values.is_empty()) {
+        return None;
+    }
+    let mut values: Vec<_> = options
+        .inputs
+        .iter()
+        .map(|input| *input.values.start())
+        .collect();
+    loop {
+        if run(program, &options.inputs, &values, options.output) == Some(options.target) {
+            return Some(values);
+        }
+        if !next(&mut values, &options.inputs) {
+            return None;
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_options()?;
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
-    let program: Vec<i32> = Nums::new(reader).map(|i| i.unwrap()).collect();
-
-    for i in 0..=99 {
-        for j in 0..=99 {
-            let result = run(&program, i, j)?;
-            if result == 19690720 {
-                println!("Result: {}", 100 * i + j);
-                return Ok(())
+    let program = Intcode::load(reader)?;
+
+    // if the output is an affine function of the inputs, solve for them; otherwise, or if the
+    // solution doesn't check out, try every combination
+    let solution = match analyze(&program, &options.inputs, options.output) {
+        Ok(affine) => {
+            println!("Output = {}", affine.describe(&options.inputs));
+            match solve(&affine, &options.inputs, options.target) {
+                Ok(Some(values))
+                    if run(&program, &options.inputs, &values, options.output)
+                        == Some(options.target) =>
+                {
+                    Some(values)
+                }
+                Ok(Some(_)) => {
+                    println!("The solution doesn't check out. Trying every input.");
+                    brute_force(&program, &options)
+                }
+                Ok(None) => None,
+                Err(reason) => {
+                    println!("{} Trying every input.", reason);
+                    brute_force(&program, &options)
+                }
             }
         }
+        Err(reason) => {
+            println!("{} Trying every input.", reason);
+            brute_force(&program, &options)
+        }
+    };
+    let values = solution.ok_or(format!("Nothing outputs {}.", options.target))?;
+
+    for (input, value) in options.inputs.iter().zip(values.iter()) {
+        println!("{} = {}", input.name(), value);
+    }
+    if let [noun, verb] = values[..] {
+        if options.inputs[0].address == 1 && options.inputs[1].address == 2 {
+            println!("Result: {}", 100 * noun + verb);
+        }
     }
 
     Ok(())
@@ -132,9 +132,9 @@ impl<B: BufRead> Iterator for WireReader<B> {
 
                 // find position of comma or newline
                 let mut idx = None;
-                for i in 0..available.len() {
-                    if available[i] == b',' || available[i] == b'\n' {
-                        if available[i] == b'\n' {
+                for (i, &byte) in available.iter().enumerate() {
+                    if byte == b',' || byte == b'\n' {
+                        if byte == b'\n' {
                             self.wire += 1;
                         }
                         idx = Some(i);
@@ -148,7 +148,7 @@ impl<B: BufRead> Iterator for WireReader<B> {
                         (true, i + 1)
                     }
                     None => {
-                        self.buf.extend_from_slice(&available);
+                        self.buf.extend_from_slice(available);
                         (false, available.len())
                     }
                 }
@@ -133,9 +133,9 @@ impl<B: BufRead> Iterator for WireReader<B> {
 
                 // find position of comma or newline
                 let mut idx = None;
-                for i in 0..available.len() {
-                    if available[i] == b',' || available[i] == b'\n' {
-                        if available[i] == b'\n' {
+                for (i, &byte) in available.iter().enumerate() {
+                    if byte == b',' || byte == b'\n' {
+                        if byte == b'\n' {
                             self.wire += 1;
                         }
                         idx = Some(i);
@@ -149,7 +149,7 @@ impl<B: BufRead> Iterator for WireReader<B> {
                         (true, i + 1)
                     },
                     None => {
-                        self.buf.extend_from_slice(&available);
+                        self.buf.extend_from_slice(available);
                         (false, available.len())
                     }
                 }
@@ -187,7 +187,7 @@ fn main() -> io::Result<()> {
         }
     }
 
-    let mut min = std::i32::MAX;
+    let mut min = i32::MAX;
     for p in sets[0].iter() {
         if let Some(p2) = sets[1].get(p) {
             let new_min = p.2 + p2.2;
@@ -40,14 +40,10 @@ impl Iterator for Digits {
     type Item = u32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.position > 0 {
-            let result = self.num / self.position;
-            self.num %= self.position;
-            self.position /= 10;
-            Some(result)
-        } else {
-            None
-        }
+        let result = self.num.checked_div(self.position)?;
+        self.num %= self.position;
+        self.position /= 10;
+        Some(result)
     }
 }
 
@@ -86,185 +86,19 @@
 //! After providing 1 to the only input instruction and passing all the tests, what diagnostic code
 //! does the program produce?
 
-use std::convert::{TryFrom, TryInto};
+use intcode::Intcode;
 use std::error::Error;
-use std::fmt::{self, Display};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str;
-
-#[derive(Debug)]
-struct InvalidParameterMode(i32);
-
-impl Display for InvalidParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode.", self.0)
-    }
-}
-
-impl Error for InvalidParameterMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOpcode(i32);
-
-impl Display for InvalidOpcode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid opcode.", self.0)
-    }
-}
-
-impl Error for InvalidOpcode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-enum ParameterMode {
-    Position,
-    Immediate,
-}
-
-impl TryFrom<i32> for ParameterMode {
-    type Error = InvalidParameterMode;
-
-    fn try_from(code: i32) -> Result<Self, Self::Error> {
-        match code {
-            0 => Ok(ParameterMode::Position),
-            1 => Ok(ParameterMode::Immediate),
-            _ => Err(InvalidParameterMode(code)),
-        }
-    }
-}
-
-impl ParameterMode {
-    fn modes2(code: i32) -> Result<(Self, Self), InvalidParameterMode> {
-        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
-    }
-}
-
-enum Opcode {
-    Add(ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode),
-    Input(ParameterMode),
-    Output(ParameterMode),
-    Halt,
-}
-
-impl TryFrom<i32> for Opcode {
-    type Error = InvalidOpcode;
-
-    fn try_from(code: i32) -> Result<Self, Self::Error> {
-        match code % 100 {
-            1 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Add(mode1, mode2))
-            }
-            2 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Multiply(mode1, mode2))
-            }
-            3 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Input(mode))
-            },
-            4 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Output(mode))
-            },
-            99 => Ok(Opcode::Halt),
-            _ => Err(InvalidOpcode(code)),
-        }
-    }
-}
-
-struct Intcode(Vec<i32>);
-
-impl Intcode {
-    fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
-        let program = reader
-            .split(b',')
-            .map(|code| match code {
-                Ok(code) => {
-                    let s = str::from_utf8(&code)?;
-                    Ok(s.trim().parse()?)
-                }
-                Err(e) => Err(Box::new(e) as Box<dyn Error>),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Intcode(program))
-    }
-
-    fn operand(&self, sp: usize, mode: ParameterMode) -> i32 {
-        let v = self.0[sp];
-        match mode {
-            ParameterMode::Position => self.0[v as usize],
-            ParameterMode::Immediate => v,
-        }
-    }
-
-    fn operands3(
-        &self,
-        sp: usize,
-        mode1: ParameterMode,
-        mode2: ParameterMode,
-        mode3: ParameterMode,
-    ) -> (i32, i32, i32) {
-        (
-            self.operand(sp, mode1),
-            self.operand(sp + 1, mode2),
-            self.operand(sp + 2, mode3),
-        )
-    }
-
-    fn run(&mut self, input: Vec<i32>) -> Result<i32, Box<dyn Error>> {
-        let mut inputp = 0;
-        let mut ip = 0;
-        loop {
-            let opcode: Opcode = self.0[ip].try_into()?;
-            let advance = match opcode {
-                Opcode::Add(mode1, mode2) => {
-                    let (op1, op2, op3) =
-                        self.operands3(ip + 1, mode1, mode2, ParameterMode::Immediate);
-                    self.0[op3 as usize] = op1 + op2;
-                    4
-                }
-                Opcode::Multiply(mode1, mode2) => {
-                    let (op1, op2, op3) =
-                        self.operands3(ip + 1, mode1, mode2, ParameterMode::Immediate);
-                    self.0[op3 as usize] = op1 * op2;
-                    4
-                }
-                Opcode::Input(_mode) => {
-                    // not sure what to do if mode is not immediate?
-                    let op = self.operand(ip + 1, ParameterMode::Immediate) as usize;
-                    self.0[op] = input[inputp];
-                    inputp += 1;
-                    2
-                },
-                Opcode::Output(mode) => {
-                    let op = self.operand(ip + 1, mode);
-                    println!("Output: {}", op);
-                    2
-                }
-                Opcode::Halt => break,
-            };
-            ip += advance;
-        }
-        Ok(self.0[0])
-    }
-}
+use std::io::BufReader;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
     let mut program = Intcode::load(reader)?;
-    program.run(vec![1])?;
+    let (output, _) = program.run_batch(&[1])?;
+    for out in output {
+        println!("Output: {}", out);
+    }
 
     Ok(())
 }
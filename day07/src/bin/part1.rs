@@ -74,308 +74,44 @@
 //! Try every combination of phase settings on the amplifiers. What is the highest signal that can
 //! be sent to the thrusters?
 
-use std::clone::Clone;
-use std::convert::{TryFrom, TryInto};
+use day07::{parse_options, search};
+use intcode::Intcode;
 use std::error::Error;
-use std::fmt::{self, Display};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str;
-
-const AMPLIFIERS: usize = 5;
-
-#[derive(Debug)]
-struct InvalidParameterMode(i32);
-
-impl Display for InvalidParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode.", self.0)
-    }
-}
-
-impl Error for InvalidParameterMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOpcode(i32);
-
-impl Display for InvalidOpcode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid opcode.", self.0)
-    }
-}
-
-impl Error for InvalidOpcode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-enum ParameterMode {
-    Position,
-    Immediate,
-}
-
-impl TryFrom<i32> for ParameterMode {
-    type Error = InvalidParameterMode;
-
-    fn try_from(code: i32) -> Result<Self, Self::Error> {
-        match code {
-            0 => Ok(ParameterMode::Position),
-            1 => Ok(ParameterMode::Immediate),
-            _ => Err(InvalidParameterMode(code)),
-        }
-    }
-}
-
-impl ParameterMode {
-    fn modes2(code: i32) -> Result<(Self, Self), InvalidParameterMode> {
-        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
-    }
-}
-
-enum Opcode {
-    Add(ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode),
-    Input(ParameterMode),
-    Output(ParameterMode),
-    JumpIfTrue(ParameterMode, ParameterMode),
-    JumpIfFalse(ParameterMode, ParameterMode),
-    LessThan(ParameterMode, ParameterMode),
-    Equal(ParameterMode, ParameterMode),
-    Halt,
-}
-
-impl TryFrom<i32> for Opcode {
-    type Error = InvalidOpcode;
-
-    fn try_from(code: i32) -> Result<Self, Self::Error> {
-        match code % 100 {
-            1 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Add(mode1, mode2))
-            }
-            2 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Multiply(mode1, mode2))
-            }
-            3 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Input(mode))
-            }
-            4 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Output(mode))
-            }
-            5 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfTrue(mode1, mode2))
-            }
-            6 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfFalse(mode1, mode2))
-            }
-            7 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::LessThan(mode1, mode2))
-            }
-            8 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Equal(mode1, mode2))
-            }
-            99 => Ok(Opcode::Halt),
-            _ => Err(InvalidOpcode(code)),
-        }
-    }
-}
-
-#[derive(Clone)]
-struct Intcode(Vec<i32>);
-
-impl Intcode {
-    fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
-        let program = reader
-            .split(b',')
-            .map(|code| match code {
-                Ok(code) => {
-                    let s = str::from_utf8(&code)?;
-                    Ok(s.trim().parse()?)
-                }
-                Err(e) => Err(Box::new(e) as Box<dyn Error>),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Intcode(program))
-    }
-
-    fn operand(&self, sp: usize, mode: ParameterMode) -> i32 {
-        let v = self.0[sp];
-        match mode {
-            ParameterMode::Position => self.0[v as usize],
-            ParameterMode::Immediate => v,
-        }
-    }
-
-    fn operands2(&self, sp: usize, mode1: ParameterMode, mode2: ParameterMode) -> (i32, i32) {
-        (self.operand(sp, mode1), self.operand(sp + 1, mode2))
-    }
-
-    fn operands3(
-        &self,
-        sp: usize,
-        mode1: ParameterMode,
-        mode2: ParameterMode,
-        mode3: ParameterMode,
-    ) -> (i32, i32, i32) {
-        (
-            self.operand(sp, mode1),
-            self.operand(sp + 1, mode2),
-            self.operand(sp + 2, mode3),
-        )
-    }
-
-    fn run(&mut self, input: &Vec<i32>) -> Result<Vec<i32>, Box<dyn Error>> {
-        let mut output: Vec<i32> = Vec::new();
-        let mut inputp = 0;
-        let mut ip = 0;
-        loop {
-            let opcode: Opcode = self.0[ip].try_into()?;
-            ip = match opcode {
-                Opcode::Add(mode1, mode2) => {
-                    let (op1, op2, op3) =
-                        self.operands3(ip + 1, mode1, mode2, ParameterMode::Immediate);
-                    self.0[op3 as usize] = op1 + op2;
-                    ip + 4
-                }
-                Opcode::Multiply(mode1, mode2) => {
-                    let (op1, op2, op3) =
-                        self.operands3(ip + 1, mode1, mode2, ParameterMode::Immediate);
-                    self.0[op3 as usize] = op1 * op2;
-                    ip + 4
-                }
-                Opcode::Input(_mode) => {
-                    // not sure what to do if mode is not immediate?
-                    let op = self.operand(ip + 1, ParameterMode::Immediate) as usize;
-                    self.0[op] = input[inputp];
-                    inputp += 1;
-                    ip + 2
-                }
-                Opcode::Output(mode) => {
-                    let op = self.operand(ip + 1, mode);
-                    output.push(op);
-                    ip + 2
-                }
-                Opcode::JumpIfTrue(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(ip + 1, mode1, mode2);
-                    if op1 != 0 {
-                        op2 as usize
-                    } else {
-                        ip + 3
-                    }
-                }
-                Opcode::JumpIfFalse(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(ip + 1, mode1, mode2);
-                    if op1 == 0 {
-                        op2 as usize
-                    } else {
-                        ip + 3
-                    }
-                }
-                Opcode::LessThan(mode1, mode2) => {
-                    let (op1, op2, op3) =
-                        self.operands3(ip + 1, mode1, mode2, ParameterMode::Immediate);
-                    self.0[op3 as usize] = if op1 < op2 { 1 } else { 0 };
-                    ip + 4
-                }
-                Opcode::Equal(mode1, mode2) => {
-                    let (op1, op2, op3) =
-                        self.operands3(ip + 1, mode1, mode2, ParameterMode::Immediate);
-                    self.0[op3 as usize] = if op1 == op2 { 1 } else { 0 };
-                    ip + 4
-                }
-                Opcode::Halt => break,
-            };
-        }
-        Ok(output)
-    }
-}
-
-struct PhaseSettings([i32; AMPLIFIERS], [usize; AMPLIFIERS], usize);
-
-impl PhaseSettings {
-    fn permutations() -> PhaseSettings {
-        PhaseSettings([0, 1, 2, 3, 4], [0; AMPLIFIERS], AMPLIFIERS)
-    }
-}
-
-impl Iterator for PhaseSettings {
-    type Item = [i32; AMPLIFIERS];
-
-    // Heap's algorithm for permutations
-    // Basically copied from the non-recursive implementation in the Wikipedia article.
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.2 == AMPLIFIERS {
-            self.2 = 0;
-            return Some(self.0);
-        }
-
-        while self.2 < AMPLIFIERS {
-            if self.1[self.2] < self.2 {
-                if (self.2 & 1) == 0 {
-                    self.0.swap(0, self.2);
-                } else {
-                    self.0.swap(self.1[self.2], self.2);
-                }
-                self.1[self.2] += 1;
-                self.2 = 0;
-                return Some(self.0);
-            } else {
-                self.1[self.2] = 0;
-                self.2 += 1;
-            }
-        }
-        None
+use std::io::BufReader;
+
+const DEFAULT_PHASES: [i64; 5] = [0, 1, 2, 3, 4];
+const USAGE: &str =
+    "Usage: day07-part1 [--phases <first>-<last>|<list>] [--amplifiers <n>] [--top <k>] [--threads <n>]";
+
+/// The signal sent to the thrusters when each amplifier in the chain gets its phase setting. The
+/// chain always sends one.
+fn thrust(program: &Intcode, phase_settings: &[i64]) -> Result<Option<i64>, Box<dyn Error>> {
+    let mut signal = 0;
+    for &phase_setting in phase_settings {
+        let mut program = program.clone();
+        let (output, _) = program.run_batch(&[phase_setting, signal])?;
+        signal = *output
+            .first()
+            .ok_or("An amplifier didn't output anything.")?;
     }
+    Ok(Some(signal))
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_options(&DEFAULT_PHASES, USAGE)?;
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
     let program = Intcode::load(reader)?;
-    let mut input = vec![0i32; 2];
-    let mut best = 0i32;
-    for phase_settings in PhaseSettings::permutations() {
-        input[1] = 0;
-        for i in 0..AMPLIFIERS {
-            let mut program = program.clone();
-            input[0] = phase_settings[i];
-
-            let output = program.run(&input)?;
-            input[1] = output[0];
-        }
-        println!(
-            "Output for {}{}{}{}{}: {}",
-            phase_settings[0],
-            phase_settings[1],
-            phase_settings[2],
-            phase_settings[3],
-            phase_settings[4],
-            input[1]
-        );
-
-        if input[1] > best {
-            best = input[1];
-        }
+    let results = search(&program, &options, thrust)?;
+    for (signal, phase_settings) in &results {
+        let phase_settings: Vec<_> = phase_settings.iter().map(i64::to_string).collect();
+        println!("Output for {}: {}", phase_settings.join(","), signal);
     }
-    println!("Best: {}", best);
+    let best = results
+        .first()
+        .ok_or("There aren't any phase settings to try.")?;
+    println!("Best: {}", best.0);
 
     Ok(())
 }
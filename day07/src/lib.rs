@@ -0,0 +1,228 @@
+//! The search that both parts of day 7 share: try every way of giving the amplifiers different
+//! phase settings, spread over a few threads, and keep the settings that send the highest signals.
+//! The parts only differ in how the amplifiers are wired, and so in how the signal is computed.
+
+use cli::parse_list;
+use intcode::Intcode;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::env;
+use std::error::Error;
+use std::sync::Mutex;
+use std::thread;
+
+/// The most phase settings `--phases` can give, which is already far more than there's time to
+/// try every permutation of on five amplifiers.
+const MAX_PHASES: usize = 100;
+
+/// How to search: which phase settings to try, on how many amplifiers, how many of the best
+/// settings to report, and how many threads to search with.
+pub struct Options {
+    pub phases: Vec<i64>,
+    pub amplifiers: usize,
+    pub top: usize,
+    pub threads: usize,
+}
+
+/// Parses `--phases`, `--amplifiers`, `--top`, and `--threads`. Phase settings can't be used
+/// twice, so there has to be at least one amplifier and no more than there are phase settings.
+pub fn parse_options(default_phases: &[i64], usage: &str) -> Result<Options, Box<dyn Error>> {
+    let mut phases = default_phases.to_vec();
+    let mut amplifiers: Option<usize> = None;
+    let mut top = 5;
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(usage)?;
+        match arg.as_str() {
+            "--phases" => phases = parse_list(&value, MAX_PHASES)?,
+            "--amplifiers" => amplifiers = Some(value.parse()?),
+            "--top" => top = value.parse()?,
+            "--threads" => threads = value.parse()?,
+            _ => return Err(usage.into()),
+        }
+    }
+
+    if top == 0 {
+        return Err("--top has to be at least 1.".into());
+    }
+
+    let mut seen = HashSet::new();
+    if let Some(phase) = phases.iter().find(|&&phase| !seen.insert(phase)) {
+        return Err(format!("Phase setting {} is given more than once.", phase).into());
+    }
+
+    // by default, every amplifier gets a different phase setting, and every setting is used
+    let amplifiers = amplifiers.unwrap_or(phases.len());
+    if amplifiers == 0 {
+        return Err("There has to be at least one amplifier.".into());
+    }
+    if amplifiers > phases.len() {
+        return Err(format!(
+            "Can't give {} amplifiers different phase settings from only {}.",
+            amplifiers,
+            phases.len()
+        )
+        .into());
+    }
+    Ok(Options {
+        phases,
+        amplifiers,
+        top,
+        threads: threads.max(1),
+    })
+}
+
+/// Every way to give `amplifiers` amplifiers a different phase setting from `phases`, in
+/// lexicographic order of where the settings are in `phases`. They're generated as they're
+/// needed, since there can be a lot of them.
+struct Permutations<'a> {
+    phases: &'a [i64],
+
+    /// `phases` rearranged so that the first `amplifiers` are the next permutation.
+    indices: Vec<usize>,
+
+    /// For each amplifier, how many more settings it has to try before the one before it moves on.
+    cycles: Vec<usize>,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Permutations<'a> {
+    /// Panics if there are more amplifiers than phase settings.
+    fn new(phases: &'a [i64], amplifiers: usize) -> Permutations<'a> {
+        let n = phases.len();
+        assert!(amplifiers <= n);
+        Permutations {
+            phases,
+            indices: (0..n).collect(),
+            cycles: (n - amplifiers + 1..=n).rev().collect(),
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Moves the last amplifier that hasn't tried every setting that's left for it on to its next
+    /// setting, and starts the amplifiers after it over. Returns false if every amplifier has
+    /// tried everything.
+    fn advance(&mut self) -> bool {
+        let n = self.indices.len();
+        for i in (0..self.cycles.len()).rev() {
+            self.cycles[i] -= 1;
+            if self.cycles[i] == 0 {
+                self.indices[i..].rotate_left(1);
+                self.cycles[i] = n - i;
+            } else {
+                self.indices.swap(i, n - self.cycles[i]);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Iterator for Permutations<'_> {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Vec<i64>> {
+        if self.done {
+            return None;
+        }
+        if self.started && !self.advance() {
+            self.done = true;
+            return None;
+        }
+        self.started = true;
+        let k = self.cycles.len();
+        Some(self.indices[..k].iter().map(|&i| self.phases[i]).collect())
+    }
+}
+
+/// Signals sent to the thrusters, and the phase settings that sent them.
+pub type Signals = Vec<(i64, Vec<i64>)>;
+
+/// Computes the signal a program sends to the thrusters, given each amplifier's phase setting, or
+/// `None` if the settings don't send a signal at all.
+pub type Thrust = fn(&Intcode, &[i64]) -> Result<Option<i64>, Box<dyn Error>>;
+
+/// The `k` highest signals, highest first. Ties are broken by phase settings, so that the result
+/// doesn't depend on which thread found what.
+fn top(mut results: Signals, k: usize) -> Signals {
+    results.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    results.truncate(k);
+    results
+}
+
+/// The `k` highest signals seen so far, in a heap with the lowest on top so that it's quick to
+/// drop when a higher one comes along.
+struct Top {
+    k: usize,
+    heap: BinaryHeap<Reverse<(i64, Reverse<Vec<i64>>)>>,
+}
+
+impl Top {
+    fn new(k: usize) -> Top {
+        Top {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    fn push(&mut self, signal: i64, phase_settings: Vec<i64>) {
+        self.heap.push(Reverse((signal, Reverse(phase_settings))));
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    fn into_signals(self) -> Signals {
+        let results = self.heap.into_iter();
+        top(
+            results
+                .map(|Reverse((signal, Reverse(phases)))| (signal, phases))
+                .collect(),
+            self.k,
+        )
+    }
+}
+
+/// Gives `thrust` every permutation of phase settings, and returns the `options.top` highest
+/// signals, highest first. The threads take permutations from the same iterator as they need
+/// them, and each keeps only its own top `k`.
+pub fn search(
+    program: &Intcode,
+    options: &Options,
+    thrust: Thrust,
+) -> Result<Signals, Box<dyn Error>> {
+    let candidates = Mutex::new(Permutations::new(&options.phases, options.amplifiers));
+    let k = options.top;
+    let results = thread::scope(|scope| {
+        let workers: Vec<_> = (0..options.threads)
+            .map(|_| {
+                let (program, candidates) = (program.clone(), &candidates);
+                scope.spawn(move || -> Result<_, String> {
+                    let mut results = Top::new(k);
+                    loop {
+                        let next = candidates.lock().map_err(|e| e.to_string())?.next();
+                        let phase_settings = match next {
+                            Some(phase_settings) => phase_settings,
+                            None => return Ok(results.into_signals()),
+                        };
+                        let signal =
+                            thrust(&program, &phase_settings).map_err(|e| e.to_string())?;
+                        if let Some(signal) = signal {
+                            results.push(signal, phase_settings);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for worker in workers {
+            results.extend(worker.join().map_err(|_| "A worker panicked.")??);
+        }
+        Ok::<_, Box<dyn Error>>(results)
+    })?;
+    Ok(top(results, k))
+}
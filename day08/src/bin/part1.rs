@@ -37,20 +37,20 @@
 //! multiplied by the number of 2 digits?
 
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufReader, Read};
 
 const WIDTH: usize = 25;
 const HEIGHT: usize = 6;
 
 fn main() -> io::Result<()> {
-    let file = File::open("input.txt")?;
-    let mut fewest_zeros = std::usize::MAX;
+    let file = BufReader::new(File::open("input.txt")?);
+    let mut fewest_zeros = usize::MAX;
     let mut result = 0;
     let mut counts = [0usize; 3];
     let mut i = WIDTH * HEIGHT;
     for byte in file.bytes() {
         let byte = byte?;
-        if byte < b'0' || byte > b'9' {
+        if !byte.is_ascii_digit() {
             continue;
         }
 
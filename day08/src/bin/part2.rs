@@ -41,18 +41,18 @@
 //! What message is produced after decoding your image?
 
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufReader, Read};
 
 const WIDTH: usize = 25;
 const HEIGHT: usize = 6;
 
 fn main() -> io::Result<()> {
-    let file = File::open("input.txt")?;
+    let file = BufReader::new(File::open("input.txt")?);
     let mut image = [2u8; WIDTH * HEIGHT];
     let mut i = 0;
     for byte in file.bytes() {
         let byte = byte?;
-        if byte < b'0' || byte > b'2' {
+        if !(b'0'..=b'2').contains(&byte) {
             continue;
         }
 
@@ -135,7 +135,7 @@ fn gcd(a: isize, b: isize) -> isize {
 }
 
 // ugh, there's got to be a better solution than this...
-fn calculate(astroids: &Vec<Vec<bool>>, x: usize, y: usize, width: usize, height: usize) -> u16 {
+fn calculate(astroids: &[Vec<bool>], x: usize, y: usize, width: usize, height: usize) -> u16 {
     let mut count = 0;
 
     // horizontal
@@ -147,11 +147,8 @@ fn calculate(astroids: &Vec<Vec<bool>>, x: usize, y: usize, width: usize, height
             }
         }
     }
-    for x1 in (x + 1)..width {
-        if astroids[y][x1] {
-            count += 1;
-            break;
-        }
+    if astroids[y][(x + 1)..width].iter().any(|&astroid| astroid) {
+        count += 1;
     }
 
     // vertical
@@ -163,11 +160,8 @@ fn calculate(astroids: &Vec<Vec<bool>>, x: usize, y: usize, width: usize, height
             }
         }
     }
-    for y1 in (y + 1)..height {
-        if astroids[y1][x] {
-            count += 1;
-            break;
-        }
+    if ((y + 1)..height).any(|y1| astroids[y1][x]) {
+        count += 1;
     }
 
     // diagonals
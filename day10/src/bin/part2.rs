@@ -125,7 +125,7 @@ impl Eq for Asteroid {}
 
 impl Ord for Asteroid {
     fn cmp(&self, other: &Self) -> Ordering {
-        if self.eq(&other) {
+        if self.eq(other) {
             return Ordering::Equal;
         }
         self.radians().partial_cmp(&other.radians()).unwrap()
@@ -134,7 +134,7 @@ impl Ord for Asteroid {
 
 impl PartialOrd for Asteroid {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
@@ -150,7 +150,7 @@ fn gcd(a: isize, b: isize) -> isize {
 }
 
 // ugh, there's got to be a better solution than this...
-fn get_visible_asteroids(asteroids: &mut Vec<Vec<bool>>, x: usize, y: usize, width: usize, height: usize) -> Vec<Asteroid> {
+fn get_visible_asteroids(asteroids: &mut [Vec<bool>], x: usize, y: usize, width: usize, height: usize) -> Vec<Asteroid> {
     let mut visible: Vec<Asteroid> = Vec::new();
 
     // horizontal
@@ -163,12 +163,9 @@ fn get_visible_asteroids(asteroids: &mut Vec<Vec<bool>>, x: usize, y: usize, wid
             }
         }
     }
-    for x1 in (x + 1)..width {
-        if asteroids[y][x1] {
-            visible.push(Asteroid::new(x, y, x1, y));
-            asteroids[y][x1] = false;
-            break;
-        }
+    if let Some(x1) = ((x + 1)..width).find(|&x1| asteroids[y][x1]) {
+        visible.push(Asteroid::new(x, y, x1, y));
+        asteroids[y][x1] = false;
     }
 
     // vertical
@@ -181,12 +178,9 @@ fn get_visible_asteroids(asteroids: &mut Vec<Vec<bool>>, x: usize, y: usize, wid
             }
         }
     }
-    for y1 in (y + 1)..height {
-        if asteroids[y1][x] {
-            visible.push(Asteroid::new(x, y, x, y1));
-            asteroids[y1][x] = false;
-            break;
-        }
+    if let Some(y1) = ((y + 1)..height).find(|&y1| asteroids[y1][x]) {
+        visible.push(Asteroid::new(x, y, x, y1));
+        asteroids[y1][x] = false;
     }
 
     // diagonals
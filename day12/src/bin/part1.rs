@@ -252,15 +252,15 @@ impl Moon {
     }
 
     fn kinetic_energy(&self) -> usize {
-        (self.velocity.x.abs() as usize)
-            + (self.velocity.y.abs() as usize)
-            + (self.velocity.z.abs() as usize)
+        self.velocity.x.unsigned_abs()
+            + self.velocity.y.unsigned_abs()
+            + self.velocity.z.unsigned_abs()
     }
 
     fn potential_energy(&self) -> usize {
-        (self.position.x.abs() as usize)
-            + (self.position.y.abs() as usize)
-            + (self.position.z.abs() as usize)
+        self.position.x.unsigned_abs()
+            + self.position.y.unsigned_abs()
+            + self.position.z.unsigned_abs()
     }
 
     fn energy(&self) -> usize {
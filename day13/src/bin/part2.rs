@@ -19,364 +19,87 @@
 //!
 //! Beat the game by breaking all the blocks. What is your score after the last block is broken?
 
-use std::clone::Clone;
-use std::cmp::PartialEq;
-use std::convert::{TryFrom, TryInto};
+use intcode::{Intcode, Io, Profile};
+use std::env;
 use std::error::Error;
-use std::fmt::{self, Display};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str;
+use std::io::BufReader;
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 use std::time::Duration;
 
-#[derive(Debug)]
-struct InvalidParameterMode(i64);
-
-impl Display for InvalidParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode.", self.0)
-    }
-}
-
-impl Error for InvalidParameterMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOpcode(i64);
-
-impl Display for InvalidOpcode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid opcode.", self.0)
-    }
-}
-
-impl Error for InvalidOpcode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOutputMode(ParameterMode);
-
-impl Display for InvalidOutputMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode for output.", self.0)
-    }
-}
-
-impl Error for InvalidOutputMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
-}
-
-impl Display for ParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ParameterMode::Position => "'position'",
-                ParameterMode::Immediate => "'immediate'",
-                ParameterMode::Relative => "'relative'",
-            }
-        )
-    }
-}
-
-impl TryFrom<i64> for ParameterMode {
-    type Error = InvalidParameterMode;
-
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code {
-            0 => Ok(ParameterMode::Position),
-            1 => Ok(ParameterMode::Immediate),
-            2 => Ok(ParameterMode::Relative),
-            _ => Err(InvalidParameterMode(code)),
-        }
-    }
-}
-
-impl ParameterMode {
-    fn modes2(code: i64) -> Result<(Self, Self), InvalidParameterMode> {
-        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
-    }
-
-    fn modes3(code: i64) -> Result<(Self, Self, Self), InvalidParameterMode> {
-        Ok((
-            (code % 10).try_into()?,
-            ((code / 10) % 10).try_into()?,
-            ((code / 100) % 10).try_into()?,
-        ))
-    }
-}
-
-enum Opcode {
-    Add(ParameterMode, ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode, ParameterMode),
-    Input(ParameterMode),
-    Output(ParameterMode),
-    JumpIfTrue(ParameterMode, ParameterMode),
-    JumpIfFalse(ParameterMode, ParameterMode),
-    LessThan(ParameterMode, ParameterMode, ParameterMode),
-    Equal(ParameterMode, ParameterMode, ParameterMode),
-    RelativeBaseOffset(ParameterMode),
-    Halt,
-}
-
-impl TryFrom<i64> for Opcode {
-    type Error = InvalidOpcode;
-
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code % 100 {
-            1 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Add(mode1, mode2, mode3))
-            }
-            2 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Multiply(mode1, mode2, mode3))
-            }
-            3 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Input(mode))
-            }
-            4 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Output(mode))
-            }
-            5 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfTrue(mode1, mode2))
-            }
-            6 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfFalse(mode1, mode2))
-            }
-            7 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::LessThan(mode1, mode2, mode3))
-            }
-            8 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Equal(mode1, mode2, mode3))
-            }
-            9 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::RelativeBaseOffset(mode))
-            }
-            99 => Ok(Opcode::Halt),
-            _ => Err(InvalidOpcode(code)),
+struct Game {
+    // board dimensions are 45x24
+    display: Vec<Vec<u8>>,
+    score: i64,
+    // only the x coordinates of the ball and paddle matter
+    ball: i64,
+    paddle: i64,
+    tile: Vec<i64>,
+}
+
+impl Game {
+    fn new() -> Game {
+        Game {
+            display: vec![vec![0u8; 45]; 24],
+            score: 0,
+            ball: 0,
+            paddle: 0,
+            tile: Vec::with_capacity(3),
         }
     }
-}
-
-#[derive(Clone)]
-struct Intcode {
-    code: Vec<i64>,
-    ip: usize,
-    relative_base: isize,
-}
-
-impl Intcode {
-    fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
-        let code = reader
-            .split(b',')
-            .map(|code| match code {
-                Ok(code) => {
-                    let s = str::from_utf8(&code)?;
-                    Ok(s.trim().parse()?)
-                }
-                Err(e) => Err(Box::new(e) as Box<dyn Error>),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Intcode {
-            code,
-            ip: 0,
-            relative_base: 0,
-        })
-    }
 
-    fn read(&mut self, i: usize) -> i64 {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
+    fn update_display(&mut self, x: i64, y: i64, id: i64) {
+        if x == -1 && y == 0 {
+            self.score = id;
+            return;
         }
-        self.code[i]
-    }
 
-    fn write(&mut self, i: usize, v: i64) {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
-        }
-        self.code[i] = v;
-    }
+        self.display[y as usize][x as usize] = id as u8;
 
-    fn output_operand(
-        &mut self,
-        sp: usize,
-        mode: ParameterMode,
-    ) -> Result<usize, InvalidOutputMode> {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => Ok(v as usize),
-            ParameterMode::Immediate => Err(InvalidOutputMode(mode)),
-            ParameterMode::Relative => Ok((v as isize + self.relative_base) as usize),
+        if id == 3 {
+            self.paddle = x;
+        } else if id == 4 {
+            self.ball = x;
         }
     }
 
-    fn operand(&mut self, sp: usize, mode: ParameterMode) -> i64 {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => self.read(v as usize),
-            ParameterMode::Immediate => v,
-            ParameterMode::Relative => self.read((v as isize + self.relative_base) as usize),
+    fn draw_display(&self) {
+        println!("\x1b[H");
+        println!("Score: {}", self.score);
+        for row in self.display.iter() {
+            println!(
+                "{}",
+                row.iter()
+                    .map(|id| match id {
+                        1 => '█',
+                        2 => '░',
+                        3 => '―',
+                        4 => '⍟',
+                        _ => ' ',
+                    })
+                    .collect::<String>()
+            );
         }
     }
-
-    fn operands2(&mut self, sp: usize, mode1: ParameterMode, mode2: ParameterMode) -> (i64, i64) {
-        (self.operand(sp, mode1), self.operand(sp + 1, mode2))
-    }
-
-    fn run(&mut self, input: &Vec<i64>) -> Result<(Vec<i64>, bool), Box<dyn Error>> {
-        let mut output: Vec<i64> = Vec::new();
-        let mut inputp = 0;
-        let mut halted = false;
-        loop {
-            let opcode: Opcode = self.read(self.ip).try_into()?;
-            self.ip = match opcode {
-                Opcode::Add(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 + op2);
-                    self.ip + 4
-                }
-                Opcode::Multiply(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 * op2);
-                    self.ip + 4
-                }
-                Opcode::Input(mode) => {
-                    if inputp >= input.len() {
-                        // not enough input; suspend
-                        break;
-                    }
-
-                    let op = self.output_operand(self.ip + 1, mode)?;
-                    self.write(op, input[inputp]);
-                    inputp += 1;
-                    self.ip + 2
-                }
-                Opcode::Output(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    output.push(op);
-                    self.ip + 2
-                }
-                Opcode::JumpIfTrue(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 != 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::JumpIfFalse(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 == 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::LessThan(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 < op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::Equal(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 == op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::RelativeBaseOffset(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    self.relative_base += op as isize;
-                    self.ip + 2
-                }
-                Opcode::Halt => {
-                    halted = true;
-                    break;
-                }
-            };
-        }
-        Ok((output, halted))
-    }
 }
 
-struct Point2(usize, usize);
-
-fn update_display(
-    score: &mut i64,
-    display: &mut Vec<Vec<u8>>,
-    output: &Vec<i64>,
-) -> (Point2, Point2) {
-    let mut ball = Point2(0, 0);
-    let mut paddle = Point2(0, 0);
-    for out in output.chunks(3) {
-        if out[0] == -1 && out[1] == 0 {
-            *score = out[2];
-            continue;
-        }
-
-        let x = out[0] as usize;
-        let y = out[1] as usize;
-        display[y][x] = out[2] as u8;
-
-        if out[2] == 3 {
-            paddle = Point2(x, y);
-        } else if out[2] == 4 {
-            ball = Point2(x, y);
-        }
+impl Io for Game {
+    // the game asks for input once per frame, so this is where we draw the display and decide
+    // which way to move the joystick
+    fn input(&mut self) -> Option<i64> {
+        self.draw_display();
+        sleep(Duration::from_millis(1));
+        Some((self.ball - self.paddle).signum())
     }
-    (ball, paddle)
-}
 
-fn draw_display(display: &Vec<Vec<u8>>, score: i64) {
-    println!("\x1b[H");
-    println!("Score: {}", score);
-    for row in display {
-        println!(
-            "{}",
-            row.iter()
-                .map(|id| match id {
-                    1 => '█',
-                    2 => '░',
-                    3 => '―',
-                    4 => '⍟',
-                    _ => ' ',
-                })
-                .collect::<String>()
-        );
+    // output comes in x, y, tile id triples
+    fn output(&mut self, value: i64) {
+        self.tile.push(value);
+        if self.tile.len() == 3 {
+            self.update_display(self.tile[0], self.tile[1], self.tile[2]);
+            self.tile.clear();
+        }
     }
 }
 
@@ -385,30 +108,22 @@ fn main() -> Result<(), Box<dyn Error>> {
     let reader = BufReader::new(file);
     let mut program = Intcode::load(reader)?;
     program.code[0] = 2;
-    println!("\x1b[?1049h");
-
-    // board dimensions are 45x24
-    let mut display = vec![vec![0u8; 45]; 24];
-    let mut score = 0;
-
-    // run once to get a lay of the land before providing our first input
-    let mut input = Vec::with_capacity(1);
-    let (output, mut halted) = program.run(&input)?;
-    let (ball, paddle) = update_display(&mut score, &mut display, &output);
-    draw_display(&display, score);
-    input.push(((ball.0 as i64) - (paddle.0 as i64)).signum());
 
-    // now loop until the game has halted
-    while !halted {
-        let (output, halt) = program.run(&input)?;
-        let (ball, paddle) = update_display(&mut score, &mut display, &output);
-        sleep(Duration::from_millis(1));
-        draw_display(&display, score);
-        input[0] = ((ball.0 as i64) - (paddle.0 as i64)).signum();
-        halted = halt;
+    // `--profile` prints where the game spends its time once it's over
+    let profile = Arc::new(Mutex::new(Profile::default()));
+    if env::args().any(|arg| arg == "--profile") {
+        program.tracer = Some(Box::new(profile.clone()));
     }
+    println!("\x1b[?1049h");
+
+    let mut game = Game::new();
+    program.run_io(&mut game).into_result()?;
+    game.draw_display();
 
     sleep(Duration::from_millis(500));
     println!("\x1b[?1049l");
+    if program.tracer.is_some() {
+        print!("{}", profile.lock().map_err(|_| "Profile was poisoned.")?);
+    }
     Ok(())
 }
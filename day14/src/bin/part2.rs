@@ -68,7 +68,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 let produced = ChemicalQuantity::parse(&line[(arrow + 4)..])?;
                 let requirements = line[..arrow]
                     .split(", ")
-                    .map(|s| ChemicalQuantity::parse(&s))
+                    .map(|s| ChemicalQuantity::parse(s))
                     .collect::<Result<Vec<_>, _>>()?;
                 map.insert(
                     produced.name.clone(),
@@ -127,7 +127,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 requirements,
             }) = map.get(&name)
             {
-                let multiplier = (needed_quantity + produced_quantity - 1) / produced_quantity;
+                let multiplier = needed_quantity.div_ceil(*produced_quantity);
                 needed.extend(requirements.iter().map(|cq| cq * multiplier));
 
                 let leftover = produced_quantity * multiplier - needed_quantity;
@@ -94,321 +94,15 @@
 //! What is the fewest number of movement commands required to move the repair droid from its
 //! starting position to the location of the oxygen system?
 
-use std::clone::Clone;
-use std::cmp::PartialEq;
-use std::convert::{From, TryFrom, TryInto};
+use intcode::{Intcode, Io, RunState};
+use std::collections::VecDeque;
 use std::error::Error;
-use std::fmt::{self, Display};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Write};
+use std::io::{self, BufReader, Write};
 use std::iter::successors;
-use std::str;
 use std::thread::sleep;
 use std::time::Duration;
 
-#[derive(Debug)]
-struct InvalidParameterMode(i64);
-
-impl Display for InvalidParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode.", self.0)
-    }
-}
-
-impl Error for InvalidParameterMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOpcode(i64);
-
-impl Display for InvalidOpcode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid opcode.", self.0)
-    }
-}
-
-impl Error for InvalidOpcode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOutputMode(ParameterMode);
-
-impl Display for InvalidOutputMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode for output.", self.0)
-    }
-}
-
-impl Error for InvalidOutputMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
-}
-
-impl Display for ParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ParameterMode::Position => "'position'",
-                ParameterMode::Immediate => "'immediate'",
-                ParameterMode::Relative => "'relative'",
-            }
-        )
-    }
-}
-
-impl TryFrom<i64> for ParameterMode {
-    type Error = InvalidParameterMode;
-
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code {
-            0 => Ok(ParameterMode::Position),
-            1 => Ok(ParameterMode::Immediate),
-            2 => Ok(ParameterMode::Relative),
-            _ => Err(InvalidParameterMode(code)),
-        }
-    }
-}
-
-impl ParameterMode {
-    fn modes2(code: i64) -> Result<(Self, Self), InvalidParameterMode> {
-        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
-    }
-
-    fn modes3(code: i64) -> Result<(Self, Self, Self), InvalidParameterMode> {
-        Ok((
-            (code % 10).try_into()?,
-            ((code / 10) % 10).try_into()?,
-            ((code / 100) % 10).try_into()?,
-        ))
-    }
-}
-
-enum Opcode {
-    Add(ParameterMode, ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode, ParameterMode),
-    Input(ParameterMode),
-    Output(ParameterMode),
-    JumpIfTrue(ParameterMode, ParameterMode),
-    JumpIfFalse(ParameterMode, ParameterMode),
-    LessThan(ParameterMode, ParameterMode, ParameterMode),
-    Equal(ParameterMode, ParameterMode, ParameterMode),
-    RelativeBaseOffset(ParameterMode),
-    Halt,
-}
-
-impl TryFrom<i64> for Opcode {
-    type Error = InvalidOpcode;
-
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code % 100 {
-            1 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Add(mode1, mode2, mode3))
-            }
-            2 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Multiply(mode1, mode2, mode3))
-            }
-            3 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Input(mode))
-            }
-            4 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Output(mode))
-            }
-            5 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfTrue(mode1, mode2))
-            }
-            6 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfFalse(mode1, mode2))
-            }
-            7 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::LessThan(mode1, mode2, mode3))
-            }
-            8 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Equal(mode1, mode2, mode3))
-            }
-            9 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::RelativeBaseOffset(mode))
-            }
-            99 => Ok(Opcode::Halt),
-            _ => Err(InvalidOpcode(code)),
-        }
-    }
-}
-
-#[derive(Clone)]
-struct Intcode {
-    code: Vec<i64>,
-    ip: usize,
-    relative_base: isize,
-}
-
-impl Intcode {
-    fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
-        let code = reader
-            .split(b',')
-            .map(|code| match code {
-                Ok(code) => {
-                    let s = str::from_utf8(&code)?;
-                    Ok(s.trim().parse()?)
-                }
-                Err(e) => Err(Box::new(e) as Box<dyn Error>),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Intcode {
-            code,
-            ip: 0,
-            relative_base: 0,
-        })
-    }
-
-    fn read(&mut self, i: usize) -> i64 {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
-        }
-        self.code[i]
-    }
-
-    fn write(&mut self, i: usize, v: i64) {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
-        }
-        self.code[i] = v;
-    }
-
-    fn output_operand(
-        &mut self,
-        sp: usize,
-        mode: ParameterMode,
-    ) -> Result<usize, InvalidOutputMode> {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => Ok(v as usize),
-            ParameterMode::Immediate => Err(InvalidOutputMode(mode)),
-            ParameterMode::Relative => Ok((v as isize + self.relative_base) as usize),
-        }
-    }
-
-    fn operand(&mut self, sp: usize, mode: ParameterMode) -> i64 {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => self.read(v as usize),
-            ParameterMode::Immediate => v,
-            ParameterMode::Relative => self.read((v as isize + self.relative_base) as usize),
-        }
-    }
-
-    fn operands2(&mut self, sp: usize, mode1: ParameterMode, mode2: ParameterMode) -> (i64, i64) {
-        (self.operand(sp, mode1), self.operand(sp + 1, mode2))
-    }
-
-    fn run(&mut self, input: &Vec<i64>) -> Result<(Vec<i64>, bool), Box<dyn Error>> {
-        let mut output: Vec<i64> = Vec::new();
-        let mut inputp = 0;
-        let mut halted = false;
-        loop {
-            let opcode: Opcode = self.read(self.ip).try_into()?;
-            self.ip = match opcode {
-                Opcode::Add(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 + op2);
-                    self.ip + 4
-                }
-                Opcode::Multiply(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 * op2);
-                    self.ip + 4
-                }
-                Opcode::Input(mode) => {
-                    if inputp >= input.len() {
-                        // not enough input; suspend
-                        break;
-                    }
-
-                    let op = self.output_operand(self.ip + 1, mode)?;
-                    self.write(op, input[inputp]);
-                    inputp += 1;
-                    self.ip + 2
-                }
-                Opcode::Output(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    output.push(op);
-                    self.ip + 2
-                }
-                Opcode::JumpIfTrue(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 != 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::JumpIfFalse(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 == 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::LessThan(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 < op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::Equal(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 == op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::RelativeBaseOffset(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    self.relative_base += op as isize;
-                    self.ip + 2
-                }
-                Opcode::Halt => {
-                    halted = true;
-                    break;
-                }
-            };
-        }
-        Ok((output, halted))
-    }
-}
-
 #[derive(Clone, Copy, PartialEq)]
 enum Tile {
     Wall,
@@ -542,23 +236,20 @@ impl Board {
             .map(|t| match t {
                 Tile::Clear(d) => *d + 1,
                 Tile::Oxygen(d) => *d + 1,
-                _ => std::usize::MAX,
+                _ => usize::MAX,
             })
             .min()
             .unwrap()
     }
 
     fn update_distances(&mut self, x: isize, y: isize, distance: usize) {
-        match self.get_tile_mut(x, y) {
-            Some(t) => {
-                if t.set_distance(distance) {
-                    self.update_distances(x, y - 1, distance + 1);
-                    self.update_distances(x, y + 1, distance + 1);
-                    self.update_distances(x - 1, y, distance + 1);
-                    self.update_distances(x + 1, y, distance + 1);
-                }
+        if let Some(t) = self.get_tile_mut(x, y) {
+            if t.set_distance(distance) {
+                self.update_distances(x, y - 1, distance + 1);
+                self.update_distances(x, y + 1, distance + 1);
+                self.update_distances(x - 1, y, distance + 1);
+                self.update_distances(x + 1, y, distance + 1);
             }
-            None => (),
         }
     }
 
@@ -684,7 +375,7 @@ enum History {
 }
 
 fn fill_next_move(
-    input: &mut Vec<i64>,
+    input: &mut VecDeque<i64>,
     board: &Board,
     history: &mut Vec<History>,
     x: &mut isize,
@@ -701,7 +392,7 @@ fn fill_next_move(
                 *x = *ax;
                 *y = *ay;
                 next_position(*x, *y, *m, nextx, nexty);
-                input.push(*m);
+                input.push_back(*m);
                 *hist = History::Move(*m);
                 return true;
             }
@@ -724,12 +415,9 @@ fn fill_next_move(
     // are there any unknown spots we can move to?
     let possible_moves: Vec<i64> = successors(Some(preferred_move), |&m| Some(turn_left(m)))
         .take(4)
-        .filter_map(|m| match surroundings[(m - 1) as usize] {
-            Tile::Unknown => Some(m),
-            _ => None,
-        })
+        .filter(|&m| surroundings[(m - 1) as usize] == Tile::Unknown)
         .collect();
-    if possible_moves.len() > 0 {
+    if !possible_moves.is_empty() {
         // track alternatives
         possible_moves[1..]
             .iter()
@@ -738,7 +426,7 @@ fn fill_next_move(
         // and try the first path
         next_position(*x, *y, possible_moves[0], nextx, nexty);
         history.push(History::Move(possible_moves[0]));
-        input.push(possible_moves[0]);
+        input.push_back(possible_moves[0]);
         return true;
     }
 
@@ -747,83 +435,117 @@ fn fill_next_move(
         match history.pop() {
             Some(History::Alternative(m, ax, ay)) => {
                 history.push(History::Move(m));
-                input.push(m);
+                input.push_back(m);
                 *x = ax;
                 *y = ay;
                 next_position(*x, *y, m, nextx, nexty);
                 return true;
             }
-            Some(History::Move(m)) => input.push(backtrack(m)),
+            Some(History::Move(m)) => input.push_back(backtrack(m)),
             None => return false,
         }
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let file = File::open("input.txt")?;
-    let reader = BufReader::new(file);
-    let mut program = Intcode::load(reader)?;
-    let mut board = Board::new();
-    let mut history: Vec<History> = Vec::new();
-    let mut input = Vec::new();
-    let (mut x, mut y) = (0, 0);
-    let (mut nextx, mut nexty) = (0, 0);
-    println!("\x1b[?1049h");
-    loop {
-        let (output, halt) = program.run(&input)?;
-        if halt {
-            println!("Program halted?");
-            break;
+struct Droid {
+    board: Board,
+    history: Vec<History>,
+    moves: VecDeque<i64>,
+    x: isize,
+    y: isize,
+    nextx: isize,
+    nexty: isize,
+}
+
+impl Droid {
+    fn new() -> Droid {
+        Droid {
+            board: Board::new(),
+            history: Vec::new(),
+            moves: VecDeque::new(),
+            x: 0,
+            y: 0,
+            nextx: 0,
+            nexty: 0,
         }
+    }
+}
 
-        if !output.is_empty() {
-            let outcome = Tile::from(output[output.len() - 1]);
-            if outcome == Tile::Wall {
-                // remove last move, it failed
-                history.pop();
-            } else {
-                x = nextx;
-                y = nexty;
+impl Io for Droid {
+    fn input(&mut self) -> Option<i64> {
+        if self.moves.is_empty() {
+            self.board.draw(self.x, self.y);
+            // for history in self.history.iter().rev().take(10) {
+            //     println!("{:?}", history);
+            // }
+
+            if !fill_next_move(
+                &mut self.moves,
+                &self.board,
+                &mut self.history,
+                &mut self.x,
+                &mut self.y,
+                &mut self.nextx,
+                &mut self.nexty,
+            ) {
+                // nothing left to explore
+                return None;
             }
-            board.update(nextx, nexty, outcome);
+            println!("xy: {},{}", self.x, self.y);
+            println!("next: {},{}", self.nextx, self.nexty);
+
+            sleep(Duration::from_millis(5));
+
+            // let mut stdout = io::stdout();
+            // let stdin = io::stdin();
+            // write!(stdout, "\nPress any key to continue...\n").unwrap();
+            // stdout.flush().unwrap();
+            // stdin.read_line(&mut String::new()).unwrap();
         }
-        board.draw(x, y);
-        // for history in history.iter().rev().take(10) {
-        //     println!("{:?}", history);
-        // }
-
-        if !fill_next_move(
-            &mut input,
-            &board,
-            &mut history,
-            &mut x,
-            &mut y,
-            &mut nextx,
-            &mut nexty,
-        ) {
-            break;
+        self.moves.pop_front()
+    }
+
+    fn output(&mut self, value: i64) {
+        // if we're still backtracking, we already know what's at this position; we only care
+        // about the outcome of the last move
+        if !self.moves.is_empty() {
+            return;
         }
-        println!("xy: {},{}", x, y);
-        println!("next: {},{}", nextx, nexty);
 
-        sleep(Duration::from_millis(5));
+        let outcome = Tile::from(value);
+        if outcome == Tile::Wall {
+            // remove last move, it failed
+            self.history.pop();
+        } else {
+            self.x = self.nextx;
+            self.y = self.nexty;
+        }
+        self.board.update(self.nextx, self.nexty, outcome);
+    }
+}
 
-        // let mut stdout = io::stdout();
-        // let mut stdin = io::stdin();
-        // write!(stdout, "\nPress any key to continue...\n")?;
-        // stdout.flush()?;
-        // stdin.read(&mut [0u8])?;
+fn main() -> Result<(), Box<dyn Error>> {
+    let file = File::open("input.txt")?;
+    let reader = BufReader::new(file);
+    let mut program = Intcode::load(reader)?;
+    let mut droid = Droid::new();
+    println!("\x1b[?1049h");
+    match program.run_io(&mut droid) {
+        // the droid stops asking for input once there's nothing left to explore
+        RunState::NeedsInput { .. } => (),
+        RunState::Fault(fault) => return Err(fault.into()),
+        state => println!("Program stopped early: {}", state),
     }
 
     let mut stdout = io::stdout();
-    let mut stdin = io::stdin();
+    let stdin = io::stdin();
     write!(stdout, "\nPress any key to continue...\n")?;
     stdout.flush()?;
-    stdin.read(&mut [0u8])?;
+    stdin.read_line(&mut String::new())?;
     println!("\x1b[?1049l");
 
-    let (oxygen_x, oxygen_y) = board.oxygen;
-    if let Tile::Oxygen(distance) = board.get_tile(oxygen_x, oxygen_y) {
+    let (oxygen_x, oxygen_y) = droid.board.oxygen;
+    if let Tile::Oxygen(distance) = droid.board.get_tile(oxygen_x, oxygen_y) {
         println!("Moves: {}", distance);
     }
 
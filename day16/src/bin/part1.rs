@@ -92,7 +92,7 @@
 //! After 100 phases of FFT, what are the first eight digits in the final output list?
 
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufReader, Read};
 
 struct Repeater<I: Iterator> {
     iter: I,
@@ -131,14 +131,14 @@ where
     fn size_hint(&self) -> (usize, Option<usize>) {
         let (lower, upper) = self.iter.size_hint();
         (
-            1 + lower.saturating_sub(1) * (self.repeats as usize),
-            upper.map(|u| u * (self.repeats as usize)),
+            1 + lower.saturating_sub(1) * self.repeats,
+            upper.map(|u| u * self.repeats),
         )
     }
 }
 
 fn main() -> io::Result<()> {
-    let file = File::open("input.txt")?;
+    let file = BufReader::new(File::open("input.txt")?);
     let mut input = file
         .bytes()
         .filter_map(|n| match n {
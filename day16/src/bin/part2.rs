@@ -26,7 +26,7 @@
 //! eight-digit message embedded in the final output list?
 
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufReader, Read};
 
 const REPEAT_INPUT: usize = 10_000;
 
@@ -34,7 +34,7 @@ fn main() -> io::Result<()> {
     // We don't even need to consider the first `offset` elements because we're only interested in
     // the result after the offset. Since the base pattern starts with a 0, those numbers will
     // never be considered in the computation.
-    let file = File::open("input.txt")?;
+    let file = BufReader::new(File::open("input.txt")?);
     let mut input = file
         .bytes()
         .filter_map(|n| match n {
@@ -104,320 +104,78 @@
 //! After visiting every part of the scaffold at least once, how much dust does the vacuum robot
 //! report it has collected?
 
-use std::clone::Clone;
-use std::cmp::PartialEq;
-use std::convert::{From, TryFrom, TryInto};
+use intcode::{ascii, AsciiMachine, Intcode, Io};
+use std::collections::{HashSet, VecDeque};
+use std::env;
 use std::error::Error;
-use std::fmt::{self, Display, Write};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::str;
-
-#[derive(Debug)]
-struct InvalidParameterMode(i64);
-
-impl Display for InvalidParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode.", self.0)
-    }
-}
-
-impl Error for InvalidParameterMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOpcode(i64);
-
-impl Display for InvalidOpcode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid opcode.", self.0)
-    }
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// The most characters the robot can remember for the main routine or a movement function.
+const MEMORY: usize = 20;
+const FUNCTION_NAMES: [&str; 3] = ["A", "B", "C"];
+const FRAME_DELAY: Duration = Duration::from_millis(10);
+const USAGE: &str = "Usage: day17-part2 [--video] [--record <file>] [--replay <file>]";
+
+struct Options {
+    /// Watch the robot on the continuous video feed.
+    video: bool,
+
+    /// Write each frame of the video feed to a file.
+    record: Option<String>,
+
+    /// Play back a recording instead of running the robot.
+    replay: Option<String>,
 }
 
-impl Error for InvalidOpcode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug)]
-struct InvalidOutputMode(ParameterMode);
-
-impl Display for InvalidOutputMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode for output.", self.0)
-    }
-}
-
-impl Error for InvalidOutputMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
-
-#[derive(Debug, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
-}
-
-impl Display for ParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ParameterMode::Position => "'position'",
-                ParameterMode::Immediate => "'immediate'",
-                ParameterMode::Relative => "'relative'",
-            }
-        )
-    }
-}
-
-impl TryFrom<i64> for ParameterMode {
-    type Error = InvalidParameterMode;
-
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code {
-            0 => Ok(ParameterMode::Position),
-            1 => Ok(ParameterMode::Immediate),
-            2 => Ok(ParameterMode::Relative),
-            _ => Err(InvalidParameterMode(code)),
+fn parse_options() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        video: false,
+        record: None,
+        replay: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--video" => options.video = true,
+            "--record" => options.record = Some(args.next().ok_or(USAGE)?),
+            "--replay" => options.replay = Some(args.next().ok_or(USAGE)?),
+            _ => return Err(USAGE.into()),
         }
     }
+    Ok(options)
 }
 
-impl ParameterMode {
-    fn modes2(code: i64) -> Result<(Self, Self), InvalidParameterMode> {
-        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
-    }
-
-    fn modes3(code: i64) -> Result<(Self, Self, Self), InvalidParameterMode> {
-        Ok((
-            (code % 10).try_into()?,
-            ((code / 10) % 10).try_into()?,
-            ((code / 100) % 10).try_into()?,
-        ))
-    }
-}
-
-enum Opcode {
-    Add(ParameterMode, ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode, ParameterMode),
-    Input(ParameterMode),
-    Output(ParameterMode),
-    JumpIfTrue(ParameterMode, ParameterMode),
-    JumpIfFalse(ParameterMode, ParameterMode),
-    LessThan(ParameterMode, ParameterMode, ParameterMode),
-    Equal(ParameterMode, ParameterMode, ParameterMode),
-    RelativeBaseOffset(ParameterMode),
-    Halt,
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    Left,
+    Right,
+    Forward(usize),
 }
 
-impl TryFrom<i64> for Opcode {
-    type Error = InvalidOpcode;
-
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code % 100 {
-            1 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Add(mode1, mode2, mode3))
-            }
-            2 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Multiply(mode1, mode2, mode3))
-            }
-            3 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Input(mode))
-            }
-            4 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Output(mode))
-            }
-            5 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfTrue(mode1, mode2))
-            }
-            6 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfFalse(mode1, mode2))
-            }
-            7 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::LessThan(mode1, mode2, mode3))
-            }
-            8 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Equal(mode1, mode2, mode3))
-            }
-            9 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::RelativeBaseOffset(mode))
-            }
-            99 => Ok(Opcode::Halt),
-            _ => Err(InvalidOpcode(code)),
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Left => write!(f, "L"),
+            Command::Right => write!(f, "R"),
+            Command::Forward(n) => write!(f, "{}", n),
         }
     }
 }
 
-#[derive(Clone)]
-struct Intcode {
-    code: Vec<i64>,
-    ip: usize,
-    relative_base: isize,
-}
-
-impl Intcode {
-    fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
-        let code = reader
-            .split(b',')
-            .map(|code| match code {
-                Ok(code) => {
-                    let s = str::from_utf8(&code)?;
-                    Ok(s.trim().parse()?)
-                }
-                Err(e) => Err(Box::new(e) as Box<dyn Error>),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Intcode {
-            code,
-            ip: 0,
-            relative_base: 0,
-        })
-    }
-
-    fn read(&mut self, i: usize) -> i64 {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
-        }
-        self.code[i]
-    }
-
-    fn write(&mut self, i: usize, v: i64) {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
-        }
-        self.code[i] = v;
-    }
-
-    fn output_operand(
-        &mut self,
-        sp: usize,
-        mode: ParameterMode,
-    ) -> Result<usize, InvalidOutputMode> {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => Ok(v as usize),
-            ParameterMode::Immediate => Err(InvalidOutputMode(mode)),
-            ParameterMode::Relative => Ok((v as isize + self.relative_base) as usize),
-        }
-    }
-
-    fn operand(&mut self, sp: usize, mode: ParameterMode) -> i64 {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => self.read(v as usize),
-            ParameterMode::Immediate => v,
-            ParameterMode::Relative => self.read((v as isize + self.relative_base) as usize),
-        }
-    }
-
-    fn operands2(&mut self, sp: usize, mode1: ParameterMode, mode2: ParameterMode) -> (i64, i64) {
-        (self.operand(sp, mode1), self.operand(sp + 1, mode2))
-    }
-
-    fn run(&mut self, input: &Vec<i64>) -> Result<(Vec<i64>, bool), Box<dyn Error>> {
-        let mut output: Vec<i64> = Vec::new();
-        let mut inputp = 0;
-        let mut halted = false;
-        loop {
-            let opcode: Opcode = self.read(self.ip).try_into()?;
-            self.ip = match opcode {
-                Opcode::Add(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 + op2);
-                    self.ip + 4
-                }
-                Opcode::Multiply(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 * op2);
-                    self.ip + 4
-                }
-                Opcode::Input(mode) => {
-                    if inputp >= input.len() {
-                        // not enough input; suspend
-                        break;
-                    }
-
-                    let op = self.output_operand(self.ip + 1, mode)?;
-                    self.write(op, input[inputp]);
-                    inputp += 1;
-                    self.ip + 2
-                }
-                Opcode::Output(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    output.push(op);
-                    self.ip + 2
-                }
-                Opcode::JumpIfTrue(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 != 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::JumpIfFalse(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 == 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::LessThan(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 < op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::Equal(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 == op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::RelativeBaseOffset(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    self.relative_base += op as isize;
-                    self.ip + 2
-                }
-                Opcode::Halt => {
-                    halted = true;
-                    break;
-                }
-            };
-        }
-        Ok((output, halted))
-    }
+fn join(commands: &[Command]) -> String {
+    commands
+        .iter()
+        .map(Command::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 fn left(
-    map: &Vec<Vec<char>>,
+    map: &[Vec<char>],
     heading: char,
     x: usize,
     y: usize,
@@ -458,7 +216,7 @@ fn left(
 }
 
 fn right(
-    map: &Vec<Vec<char>>,
+    map: &[Vec<char>],
     heading: char,
     x: usize,
     y: usize,
@@ -499,7 +257,7 @@ fn right(
 }
 
 fn forward(
-    map: &Vec<Vec<char>>,
+    map: &[Vec<char>],
     heading: char,
     x: usize,
     y: usize,
@@ -539,138 +297,405 @@ fn forward(
     }
 }
 
+fn reverse(heading: char) -> char {
+    match heading {
+        '^' => 'v',
+        '>' => '<',
+        'v' => '^',
+        '<' => '>',
+        _ => unreachable!(),
+    }
+}
+
+/// Walks every route that covers the whole scaffold without using any stretch of it twice.
+struct Walk {
+    map: Vec<Vec<char>>,
+    width: usize,
+    height: usize,
+    visits: Vec<Vec<usize>>,
+    unvisited: usize,
+    used: HashSet<((usize, usize), (usize, usize))>,
+
+    /// 'L' and 'R' turn, 'F' moves forward one unit.
+    steps: Vec<char>,
+}
+
+impl Walk {
+    fn new(map: &[Vec<char>]) -> Walk {
+        // the robot is standing on scaffold, too
+        let map: Vec<Vec<char>> = map
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&c| if "^>v<".contains(c) { '#' } else { c })
+                    .collect()
+            })
+            .collect();
+        let unvisited = map.iter().flatten().filter(|&&c| c == '#').count();
+        Walk {
+            width: map[0].len(),
+            height: map.len(),
+            visits: vec![vec![0; map[0].len()]; map.len()],
+            unvisited,
+            used: HashSet::new(),
+            steps: vec![],
+            map,
+        }
+    }
+
+    fn visit(&mut self, x: usize, y: usize) {
+        self.visits[y][x] += 1;
+        if self.visits[y][x] == 1 {
+            self.unvisited -= 1;
+        }
+    }
+
+    fn unvisit(&mut self, x: usize, y: usize) {
+        self.visits[y][x] -= 1;
+        if self.visits[y][x] == 0 {
+            self.unvisited += 1;
+        }
+    }
+
+    fn route(&self) -> Vec<Command> {
+        let mut route = vec![];
+        for &step in &self.steps {
+            match (step, route.last_mut()) {
+                ('F', Some(Command::Forward(n))) => *n += 1,
+                ('F', _) => route.push(Command::Forward(1)),
+                ('L', _) => route.push(Command::Left),
+                _ => route.push(Command::Right),
+            }
+        }
+        route
+    }
+
+    /// Calls `found` with each route from x, y, trying to go straight through intersections
+    /// before turning at them, until `found` returns true.
+    fn explore(
+        &mut self,
+        x: usize,
+        y: usize,
+        heading: char,
+        found: &mut dyn FnMut(&[Command]) -> bool,
+    ) -> bool {
+        if self.steps.is_empty() {
+            self.visit(x, y);
+        }
+        if self.unvisited == 0 {
+            return found(&self.route());
+        }
+
+        let (map, width, height) = (&self.map, self.width, self.height);
+        let mut moves = vec![
+            (
+                "F",
+                forward(map, heading, x, y, width, height).map(|(x, y)| (x, y, heading)),
+            ),
+            ("LF", left(map, heading, x, y, width, height)),
+            ("RF", right(map, heading, x, y, width, height)),
+        ];
+        if self.steps.is_empty() {
+            // the robot might be facing the wrong way to begin with
+            let back = reverse(heading);
+            let behind = forward(map, back, x, y, width, height).map(|(x, y)| (x, y, back));
+            moves.push(("LLF", behind));
+        }
+
+        for (steps, next) in moves {
+            let (nextx, nexty, nextheading) = match next {
+                Some(next) => next,
+                None => continue,
+            };
+            let edge = ((x, y).min((nextx, nexty)), (x, y).max((nextx, nexty)));
+            if !self.used.insert(edge) {
+                continue;
+            }
+            let len = self.steps.len();
+            self.steps.extend(steps.chars());
+            self.visit(nextx, nexty);
+            if self.explore(nextx, nexty, nextheading, found) {
+                return true;
+            }
+            self.unvisit(nextx, nexty);
+            self.steps.truncate(len);
+            self.used.remove(&edge);
+        }
+        false
+    }
+}
+
+/// A main routine, as indexes into `functions`.
+struct Movement {
+    main: Vec<usize>,
+    functions: Vec<Vec<Command>>,
+}
+
+/// Splits a route into a main routine and up to three movement functions that each fit in the
+/// robot's memory.
+fn compress(route: &[Command]) -> Option<Movement> {
+    let mut functions = vec![];
+    let mut main = vec![];
+    if split(route, &mut functions, &mut main) {
+        Some(Movement {
+            main,
+            functions: functions.iter().map(|f| f.to_vec()).collect(),
+        })
+    } else {
+        None
+    }
+}
+
+fn split<'a>(
+    route: &'a [Command],
+    functions: &mut Vec<&'a [Command]>,
+    main: &mut Vec<usize>,
+) -> bool {
+    if route.is_empty() {
+        return true;
+    }
+
+    // one more call would add a comma and a letter to the main routine
+    if main.len() * 2 + 1 > MEMORY {
+        return false;
+    }
+
+    for i in 0..functions.len() {
+        let function = functions[i];
+        if route.starts_with(function) {
+            main.push(i);
+            if split(&route[function.len()..], functions, main) {
+                return true;
+            }
+            main.pop();
+        }
+    }
+
+    // otherwise, the next function starts here - longer functions are more likely to work
+    if functions.len() < FUNCTION_NAMES.len() {
+        let longest = (1..=route.len())
+            .take_while(|&len| join(&route[..len]).len() <= MEMORY)
+            .last()
+            .unwrap_or(0);
+        for len in (1..=longest).rev() {
+            functions.push(&route[..len]);
+            main.push(functions.len() - 1);
+            if split(&route[len..], functions, main) {
+                return true;
+            }
+            main.pop();
+            functions.pop();
+        }
+    }
+    false
+}
+
+/// Camera frames are separated by a blank line, but so are the robot's prompts, so a frame is
+/// anything that only contains scaffold, space, and the robot.
+fn is_frame(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| "\n.#^>v<X".contains(c))
+}
+
+fn draw_frame(frame: &str) {
+    println!("\x1b[H");
+    println!("{}", frame);
+    sleep(FRAME_DELAY);
+}
+
+/// Feeds the robot its movement rules, and watches its video feed.
+struct VideoFeed {
+    input: VecDeque<i64>,
+
+    /// The text since the last blank line.
+    text: String,
+    frames: usize,
+    draw: bool,
+    record: Option<BufWriter<File>>,
+
+    /// The first error writing the recording; the rest of the recording is dropped.
+    error: Option<io::Error>,
+    dust: Option<i64>,
+}
+
+impl VideoFeed {
+    fn new(lines: &[String], draw: bool, record: Option<BufWriter<File>>) -> VideoFeed {
+        let mut input = VecDeque::new();
+        for line in lines {
+            input.extend(ascii(line));
+            input.push_back(10);
+        }
+        VideoFeed {
+            input,
+            text: String::new(),
+            frames: 0,
+            draw,
+            record,
+            error: None,
+            dust: None,
+        }
+    }
+
+    fn frame(&mut self) {
+        let frame = self.text.trim_end();
+        if !is_frame(frame) {
+            return;
+        }
+        self.frames += 1;
+        if self.draw {
+            draw_frame(frame);
+        }
+        if let Some(record) = &mut self.record {
+            if let Err(e) = write!(record, "{}\n\n", frame) {
+                self.error = Some(e);
+                self.record = None;
+            }
+        }
+    }
+}
+
+impl Io for VideoFeed {
+    fn input(&mut self) -> Option<i64> {
+        self.input.pop_front()
+    }
+
+    // the dust is the only output that isn't ASCII
+    fn output(&mut self, value: i64) {
+        if !(0..128).contains(&value) {
+            self.dust = Some(value);
+            return;
+        }
+        self.text.push(char::from(value as u8));
+        if self.text.ends_with("\n\n") {
+            self.frame();
+            self.text.clear();
+        }
+    }
+}
+
+/// Plays back a recording made with `--record`.
+fn replay(path: &str) -> Result<(), Box<dyn Error>> {
+    let recording = fs::read_to_string(path)?;
+    println!("\x1b[?1049h");
+    for frame in recording.split("\n\n").filter(|frame| is_frame(frame)) {
+        draw_frame(frame);
+    }
+    sleep(Duration::from_millis(500));
+    println!("\x1b[?1049l");
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_options()?;
+    if let Some(path) = &options.replay {
+        return replay(path);
+    }
+
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
-    let mut observer = Intcode::load(reader)?;
-    let mut program = observer.clone();
+    let program = Intcode::load(reader)?;
+    let mut observer = AsciiMachine::new(program.clone());
 
     // first, we'll build the map so we can determine the route to get from start to finish
-    let (map, _) = observer.run(&vec![])?;
-    let (mut map, robotidx, mut heading) =
-        map.iter().map(|&i| char::from(i as u8)).enumerate().fold(
-            (vec![vec![]], 0, '^'),
-            |(mut acc, robotidx, heading), (idx, c)| match c {
-                '\n' => {
-                    acc.push(vec![]);
+    let map = observer.read_until_prompt()?.text;
+    let (mut map, robotidx, heading) = map.chars().enumerate().fold(
+        (vec![vec![]], 0, '^'),
+        |(mut acc, robotidx, heading), (idx, c)| match c {
+            '\n' => {
+                acc.push(vec![]);
+                (acc, robotidx, heading)
+            }
+            c => {
+                let accidx = acc.len() - 1;
+                acc[accidx].push(c);
+                if robotidx == 0 && (c == '^' || c == '>' || c == 'v' || c == '<') {
+                    (acc, idx, c)
+                } else {
                     (acc, robotidx, heading)
                 }
-                c => {
-                    let accidx = acc.len() - 1;
-                    acc[accidx].push(c);
-                    if robotidx == 0 && (c == '^' || c == '>' || c == 'v' || c == '<') {
-                        (acc, idx, c)
-                    } else {
-                        (acc, robotidx, heading)
-                    }
-                }
-            },
-        );
+            }
+        },
+    );
     for i in (0..map.len()).rev() {
-        if map[i].len() > 0 {
+        if !map[i].is_empty() {
             map.truncate(i + 1);
             break;
         }
     }
 
-    // plan our route - width + 1 in robotx/y calcs to account for newline chars
-    let height = map.len();
+    // width + 1 in robotx/y calcs to account for newline chars
     let width = map[0].len();
-    let mut moved = 0;
-    let mut route = String::new();
-    let (mut robotx, mut roboty) = (robotidx % (width + 1), robotidx / (width + 1));
+    let (robotx, roboty) = (robotidx % (width + 1), robotidx / (width + 1));
     println!(
         "Starting position: idx {} = {},{}; heading = {}",
         robotidx, robotx, roboty, heading
     );
-    loop {
-        if let Some((x, y)) = forward(&map, heading, robotx, roboty, width, height) {
-            robotx = x;
-            roboty = y;
-        } else if let Some((x, y, newheading)) = left(&map, heading, robotx, roboty, width, height)
-        {
-            robotx = x;
-            roboty = y;
-            heading = newheading;
-            if moved > 0 {
-                write!(route, "{},L,", moved)?;
-                moved = 0;
-            } else {
-                write!(route, "L,")?;
-            }
-        } else if let Some((x, y, newheading)) = right(&map, heading, robotx, roboty, width, height)
-        {
-            robotx = x;
-            roboty = y;
-            heading = newheading;
-            if moved > 0 {
-                write!(route, "{},R,", moved)?;
-                moved = 0;
-            } else {
-                write!(route, "R,")?;
-            }
-        } else {
-            // must have reached the end
-            break;
+
+    // walk the scaffold, going straight through intersections first, until we find a route that
+    // fits in the robot's memory
+    let mut walk = Walk::new(&map);
+    let mut tried = 0;
+    let mut movement = None;
+    walk.explore(robotx, roboty, heading, &mut |route| {
+        tried += 1;
+        if tried == 1 {
+            println!("Route: {}", join(route));
+        }
+        movement = compress(route);
+        if movement.is_some() && tried > 1 {
+            println!("Route {}: {}", tried, join(route));
         }
-        moved += 1;
+        movement.is_some()
+    });
+    let movement = movement.ok_or("No route around the scaffold fits in the robot's memory.")?;
+
+    let main = movement
+        .main
+        .iter()
+        .map(|&f| FUNCTION_NAMES[f].to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    println!("Main: {}", main);
+    let mut lines = vec![main];
+    for (name, function) in FUNCTION_NAMES.iter().zip(movement.functions.iter()) {
+        println!("{}: {}", name, join(function));
+        lines.push(join(function));
     }
-    if moved > 0 {
-        write!(route, "{}", moved)?;
-    } else {
-        route.pop();
+
+    // the robot asks for all three functions, even if we don't call them all
+    while lines.len() < FUNCTION_NAMES.len() + 1 {
+        lines.push(lines[1].clone());
     }
-    println!("Route: {}", route);
-
-    // now find the longest repeating, non-overlapping substring
-    let broute = route.as_bytes();
-    let mut lcsre = vec![vec![0; broute.len() + 1]; broute.len() + 1];
-    let mut longest_len = 0;
-    let mut longest_idx = 0;
-    for i in 1..(broute.len() + 1) {
-        for j in (i + 1)..(broute.len() + 1) {
-            if broute[i - 1] == broute[j - 1] && lcsre[i - 1][j - 1] < (j - i) {
-                lcsre[i][j] = lcsre[i - 1][j - 1] + 1;
-                if lcsre[i][j] > longest_len {
-                    longest_len = lcsre[i][j];
-                    longest_idx = longest_idx.max(i);
-                }
-            }
-        }
+    // the video feed is a frame after every move, which is plenty to draw, so only ask for it if
+    // someone is going to look at it
+    let continuous = options.video || options.record.is_some();
+    lines.push(if continuous { "y" } else { "n" }.to_string());
+    let record = match &options.record {
+        Some(path) => Some(BufWriter::new(File::create(path)?)),
+        None => None,
+    };
+    let mut feed = VideoFeed::new(&lines, options.video, record);
+    let mut robot = program;
+    robot.code[0] = 2;
+    if options.video {
+        println!("\x1b[?1049h");
+    }
+    let state = robot.run_io(&mut feed);
+    if options.video {
+        sleep(Duration::from_millis(500));
+        println!("\x1b[?1049l");
+    }
+    state.into_result()?;
+    if let Some(e) = feed.error {
+        return Err(e.into());
+    }
+    if let Some(mut record) = feed.record {
+        record.flush()?;
+        println!("Recorded {} frames", feed.frames);
     }
     println!(
-        "Longest Substr {}: {}",
-        longest_len,
-        route[(longest_idx - longest_len)..longest_idx].to_owned()
+        "Dust: {}",
+        feed.dust.ok_or("The robot didn't report any dust.")?
     );
 
-    // From here, I'm working manually because I didn't feel like figuring out how to automate
-    // this. The route produced above is:
-    //   R,6,R,6,R,8,L,10,L,4,R,6,L,10,R,8,R,6,L,10,R,8,R,6,R,6,R,8,L,10,L,4,L,4,L,12,R,6,L,10,R,6,
-    //   R,6,R,8,L,10,L,4,L,4,L,12,R,6,L,10,R,6,R,6,R,8,L,10,L,4,L,4,L,12,R,6,L,10,R,6,L,10,R,8
-    //
-    // And the longest substring is 44 characters:
-    //   ,R,6,R,6,R,8,L,10,L,4,L,4,L,12,R,6,L,10,R,6,
-    let main = "A,B,B,A,C,A,C,A,C,B\n";
-    let func_a = "R,6,R,6,R,8,L,10,L,4\n";
-    let func_b = "R,6,L,10,R,8\n";
-    let func_c = "L,4,L,12,R,6,L,10\n";
-    let continuous = "n\n";
-    let input: Vec<i64> = main
-        .bytes()
-        .chain(func_a.bytes())
-        .chain(func_b.bytes())
-        .chain(func_c.bytes())
-        .chain(continuous.bytes())
-        .map(|c| c as i64)
-        .collect();
-    program.code[0] = 2;
-
-    // apparently the outut is the starting map, a print-out of main and the functions, then the
-    // ending map, a blank line, and finally the number of dust... so, what we really care about is
-    // the last item in output.
-    let (output, _) = program.run(&input)?;
-    println!("Dust: {}", output[output.len() - 1]);
-
     Ok(())
 }
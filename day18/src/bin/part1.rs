@@ -167,9 +167,10 @@ fn build_graph(map: Vec<Vec<u8>>) -> HashMap<u8, Node> {
     // This function does a bfs to find the connections between a given key to all other keys. It
     // assumes there is only one way of getting between each pair so it doesn't bother making sure
     // each path is the "shortest".
+    #[allow(clippy::too_many_arguments)]
     fn find_keys(
-        map: &Vec<Vec<u8>>,
-        visited: &mut Vec<Vec<u8>>,
+        map: &[Vec<u8>],
+        visited: &mut [Vec<u8>],
         all_keys: &HashSet<u8>,
         from_key: u8,
         keys: &mut HashMap<u8, Edge>,
@@ -185,9 +186,9 @@ fn build_graph(map: Vec<Vec<u8>>) -> HashMap<u8, Node> {
             visited[y][x] = from_key;
 
             match map[y][x] {
-                key if key != from_key && KEYS.contains(&key) => {
+                key if key != from_key && KEYS.contains(&key)
                     // if we require *this* key to reach *this* key, that clearly can't happen
-                    if !required_keys.contains(&key) {
+                    && !required_keys.contains(&key) => {
                         keys.insert(
                             key,
                             Edge {
@@ -196,7 +197,6 @@ fn build_graph(map: Vec<Vec<u8>>) -> HashMap<u8, Node> {
                             },
                         );
                     }
-                }
                 door if DOORS.contains(&door) => {
                     let mut required_keys_set = required_keys.as_ref().clone();
                     required_keys_set.insert(door - b'A' + b'a');
@@ -316,7 +316,7 @@ fn find_shortest_path(graph: HashMap<u8, Node>) -> usize {
         }
 
         let mut collected_keys = collected_keys.clone();
-        collected_keys.insert(current.clone());
+        collected_keys.insert(current);
 
         let node = graph.get(&current).unwrap();
         let reachable_neighbors: Vec<(&u8, &Edge)> = node
@@ -327,10 +327,10 @@ fn find_shortest_path(graph: HashMap<u8, Node>) -> usize {
             })
             .collect();
 
-        let mut shortest_distance = std::usize::MAX;
+        let mut shortest_distance = usize::MAX;
         for (key, edge) in reachable_neighbors {
             // dfs may return MAX if we reach a node from which we cannot complete the maze
-            let distance = dfs(cache, graph, *key, all_keys, &mut collected_keys)
+            let distance = dfs(cache, graph, *key, all_keys, &collected_keys)
                 .saturating_add(edge.distance);
             if distance < shortest_distance {
                 shortest_distance = distance;
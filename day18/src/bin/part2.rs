@@ -213,9 +213,9 @@ impl fmt::Display for Graph {
         let mut collected_keys: Vec<char> =
             self.collected_keys.iter().map(|&c| char::from(c)).collect();
         collected_keys.sort();
-        write!(
+        writeln!(
             f,
-            "Keys not in this graph: {}\n",
+            "Keys not in this graph: {}",
             collected_keys.iter().collect::<String>()
         )?;
 
@@ -223,7 +223,7 @@ impl fmt::Display for Graph {
         keys.sort();
         for key in keys {
             let node = self.nodes.get(&key).unwrap();
-            write!(f, "{}: {}\n", char::from(key), node)?;
+            writeln!(f, "{}: {}", char::from(key), node)?;
         }
 
         Ok(())
@@ -234,9 +234,10 @@ fn build_graphs(mut map: Vec<Vec<u8>>) -> Vec<Graph> {
     // This function does a bfs to find the connections between a given key to all other keys. It
     // assumes there is only one way of getting between each pair so it doesn't bother making sure
     // each path is the "shortest".
+    #[allow(clippy::too_many_arguments)]
     fn find_keys(
-        map: &Vec<Vec<u8>>,
-        visited: &mut Vec<Vec<u8>>,
+        map: &[Vec<u8>],
+        visited: &mut [Vec<u8>],
         from_key: u8,
         keys: &mut HashMap<u8, Edge>,
         validx: &Range<usize>,
@@ -251,9 +252,9 @@ fn build_graphs(mut map: Vec<Vec<u8>>) -> Vec<Graph> {
             visited[y][x] = from_key;
 
             match map[y][x] {
-                key if key != from_key && KEYS.contains(&key) => {
+                key if key != from_key && KEYS.contains(&key)
                     // if we require *this* key to reach *this* key, that clearly can't happen
-                    if !required_keys.contains(&key) {
+                    && !required_keys.contains(&key) => {
                         keys.insert(
                             key,
                             Edge {
@@ -262,7 +263,6 @@ fn build_graphs(mut map: Vec<Vec<u8>>) -> Vec<Graph> {
                             },
                         );
                     }
-                }
                 door if DOORS.contains(&door) => {
                     let mut required_keys_set = required_keys.as_ref().clone();
                     required_keys_set.insert(door - b'A' + b'a');
@@ -290,13 +290,13 @@ fn build_graphs(mut map: Vec<Vec<u8>>) -> Vec<Graph> {
     let (mut middlex, mut middley) = (0, 0);
     let mut all_keys = HashSet::new();
     let mut positions = Vec::new();
-    for y in 1..(height - 1) {
-        for x in 1..(width - 1) {
-            if map[y][x] == b'@' {
+    for (y, row) in map.iter().enumerate().take(height - 1).skip(1) {
+        for (x, &tile) in row.iter().enumerate().take(width - 1).skip(1) {
+            if tile == b'@' {
                 middlex = x;
                 middley = y;
-            } else if KEYS.contains(&map[y][x]) {
-                all_keys.insert(map[y][x]);
+            } else if KEYS.contains(&tile) {
+                all_keys.insert(tile);
                 positions.push((x, y));
             }
         }
@@ -356,8 +356,8 @@ fn build_graphs(mut map: Vec<Vec<u8>>) -> Vec<Graph> {
                     &mut visited,
                     map[*y][*x],
                     &mut keys,
-                    &validx,
-                    &validy,
+                    validx,
+                    validy,
                     *x,
                     *y,
                 );
@@ -412,7 +412,7 @@ fn find_shortest_path(graph: &HashMap<u8, Node>, collected_keys: &HashSet<u8>) -
         }
 
         let mut collected_keys = collected_keys.clone();
-        collected_keys.insert(current.clone());
+        collected_keys.insert(current);
 
         if all_keys.is_subset(&collected_keys) {
             // we're at the last key so we no longer need to move to finish
@@ -429,10 +429,10 @@ fn find_shortest_path(graph: &HashMap<u8, Node>, collected_keys: &HashSet<u8>) -
             })
             .collect();
 
-        let mut shortest_distance = std::usize::MAX;
+        let mut shortest_distance = usize::MAX;
         for (key, edge) in reachable_neighbors {
             // dfs may return MAX if we reach a node from which we cannot complete the maze
-            let distance = dfs(cache, graph, *key, all_keys, &mut collected_keys)
+            let distance = dfs(cache, graph, *key, all_keys, &collected_keys)
                 .saturating_add(edge.distance);
             if distance < shortest_distance {
                 shortest_distance = distance;
@@ -444,7 +444,7 @@ fn find_shortest_path(graph: &HashMap<u8, Node>, collected_keys: &HashSet<u8>) -
     }
 
     let all_keys: HashSet<u8> = graph.keys().copied().collect();
-    dfs(&mut HashMap::new(), &graph, b'@', &all_keys, collected_keys)
+    dfs(&mut HashMap::new(), graph, b'@', &all_keys, collected_keys)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
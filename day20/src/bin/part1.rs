@@ -113,8 +113,11 @@ impl fmt::Display for Node {
     }
 }
 
-fn find_labels(map: &Vec<Vec<u8>>) -> Result<HashMap<String, Vec<(usize, usize)>>, Box<dyn Error>> {
-    let mut label_map: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+/// The positions next to each label's portals.
+type Labels = HashMap<String, Vec<(usize, usize)>>;
+
+fn find_labels(map: &[Vec<u8>]) -> Result<Labels, Box<dyn Error>> {
+    let mut label_map: Labels = HashMap::new();
     let (width, height) = (map[0].len(), map.len());
     println!("Finding labels...");
     for y in 0..height {
@@ -152,14 +155,15 @@ fn find_labels(map: &Vec<Vec<u8>>) -> Result<HashMap<String, Vec<(usize, usize)>
 }
 
 fn find_connections(
-    map: &Vec<Vec<u8>>,
-    labels: &HashMap<String, Vec<(usize, usize)>>,
+    map: &[Vec<u8>],
+    labels: &Labels,
 ) -> HashMap<String, Node> {
+    #[allow(clippy::too_many_arguments)]
     fn bfs(
         from_label: &String,
         edges: &mut HashMap<String, usize>,
-        map: &Vec<Vec<u8>>,
-        visited: &mut Vec<Vec<bool>>,
+        map: &[Vec<u8>],
+        visited: &mut [Vec<bool>],
         label_positions: &HashMap<(usize, usize), String>,
         width: usize,
         height: usize,
@@ -180,7 +184,7 @@ fn find_connections(
                     distance + 1
                 };
                 let old = edges.insert(label.clone(), distance);
-                if !old.is_none() {
+                if old.is_some() {
                     panic!("Label {} can reach both portals for {}", from_label, label);
                 }
             }
@@ -215,7 +219,7 @@ fn find_connections(
     let (width, height) = (map[0].len(), map.len());
     let label_positions: HashMap<(usize, usize), String> = labels
         .iter()
-        .flat_map(|(l, v)| v.iter().map(move |p| (p.clone(), l.clone())))
+        .flat_map(|(l, v)| v.iter().map(move |p| (*p, l.clone())))
         .collect();
 
     for (label, positions) in labels.iter() {
@@ -225,7 +229,7 @@ fn find_connections(
             bfs(
                 label,
                 &mut edges,
-                &map,
+                map,
                 &mut visited,
                 &label_positions,
                 width,
@@ -251,7 +255,7 @@ fn find_shortest_path(connections: &HashMap<String, Node>) -> usize {
         label: &String,
     ) -> usize {
         let current = connections.get(label).unwrap();
-        let distance_from_start = distances.get(label).unwrap().clone();
+        let distance_from_start = *distances.get(label).unwrap();
         if label == "ZZ" {
             return distance_from_start;
         }
@@ -273,8 +277,8 @@ fn find_shortest_path(connections: &HashMap<String, Node>) -> usize {
             .map(|l| (l, *distances.get(l).unwrap()))
             .collect();
         remaining.sort_by_key(|(_, d)| *d);
-        if remaining.is_empty() || remaining[0].1 == std::usize::MAX {
-            return std::usize::MAX;
+        if remaining.is_empty() || remaining[0].1 == usize::MAX {
+            return usize::MAX;
         }
 
         let next_label = remaining[0].0.clone();
@@ -287,12 +291,12 @@ fn find_shortest_path(connections: &HashMap<String, Node>) -> usize {
         .map(|k| {
             (
                 k.clone(),
-                if *k == first_node { 0 } else { std::usize::MAX },
+                if *k == first_node { 0 } else { usize::MAX },
             )
         })
         .collect();
     let mut unvisited = connections.keys().cloned().collect();
-    dijkstras(&connections, &mut distances, &mut unvisited, &first_node)
+    dijkstras(connections, &mut distances, &mut unvisited, &first_node)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
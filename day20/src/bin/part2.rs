@@ -138,7 +138,7 @@
 //! In your maze, when accounting for recursion, how many steps does it take to get from the open
 //! tile marked AA to the open tile marked ZZ, both at the outermost layer?
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
@@ -166,10 +166,11 @@ impl fmt::Display for Node {
     }
 }
 
-fn find_labels(
-    map: &Vec<Vec<u8>>,
-) -> Result<HashMap<String, Vec<(usize, usize, bool)>>, Box<dyn Error>> {
-    let mut label_map: HashMap<String, Vec<(usize, usize, bool)>> = HashMap::new();
+/// The positions next to each label's portals, and whether each portal is on the inner edge.
+type Labels = HashMap<String, Vec<(usize, usize, bool)>>;
+
+fn find_labels(map: &[Vec<u8>]) -> Result<Labels, Box<dyn Error>> {
+    let mut label_map: Labels = HashMap::new();
     let (width, height) = (map[0].len(), map.len());
     println!("Finding labels...");
     for y in 0..height {
@@ -215,15 +216,16 @@ fn find_labels(
 }
 
 fn find_connections(
-    map: &Vec<Vec<u8>>,
-    labels: &HashMap<String, Vec<(usize, usize, bool)>>,
+    map: &[Vec<u8>],
+    labels: &Labels,
 ) -> HashMap<String, Node> {
+    #[allow(clippy::too_many_arguments)]
     fn bfs(
         from_label: &String,
         from_inner: bool,
         edges: &mut HashMap<(String, bool), (usize, bool)>,
-        map: &Vec<Vec<u8>>,
-        visited: &mut Vec<Vec<bool>>,
+        map: &[Vec<u8>],
+        visited: &mut [Vec<bool>],
         label_positions: &HashMap<(usize, usize), (String, bool)>,
         width: usize,
         height: usize,
@@ -253,7 +255,7 @@ fn find_connections(
                     distance + 1
                 };
                 let old = edges.insert((label.clone(), from_inner), (distance, *inner));
-                if !old.is_none() {
+                if old.is_some() {
                     panic!("Label {} can reach both portals for {}", from_label, label);
                 }
             }
@@ -308,7 +310,7 @@ fn find_connections(
                 label,
                 *inner,
                 &mut edges,
-                &map,
+                map,
                 &mut visited,
                 &label_positions,
                 width,
@@ -339,7 +341,7 @@ fn find_shortest_path(connections: &HashMap<String, Node>) -> usize {
     // to revisit nodes to walk our way back up to level 0. Dijkstra just won't work. Because we
     // need to allow cycles, we also can't use dfs because we might end up in an infinite loop.
     // We'll need bfs instead.
-    let mut shortest = std::usize::MAX;
+    let mut shortest = usize::MAX;
     let mut queue = VecDeque::new();
     queue.push_back(("AA".to_owned(), false, 0, 0, Vec::<String>::new()));
     while let Some((current, from_inner, total_distance, level, path)) = queue.pop_front() {
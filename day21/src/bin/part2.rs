@@ -18,383 +18,523 @@
 //! Successfully survey the rest of the hull by ending your program with RUN. What amount of hull
 //! damage does the springdroid now report?
 
-use std::clone::Clone;
-use std::cmp::PartialEq;
-use std::convert::{TryFrom, TryInto};
+use intcode::{AsciiMachine, AsciiOutput, Intcode};
+use std::collections::HashMap;
+use std::env;
 use std::error::Error;
-use std::fmt::{self, Display};
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::str;
-
-#[derive(Debug)]
-struct InvalidParameterMode(i64);
-
-impl Display for InvalidParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode.", self.0)
-    }
+use std::io::{self, BufReader};
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// Jump if there's a hole somewhere in the next three tiles, and there's ground to land on, and
+/// then somewhere to go from there: either another tile to walk to, or ground to jump to again.
+const FORMULA: &str = "J = !(A & B & C) & D & (E | H)";
+
+/// The springdroid only has room for this many instructions, not counting `RUN`.
+const MAX_INSTRUCTIONS: usize = 15;
+
+/// The sensors available with `RUN`.
+const SENSORS: &str = "ABCDEFGHI";
+const USAGE: &str = "Usage: day21-part2 [--formula <formula>]";
+
+/// A boolean formula over the sensors, ie, `!(A & B & C) & D & (E | H)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Formula {
+    Sensor(char),
+    Not(Box<Formula>),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
 }
 
-impl Error for InvalidParameterMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+impl Formula {
+    /// Parses a formula, optionally starting with `J =`. `!` binds tightest, then `&`, then `|`.
+    fn parse(text: &str) -> Result<Formula, Box<dyn Error>> {
+        let body = match text.trim_start().strip_prefix('J') {
+            Some(rest) if rest.trim_start().starts_with('=') => {
+                &text[text.find('=').unwrap_or(0) + 1..]
+            }
+            _ => text,
+        };
+        let offset = text.len() - body.len();
+        let mut parser = Parser {
+            chars: body.char_indices().peekable(),
+            offset,
+        };
+        let formula = parser.or()?;
+        match parser.peek() {
+            None => Ok(formula),
+            Some((i, c)) => Err(format!("Unexpected '{}' at column {}", c, i + 1).into()),
+        }
     }
-}
 
-#[derive(Debug)]
-struct InvalidOpcode(i64);
-
-impl Display for InvalidOpcode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid opcode.", self.0)
+    fn not(self) -> Formula {
+        match self {
+            Formula::Not(inner) => *inner,
+            formula => Formula::Not(Box::new(formula)),
+        }
     }
-}
 
-impl Error for InvalidOpcode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+    fn sensors(&self) -> Vec<char> {
+        match self {
+            Formula::Sensor(sensor) => vec![*sensor],
+            Formula::Not(inner) => inner.sensors(),
+            Formula::And(terms) | Formula::Or(terms) => {
+                terms.iter().flat_map(Formula::sensors).collect()
+            }
+        }
     }
 }
 
-#[derive(Debug)]
-struct InvalidOutputMode(ParameterMode);
-
-impl Display for InvalidOutputMode {
+impl fmt::Display for Formula {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode for output.", self.0)
-    }
-}
-
-impl Error for InvalidOutputMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+        let join = |f: &mut fmt::Formatter<'_>, terms: &[Formula], op| {
+            write!(f, "(")?;
+            for (i, term) in terms.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                write!(f, "{}", term)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            Formula::Sensor(sensor) => write!(f, "{}", sensor),
+            Formula::Not(inner) => write!(f, "!{}", inner),
+            Formula::And(terms) => join(f, terms, "&"),
+            Formula::Or(terms) => join(f, terms, "|"),
+        }
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
+/// A recursive descent parser for formulas. `offset` is where the formula starts in the text the
+/// user typed, so that errors point at the right column.
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    offset: usize,
 }
 
-impl Display for ParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ParameterMode::Position => "'position'",
-                ParameterMode::Immediate => "'immediate'",
-                ParameterMode::Relative => "'relative'",
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
             }
-        )
+            self.chars.next();
+        }
+        let offset = self.offset;
+        self.chars.peek().map(|&(i, c)| (i + offset, c))
     }
-}
-
-impl TryFrom<i64> for ParameterMode {
-    type Error = InvalidParameterMode;
 
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code {
-            0 => Ok(ParameterMode::Position),
-            1 => Ok(ParameterMode::Immediate),
-            2 => Ok(ParameterMode::Relative),
-            _ => Err(InvalidParameterMode(code)),
+    fn or(&mut self) -> Result<Formula, Box<dyn Error>> {
+        let mut terms = vec![self.and()?];
+        while let Some((_, '|')) = self.peek() {
+            self.chars.next();
+            terms.push(self.and()?);
         }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Formula::Or(terms)
+        })
     }
-}
 
-impl ParameterMode {
-    fn modes2(code: i64) -> Result<(Self, Self), InvalidParameterMode> {
-        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
+    fn and(&mut self) -> Result<Formula, Box<dyn Error>> {
+        let mut terms = vec![self.unary()?];
+        while let Some((_, '&')) = self.peek() {
+            self.chars.next();
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Formula::And(terms)
+        })
     }
 
-    fn modes3(code: i64) -> Result<(Self, Self, Self), InvalidParameterMode> {
-        Ok((
-            (code % 10).try_into()?,
-            ((code / 10) % 10).try_into()?,
-            ((code / 100) % 10).try_into()?,
-        ))
-    }
-}
-
-enum Opcode {
-    Add(ParameterMode, ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode, ParameterMode),
-    Input(ParameterMode),
-    Output(ParameterMode),
-    JumpIfTrue(ParameterMode, ParameterMode),
-    JumpIfFalse(ParameterMode, ParameterMode),
-    LessThan(ParameterMode, ParameterMode, ParameterMode),
-    Equal(ParameterMode, ParameterMode, ParameterMode),
-    RelativeBaseOffset(ParameterMode),
-    Halt,
-}
-
-impl TryFrom<i64> for Opcode {
-    type Error = InvalidOpcode;
-
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code % 100 {
-            1 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Add(mode1, mode2, mode3))
-            }
-            2 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Multiply(mode1, mode2, mode3))
-            }
-            3 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Input(mode))
-            }
-            4 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Output(mode))
-            }
-            5 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfTrue(mode1, mode2))
+    fn unary(&mut self) -> Result<Formula, Box<dyn Error>> {
+        match self.peek() {
+            Some((_, '!')) => {
+                self.chars.next();
+                Ok(self.unary()?.not())
             }
-            6 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfFalse(mode1, mode2))
-            }
-            7 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::LessThan(mode1, mode2, mode3))
+            Some((_, '(')) => {
+                self.chars.next();
+                let formula = self.or()?;
+                match self.peek() {
+                    Some((_, ')')) => {
+                        self.chars.next();
+                        Ok(formula)
+                    }
+                    Some((i, c)) => {
+                        Err(format!("Expected ')' at column {}, got '{}'", i + 1, c).into())
+                    }
+                    None => Err("Expected ')' at the end of the formula".into()),
+                }
             }
-            8 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Equal(mode1, mode2, mode3))
+            Some((_, c)) if c.is_ascii_uppercase() => {
+                self.chars.next();
+                Ok(Formula::Sensor(c))
             }
-            9 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::RelativeBaseOffset(mode))
+            Some((i, c)) => {
+                Err(format!("Expected a sensor at column {}, got '{}'", i + 1, c).into())
             }
-            99 => Ok(Opcode::Halt),
-            _ => Err(InvalidOpcode(code)),
+            None => Err("Expected a sensor at the end of the formula".into()),
         }
     }
 }
 
-#[derive(Clone)]
-struct Intcode {
-    code: Vec<i64>,
-    ip: usize,
-    relative_base: isize,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Instruction {
+    op: &'static str,
+    x: char,
+    y: char,
 }
 
-impl Intcode {
-    fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
-        let code = reader
-            .split(b',')
-            .map(|code| match code {
-                Ok(code) => {
-                    let s = str::from_utf8(&code)?;
-                    Ok(s.trim().parse()?)
-                }
-                Err(e) => Err(Box::new(e) as Box<dyn Error>),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Intcode {
-            code,
-            ip: 0,
-            relative_base: 0,
-        })
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.op, self.x, self.y)
+    }
+}
+
+/// What we know about the registers when some code starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Registers {
+    /// The register the code computes into is still false, like it is when the program starts.
+    target_false: bool,
+
+    /// The other register can be used for scratch work.
+    other_free: bool,
+    other_false: bool,
+}
+
+fn other(register: char) -> char {
+    if register == 'J' {
+        'T'
+    } else {
+        'J'
     }
+}
+
+/// Compiles formulas to springscript that only uses `T` and `J`, by trying a handful of ways of
+/// putting each part of the formula together and keeping the shortest it finds. That isn't
+/// necessarily the shortest springscript there is.
+///
+/// Every sensor in a formula takes at least one instruction to read, so the compiler doesn't try
+/// ways of putting a formula together that can't be shorter than what it already has, or than the
+/// room that's left for them.
+#[derive(Default)]
+struct Compiler {
+    /// The shortest code found for each part of a formula, or, if nothing was found, the most
+    /// instructions the compiler was looking for.
+    shortest: HashMap<(Formula, char, Registers), Result<Vec<Instruction>, usize>>,
+}
 
-    fn read(&mut self, i: usize) -> i64 {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
+/// The shortest code found so far for a formula, and how long code has to be to be worth trying.
+struct Best {
+    code: Option<Vec<Instruction>>,
+    limit: Option<usize>,
+}
+
+impl Best {
+    /// Keeps `code` if it fits, and from then on, only looks for something shorter.
+    fn offer(&mut self, code: Vec<Instruction>) {
+        if self.fits(code.len()) {
+            self.limit = code.len().checked_sub(1);
+            self.code = Some(code);
         }
-        self.code[i]
     }
 
-    fn write(&mut self, i: usize, v: i64) {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
-        }
-        self.code[i] = v;
+    fn fits(&self, len: usize) -> bool {
+        self.limit.is_some_and(|limit| len <= limit)
     }
 
-    fn output_operand(
-        &mut self,
-        sp: usize,
-        mode: ParameterMode,
-    ) -> Result<usize, InvalidOutputMode> {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => Ok(v as usize),
-            ParameterMode::Immediate => Err(InvalidOutputMode(mode)),
-            ParameterMode::Relative => Ok((v as isize + self.relative_base) as usize),
-        }
+    /// How many instructions are left for one part of a candidate, if the rest needs `used`.
+    fn room(&self, used: usize) -> Option<usize> {
+        self.limit.and_then(|limit| limit.checked_sub(used))
     }
+}
 
-    fn operand(&mut self, sp: usize, mode: ParameterMode) -> i64 {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => self.read(v as usize),
-            ParameterMode::Immediate => v,
-            ParameterMode::Relative => self.read((v as isize + self.relative_base) as usize),
+impl Compiler {
+    /// The shortest code the compiler finds that leaves the value of `formula` in `target`, if it
+    /// finds any way to do it with two registers in no more than `limit` instructions.
+    fn compile(
+        &mut self,
+        formula: &Formula,
+        target: char,
+        registers: Registers,
+        limit: Option<usize>,
+    ) -> Option<Vec<Instruction>> {
+        let limit = limit.filter(|&limit| limit >= formula.sensors().len())?;
+        let key = (formula.clone(), target, registers);
+        match self.shortest.get(&key) {
+            Some(Ok(code)) => return Some(code.clone()).filter(|code| code.len() <= limit),
+            Some(Err(searched)) if *searched >= limit => return None,
+            _ => {}
         }
-    }
 
-    fn operands2(&mut self, sp: usize, mode1: ParameterMode, mode2: ParameterMode) -> (i64, i64) {
-        (self.operand(sp, mode1), self.operand(sp + 1, mode2))
+        let mut best = Best {
+            code: None,
+            limit: Some(limit),
+        };
+        self.candidates(formula, target, registers, &mut best);
+        self.shortest.insert(key, best.code.clone().ok_or(limit));
+        best.code
     }
 
-    fn run(&mut self, input: &Vec<i64>) -> Result<(Vec<i64>, bool), Box<dyn Error>> {
-        let mut output: Vec<i64> = Vec::new();
-        let mut inputp = 0;
-        let mut halted = false;
-        loop {
-            let opcode: Opcode = self.read(self.ip).try_into()?;
-            self.ip = match opcode {
-                Opcode::Add(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 + op2);
-                    self.ip + 4
+    /// Offers `best` every way the compiler knows of leaving the value of `formula` in `target`.
+    fn candidates(
+        &mut self,
+        formula: &Formula,
+        target: char,
+        registers: Registers,
+        best: &mut Best,
+    ) {
+        let ins = |op, x, y| Instruction { op, x, y };
+        let scratch = other(target);
+        match formula {
+            Formula::Sensor(x) if registers.target_false => {
+                best.offer(vec![ins("OR", *x, target)]);
+            }
+            Formula::Sensor(x) => {
+                best.offer(vec![ins("NOT", *x, target), ins("NOT", target, target)]);
+            }
+            Formula::Not(inner) => {
+                if let Formula::Sensor(x) = **inner {
+                    best.offer(vec![ins("NOT", x, target)]);
                 }
-                Opcode::Multiply(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 * op2);
-                    self.ip + 4
+                if let Some(mut code) = self.compile(inner, target, registers, best.room(1)) {
+                    code.push(ins("NOT", target, target));
+                    best.offer(code);
                 }
-                Opcode::Input(mode) => {
-                    if inputp >= input.len() {
-                        // not enough input; suspend
+
+                // De Morgan: !(a & b) is !a | !b, and !(a | b) is !a & !b
+                let negated = match &**inner {
+                    Formula::And(terms) => Some(Formula::Or(
+                        terms.iter().cloned().map(Formula::not).collect(),
+                    )),
+                    Formula::Or(terms) => Some(Formula::And(
+                        terms.iter().cloned().map(Formula::not).collect(),
+                    )),
+                    _ => None,
+                };
+                if let Some(negated) = negated {
+                    if let Some(code) = self.compile(&negated, target, registers, best.room(0)) {
+                        best.offer(code);
+                    }
+                }
+            }
+            Formula::And(terms) | Formula::Or(terms) => {
+                let and = matches!(formula, Formula::And(_));
+                let op = if and { "AND" } else { "OR" };
+
+                // compute all but one term, then combine it with the last one
+                for (i, last) in terms.iter().enumerate() {
+                    if !best.fits(formula.sensors().len()) {
                         break;
                     }
+                    let mut rest: Vec<_> = terms.clone();
+                    rest.remove(i);
+                    let rest = match rest.len() {
+                        1 => rest.remove(0),
+                        _ if and => Formula::And(rest),
+                        _ => Formula::Or(rest),
+                    };
+
+                    match last {
+                        Formula::Sensor(x) => {
+                            if let Some(mut code) =
+                                self.compile(&rest, target, registers, best.room(1))
+                            {
+                                code.push(ins(op, *x, target));
+                                best.offer(code);
+                            }
+                        }
+                        Formula::Not(inner) => {
+                            if let Formula::Sensor(x) = **inner {
+                                self.combine_negated(&rest, x, and, target, registers, best);
+                            }
+                        }
+                        _ => {}
+                    }
 
-                    let op = self.output_operand(self.ip + 1, mode)?;
-                    self.write(op, input[inputp]);
-                    inputp += 1;
-                    self.ip + 2
-                }
-                Opcode::Output(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    output.push(op);
-                    self.ip + 2
-                }
-                Opcode::JumpIfTrue(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 != 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
+                    if !registers.other_free {
+                        continue;
                     }
-                }
-                Opcode::JumpIfFalse(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 == 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
+
+                    // compute the rest, then the last term in the scratch register without any
+                    // scratch of its own
+                    let room = best.room(last.sensors().len() + 1);
+                    if let Some(rest_code) = self.compile(&rest, target, registers, room) {
+                        let before = Registers {
+                            target_false: registers.other_false
+                                && rest_code.iter().all(|i| i.y != scratch),
+                            other_free: false,
+                            other_false: false,
+                        };
+                        let room = best.room(rest_code.len() + 1);
+                        if let Some(last_code) = self.compile(last, scratch, before, room) {
+                            let mut code = rest_code;
+                            code.extend(last_code);
+                            code.push(ins(op, scratch, target));
+                            best.offer(code);
+                        }
+                    }
+
+                    // or the other way around: the last term first, then the rest without scratch
+                    let flipped = Registers {
+                        target_false: registers.other_false,
+                        other_free: true,
+                        other_false: registers.target_false,
+                    };
+                    let room = best.room(rest.sensors().len() + 1);
+                    let last_code = match self.compile(last, scratch, flipped, room) {
+                        Some(code) => code,
+                        None => continue,
+                    };
+                    let after = Registers {
+                        target_false: registers.target_false
+                            && last_code.iter().all(|i| i.y != target),
+                        other_free: false,
+                        other_false: false,
+                    };
+                    let room = best.room(last_code.len() + 1);
+                    if let Some(rest_code) = self.compile(&rest, target, after, room) {
+                        let mut code = last_code;
+                        code.extend(rest_code);
+                        code.push(ins(op, scratch, target));
+                        best.offer(code);
                     }
                 }
-                Opcode::LessThan(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 < op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::Equal(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 == op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::RelativeBaseOffset(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    self.relative_base += op as isize;
-                    self.ip + 2
-                }
-                Opcode::Halt => {
-                    halted = true;
-                    break;
-                }
-            };
+            }
+        }
+    }
+
+    /// Combines `rest` with `!x`, where `x` is a sensor, using `AND` or `OR`.
+    fn combine_negated(
+        &mut self,
+        rest: &Formula,
+        x: char,
+        and: bool,
+        target: char,
+        registers: Registers,
+        best: &mut Best,
+    ) {
+        let ins = |op, x, y| Instruction { op, x, y };
+        let (op, dual) = if and { ("AND", "OR") } else { ("OR", "AND") };
+        if registers.other_free {
+            if let Some(mut code) = self.compile(rest, target, registers, best.room(2)) {
+                code.push(ins("NOT", x, other(target)));
+                code.push(ins(op, other(target), target));
+                best.offer(code);
+            }
+        }
+
+        // without a scratch register: a & !x is !(!a | x), and a | !x is !(!a & x)
+        let negated = rest.clone().not();
+        if let Some(mut code) = self.compile(&negated, target, registers, best.room(2)) {
+            code.push(ins(dual, x, target));
+            code.push(ins("NOT", target, target));
+            best.offer(code);
         }
-        Ok((output, halted))
     }
 }
 
-fn render_output(output: &Vec<i64>) -> bool {
-    let mut s = String::with_capacity(output.len());
-    let mut done = false;
-    for c in output.iter() {
-        if (0..256).contains(c) {
-            s.push(char::from(*c as u8));
-        } else {
-            s.push_str(&c.to_string());
-            done = true;
+/// Compiles a formula for `J` into springscript, ending with `RUN`.
+fn springscript(text: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let formula = Formula::parse(text)?;
+    if let Some(sensor) = formula
+        .sensors()
+        .into_iter()
+        .find(|s| !SENSORS.contains(*s))
+    {
+        return Err(format!(
+            "There's no sensor {}; the springdroid has {}",
+            sensor, SENSORS
+        )
+        .into());
+    }
+
+    // every sensor takes at least one instruction to read
+    let sensors = formula.sensors().len();
+    if sensors > MAX_INSTRUCTIONS {
+        return Err(format!(
+            "{} reads {} sensors, but the springdroid only has room for {} instructions",
+            formula, sensors, MAX_INSTRUCTIONS
+        )
+        .into());
+    }
+
+    let registers = Registers {
+        target_false: true,
+        other_free: true,
+        other_false: true,
+    };
+    let code = Compiler::default()
+        .compile(&formula, 'J', registers, Some(MAX_INSTRUCTIONS))
+        .ok_or_else(|| {
+            format!(
+                "Can't fit {} in the springdroid's {} instructions using only T and J",
+                formula, MAX_INSTRUCTIONS
+            )
+        })?;
+
+    let mut lines: Vec<_> = code.iter().map(Instruction::to_string).collect();
+    lines.push("RUN".to_string());
+    Ok(lines)
+}
+
+/// Prints what the springdroid said. Returns true if it made it across the hull and reported the
+/// damage.
+fn render_output(output: &AsciiOutput) -> bool {
+    print!("{}", output.text);
+    match output.value() {
+        Some(damage) => {
+            println!("Hull damage: {}", damage);
+            true
         }
+        None => false,
     }
-    println!("{}", s);
-    done
 }
 
+/// Sends springscript typed on stdin to the springdroid, up to and including `WALK` or `RUN`.
 #[allow(dead_code)]
-fn read_input() -> Result<Vec<i64>, Box<dyn Error>> {
+fn read_input(droid: &mut AsciiMachine) -> Result<(), Box<dyn Error>> {
     let stdio = io::stdin();
-    let mut s = String::new();
-    let mut input = Vec::new();
-    while s != "WALK\n" && s != "RUN\n" {
-        s.clear();
-        stdio.read_line(&mut s)?;
-        for c in s.bytes() {
-            input.push(c as i64);
+    loop {
+        let mut s = String::new();
+        if stdio.read_line(&mut s)? == 0 {
+            return Err("Unexpected end of input.".into());
+        }
+        let line = s.trim_end();
+        droid.send_line(line);
+        if line == "WALK" || line == "RUN" {
+            return Ok(());
         }
     }
-
-    Ok(input)
 }
 
 #[allow(dead_code)]
 fn interactive(program: &Intcode) -> Result<(), Box<dyn Error>> {
     loop {
-        let mut program = program.clone();
-        let (output, _) = program.run(&vec![])?;
-        render_output(&output);
-
-        let input = read_input()?;
-        let (output, _) = program.run(&input)?;
-        if render_output(&output) {
+        let mut droid = AsciiMachine::new(program.clone());
+        render_output(&droid.read_until_prompt()?);
+        read_input(&mut droid)?;
+        if render_output(&droid.read_until_prompt()?) {
             break;
         }
     }
     Ok(())
 }
 
-#[allow(dead_code)]
-fn non_interactive(program: &Intcode) -> Result<bool, Box<dyn Error>> {
-    let mut program = program.clone();
-    let input = [
-        "NOT B J\n",
-        "NOT C T\n",
-        "OR T J\n",
-        "AND H J\n",
-        "NOT A T\n",
-        "OR T J\n",
-        "AND D J\n",
-        "RUN\n",
-    ];
-    let input = input
-        .iter()
-        .flat_map(|l| l.bytes().map(|c| c as i64))
-        .collect();
-    let (output, _) = program.run(&input)?;
+fn non_interactive(program: &Intcode, formula: &str) -> Result<bool, Box<dyn Error>> {
+    let mut droid = AsciiMachine::new(program.clone());
+    for line in springscript(formula)? {
+        println!("{}", line);
+        droid.send_line(&line);
+    }
+    let output = droid.read_until_prompt()?;
     Ok(render_output(&output))
 }
 
@@ -402,7 +542,109 @@ fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
     let program = Intcode::load(reader)?;
-    non_interactive(&program)?;
+    let mut args = env::args().skip(1);
+    let formula = match (args.next(), args.next()) {
+        (None, _) => FORMULA.to_string(),
+        (Some(flag), Some(formula)) if flag == "--formula" => formula,
+        _ => return Err(USAGE.into()),
+    };
+    if !non_interactive(&program, &formula)? {
+        return Err(format!("The springdroid didn't make it across with {}", formula).into());
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(formula: &Formula, sensors: &dyn Fn(char) -> bool) -> bool {
+        match formula {
+            Formula::Sensor(sensor) => sensors(*sensor),
+            Formula::Not(inner) => !eval(inner, sensors),
+            Formula::And(terms) => terms.iter().all(|term| eval(term, sensors)),
+            Formula::Or(terms) => terms.iter().any(|term| eval(term, sensors)),
+        }
+    }
+
+    /// Runs springscript the way the springdroid does, with `T` and `J` starting out false, and
+    /// returns `J`.
+    fn run(lines: &[String], sensors: &dyn Fn(char) -> bool) -> bool {
+        let (mut t, mut j) = (false, false);
+        for line in lines {
+            let parts: Vec<_> = line.split(' ').collect();
+            if parts == ["RUN"] {
+                break;
+            }
+            let x = parts[1].chars().next().unwrap();
+            let x = match x {
+                'T' => t,
+                'J' => j,
+                _ => sensors(x),
+            };
+            let y = if parts[2] == "T" { &mut t } else { &mut j };
+            *y = match parts[0] {
+                "AND" => x && *y,
+                "OR" => x || *y,
+                "NOT" => !x,
+                op => panic!("unknown instruction {}", op),
+            };
+        }
+        j
+    }
+
+    /// Checks the compiled springscript against the formula for every combination of sensors.
+    fn check(text: &str) -> usize {
+        let formula = Formula::parse(text).unwrap();
+        let lines = springscript(text).unwrap();
+        assert_eq!(lines.last().map(String::as_str), Some("RUN"));
+        for ground in 0..1 << SENSORS.len() {
+            let sensors = |sensor: char| ground & 1 << SENSORS.find(sensor).unwrap() != 0;
+            assert_eq!(
+                run(&lines, &sensors),
+                eval(&formula, &sensors),
+                "{} compiled to {:?}, with ground {:09b}",
+                text,
+                lines,
+                ground
+            );
+        }
+        lines.len() - 1
+    }
+
+    #[test]
+    fn compiles_the_jump_formula() {
+        assert_eq!(check(FORMULA), 8);
+    }
+
+    #[test]
+    fn compiles_formulas_correctly() {
+        for text in &[
+            "A",
+            "!A",
+            "!!A",
+            "A & B",
+            "A | !B",
+            "!A & !B",
+            "!(A | B) | C",
+            "A & (B | C) & (D | E)",
+            "(A | B) & !(C & D) | E & !F",
+            "!(A & !B) & (C | !D) & !(E | F | !G)",
+            "(A & B | C & D) & E | I",
+            "!(!(A | B) & !(C | !D)) & !I",
+        ] {
+            check(text);
+        }
+    }
+
+    #[test]
+    fn rejects_formulas_it_cant_compile() {
+        assert!(springscript("J = A & Z").is_err());
+        assert!(springscript("A &").is_err());
+        assert!(springscript("(A & B | C & D) & (E & F | G & H)").is_err());
+        assert!(springscript(&vec!["A"; 17].join(" & ")).is_err());
+        assert!(springscript("A&B&C&D&E&F&G&H&I&!A&!B&!C&!D&!E&!F&!G&!H").is_err());
+        assert!(springscript("A&B&C&D&E&F&G&H&I&!A&!B&!C&!D&!E&!F").is_err());
+    }
+}
@@ -181,15 +181,15 @@ fn main() -> Result<(), Box<dyn Error>> {
             Ok(line) => {
                 if line == "deal into new stack" {
                     position = DECK_SIZE - position - 1;
-                } else if line.starts_with("cut ") {
-                    let cut: i16 = line[4..].parse()?;
+                } else if let Some(cut) = line.strip_prefix("cut ") {
+                    let cut: i16 = cut.parse()?;
                     if cut < 0 {
                         position = (position + (-cut as u16)) % DECK_SIZE;
                     } else {
                         position = (DECK_SIZE + position - (cut as u16)) % DECK_SIZE;
                     }
-                } else if line.starts_with("deal with increment ") {
-                    let increment: u32 = line[20..].parse()?;
+                } else if let Some(increment) = line.strip_prefix("deal with increment ") {
+                    let increment: u32 = increment.parse()?;
                     position = ((position as u32) * increment % (DECK_SIZE as u32)) as u16;
                 }
             }
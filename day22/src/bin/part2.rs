@@ -40,7 +40,7 @@ fn modular_pow(base: i128, exponent: i128, modulus: i128) -> i128 {
         if (exponent & 1) == 1 {
             result = (result * base) % modulus;
         }
-        exponent = exponent >> 1;
+        exponent >>= 1;
         base = (base * base) % modulus;
     }
 
@@ -69,13 +69,13 @@ fn main() -> Result<(), Box<dyn Error>> {
                     // Reverses the list which means our increment is negated. Our list's first
                     // number also changes, which we can calculate by adding the new increment to
                     // our offset
-                    increment = (increment * -1).rem_euclid(DECK_SIZE);
+                    increment = (-increment).rem_euclid(DECK_SIZE);
                     offset = (offset + increment).rem_euclid(DECK_SIZE);
-                } else if line.starts_with("cut ") {
+                } else if let Some(n) = line.strip_prefix("cut ") {
                     // rotates the list which only affects the offset
-                    let n: i128 = line[4..].parse()?;
+                    let n: i128 = n.parse()?;
                     offset = (offset + increment * n).rem_euclid(DECK_SIZE);
-                } else if line.starts_with("deal with increment ") {
+                } else if let Some(n) = line.strip_prefix("deal with increment ") {
                     // This one is a bit more difficult to explain... The card at index 0 goes to
                     // index 0; from 1 to n; from 2 to 2n; 3 to 3n; etc. So the ith card goes to
                     // i*n. Offset isn't changing, but how do we calculate increment? If we knew
@@ -88,7 +88,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                     // the increment by that. One last problem: that pow() will probably overflow.
                     // There is a method to calculate modular exponentiation using exponentiation
                     // by squaring that's implemented above.
-                    let n: i128 = line[20..].parse()?;
+                    let n: i128 = n.parse()?;
                     increment = (increment * inv(n)).rem_euclid(DECK_SIZE);
                 }
             }
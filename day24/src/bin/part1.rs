@@ -85,7 +85,7 @@ fn hash(board: &Vec<Vec<u8>>) -> u64 {
     hasher.finish()
 }
 
-fn print_board(board: &Vec<Vec<u8>>) {
+fn print_board(board: &[Vec<u8>]) {
     for line in board.iter() {
         println!(
             "{}",
@@ -117,9 +117,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         // We need to make sure the first row is initialized to zeros... all the other positions
         // will be initialized as the loop runs
         let update_board = board_num ^ 1;
-        for x in 0..width {
-            boards[update_board][0][x] = 0;
-        }
+        boards[update_board][0].fill(0);
 
         for y in 0..height {
             let not_last_row = y + 1 < height;
@@ -191,7 +191,7 @@ const BOARDS: usize = 2 * MINUTES + 3;
 const STARTING_BOARD: usize = MINUTES + 1;
 
 /// Count the number of bugs in a board
-fn count_bugs(board: &Vec<Vec<u8>>) -> u8 {
+fn count_bugs(board: &[Vec<u8>]) -> u8 {
     board.iter().fold(0, |acc, line| {
         acc + line
             .iter()
@@ -228,20 +228,20 @@ fn main() -> Result<(), Box<dyn Error>> {
             // bugs to a given tile. In this loop, we'll zero the matrix, but also add 1 to the
             // tiles along the edges if the corresponding level above us had bugs adjascent to the
             // middle tile.
-            for y in 0..height {
-                for x in 0..width {
-                    convolutions[y][x] = 0;
+            for (y, row) in convolutions.iter_mut().enumerate() {
+                for (x, convolution) in row.iter_mut().enumerate() {
+                    *convolution = 0;
                     if x == 0 && prev_lvl_bugs_l {
-                        convolutions[y][x] += 1;
+                        *convolution += 1;
                     }
                     if x + 1 == width && prev_lvl_bugs_r {
-                        convolutions[y][x] += 1;
+                        *convolution += 1;
                     }
                     if y == 0 && prev_lvl_bugs_u {
-                        convolutions[y][x] += 1;
+                        *convolution += 1;
                     }
                     if y + 1 == height && prev_lvl_bugs_d {
-                        convolutions[y][x] += 1;
+                        *convolution += 1;
                     }
                 }
             }
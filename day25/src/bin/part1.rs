@@ -35,356 +35,531 @@
 //!
 //! Look around the ship and see if you can find the password for the main airlock.
 
-use std::clone::Clone;
-use std::cmp::PartialEq;
-use std::convert::{TryFrom, TryInto};
+use intcode::{ascii, AsciiMachine, AsciiOutput, Budget, Intcode, RunState, Snapshot};
+use std::collections::{HashSet, VecDeque};
+use std::env;
 use std::error::Error;
-use std::fmt::{self, Display};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::str;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
 
-#[derive(Debug)]
-struct InvalidParameterMode(i64);
+/// Items that end the game one way or another. Taking the infinite loop, well...
+const AVOID: &str = "escape pod,giant electromagnet,infinite loop,molten lava,photons";
 
-impl Display for InvalidParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode.", self.0)
-    }
-}
+/// No single command takes anywhere near this many instructions, unless something has gone wrong.
+const COMMAND_BUDGET: u64 = 10_000_000;
 
-impl Error for InvalidParameterMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
-    }
-}
+/// The most items the droid will try combinations of. The ship only has eight or so, and every
+/// item doubles the combinations there are to keep track of.
+const MAX_ITEMS: usize = 16;
 
-#[derive(Debug)]
-struct InvalidOpcode(i64);
+const CHECKPOINT: &str = "Security Checkpoint";
+const USAGE: &str = "Usage: day25-part1 [--avoid <item>,<item>,...] | [--play] [--script <file>] \
+                     [--replay <transcript>] [--transcript <file>]";
 
-impl Display for InvalidOpcode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid opcode.", self.0)
-    }
+struct Options {
+    /// Play the game interactively instead of solving it. Any of the options below imply this.
+    play: bool,
+    script: Option<String>,
+    replay: Option<String>,
+    transcript: Option<String>,
+
+    /// Items the droid won't pick up.
+    avoid: HashSet<String>,
 }
 
-impl Error for InvalidOpcode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+fn parse_options() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        play: false,
+        script: None,
+        replay: None,
+        transcript: None,
+        avoid: parse_items(AVOID),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--play" => options.play = true,
+            "--script" => options.script = Some(args.next().ok_or(USAGE)?),
+            "--replay" => options.replay = Some(args.next().ok_or(USAGE)?),
+            "--transcript" => options.transcript = Some(args.next().ok_or(USAGE)?),
+            "--avoid" => options.avoid = parse_items(&args.next().ok_or(USAGE)?),
+            _ => return Err(USAGE.into()),
+        }
     }
+    options.play |=
+        options.script.is_some() || options.replay.is_some() || options.transcript.is_some();
+    Ok(options)
 }
 
-#[derive(Debug)]
-struct InvalidOutputMode(ParameterMode);
+fn parse_items(arg: &str) -> HashSet<String> {
+    arg.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
 
-impl Display for InvalidOutputMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} is not a valid parameter mode for output.", self.0)
-    }
+/// What the droid sees when it enters a room.
+#[derive(Debug, Default)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
 }
 
-impl Error for InvalidOutputMode {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+impl Room {
+    /// Parses the last room in `text`, which is the room the droid ended up in: stepping on the
+    /// pressure-sensitive floor describes the floor, and then the checkpoint it was thrown back
+    /// to.
+    fn parse(text: &str) -> Option<Room> {
+        let start = text.rfind("== ")?;
+        let mut lines = text[start..].lines();
+        let mut room = Room {
+            name: lines
+                .next()?
+                .trim_matches(|c| c == '=' || c == ' ')
+                .to_string(),
+            ..Room::default()
+        };
+        let mut list = None;
+        for line in lines {
+            match line {
+                "Doors here lead:" => list = Some(&mut room.doors),
+                "Items here:" => list = Some(&mut room.items),
+                _ => match (line.strip_prefix("- "), &mut list) {
+                    (Some(entry), Some(list)) => list.push(entry.to_string()),
+                    _ => list = None,
+                },
+            }
+        }
+        Some(room)
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum ParameterMode {
-    Position,
-    Immediate,
-    Relative,
+fn opposite(direction: &str) -> &'static str {
+    match direction {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        _ => "east",
+    }
 }
 
-impl Display for ParameterMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ParameterMode::Position => "'position'",
-                ParameterMode::Immediate => "'immediate'",
-                ParameterMode::Relative => "'relative'",
-            }
-        )
-    }
+/// How the pressure-sensitive floor reacted to the droid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Weight {
+    TooLight,
+    TooHeavy,
+    Right,
 }
 
-impl TryFrom<i64> for ParameterMode {
-    type Error = InvalidParameterMode;
+/// Plays the game on its own: it explores the whole ship, picking up everything that isn't on
+/// the avoid list, then figures out which of those things to carry onto the pressure-sensitive
+/// floor.
+struct Explorer {
+    droid: AsciiMachine,
+    avoid: HashSet<String>,
+    inventory: Vec<String>,
+    rooms: HashSet<String>,
+
+    /// The way from the start to the security checkpoint, and then the door to the floor.
+    checkpoint: Option<(Vec<String>, String)>,
+}
 
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code {
-            0 => Ok(ParameterMode::Position),
-            1 => Ok(ParameterMode::Immediate),
-            2 => Ok(ParameterMode::Relative),
-            _ => Err(InvalidParameterMode(code)),
+impl Explorer {
+    fn new(program: Intcode, avoid: HashSet<String>) -> Explorer {
+        let mut droid = AsciiMachine::new(program);
+        droid.program.budget = Some(Budget::Instructions(COMMAND_BUDGET));
+        Explorer {
+            droid,
+            avoid,
+            inventory: vec![],
+            rooms: HashSet::new(),
+            checkpoint: None,
         }
     }
-}
 
-impl ParameterMode {
-    fn modes2(code: i64) -> Result<(Self, Self), InvalidParameterMode> {
-        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
+    fn command(&mut self, command: &str) -> Result<AsciiOutput, Box<dyn Error>> {
+        self.droid.send_line(command);
+        let output = self.droid.read_until_prompt()?;
+        match output.state {
+            RunState::NeedsInput { .. } => Ok(output),
+            _ => {
+                // an infinite loop says the same thing over and over again
+                let lines: Vec<_> = output.text.lines().filter(|l| !l.is_empty()).collect();
+                let last = lines[lines.len().saturating_sub(3)..].join("\n");
+                Err(format!("`{}` stopped the droid:\n{}", command, last).into())
+            }
+        }
     }
 
-    fn modes3(code: i64) -> Result<(Self, Self, Self), InvalidParameterMode> {
-        Ok((
-            (code % 10).try_into()?,
-            ((code / 10) % 10).try_into()?,
-            ((code / 100) % 10).try_into()?,
-        ))
+    fn walk(&mut self, direction: &str) -> Result<Room, Box<dyn Error>> {
+        let output = self.command(direction)?;
+        Room::parse(&output.text)
+            .ok_or_else(|| format!("Couldn't go {}:\n{}", direction, output.text).into())
     }
-}
-
-enum Opcode {
-    Add(ParameterMode, ParameterMode, ParameterMode),
-    Multiply(ParameterMode, ParameterMode, ParameterMode),
-    Input(ParameterMode),
-    Output(ParameterMode),
-    JumpIfTrue(ParameterMode, ParameterMode),
-    JumpIfFalse(ParameterMode, ParameterMode),
-    LessThan(ParameterMode, ParameterMode, ParameterMode),
-    Equal(ParameterMode, ParameterMode, ParameterMode),
-    RelativeBaseOffset(ParameterMode),
-    Halt,
-}
-
-impl TryFrom<i64> for Opcode {
-    type Error = InvalidOpcode;
 
-    fn try_from(code: i64) -> Result<Self, Self::Error> {
-        match code % 100 {
-            1 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Add(mode1, mode2, mode3))
+    /// Picks up `item`, unless it turns out to stop the droid: then the game goes back to the way
+    /// it was, and the item goes on the avoid list.
+    fn take(&mut self, item: &str) -> Result<(), Box<dyn Error>> {
+        let snapshot = self.droid.program.snapshot();
+        match self.command(&format!("take {}", item)) {
+            Ok(_) => {
+                println!("Took the {}.", item);
+                self.inventory.push(item.to_string());
             }
-            2 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Multiply(mode1, mode2, mode3))
+            Err(e) => {
+                println!("{}\nAvoiding the {} from now on.", e, item);
+                self.droid.program.restore(&snapshot);
+                self.droid.input.clear();
+                self.avoid.insert(item.to_string());
             }
-            3 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Input(mode))
+        }
+        Ok(())
+    }
+
+    /// Depth-first search from `room`, which the droid entered from `path`. Every room is left
+    /// the way it was entered, so the droid ends up back where it started.
+    fn explore(&mut self, room: Room, path: &mut Vec<String>) -> Result<(), Box<dyn Error>> {
+        self.rooms.insert(room.name.clone());
+        for item in &room.items {
+            if !self.avoid.contains(item) {
+                self.take(item)?;
             }
-            4 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Output(mode))
+        }
+
+        let back = path.last().map(|door| opposite(door));
+        for door in &room.doors {
+            if Some(door.as_str()) == back {
+                continue;
             }
-            5 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfTrue(mode1, mode2))
+            let next = self.walk(door)?;
+            if next.name == room.name {
+                // the floor threw the droid back out; come back once it has everything
+                if room.name == CHECKPOINT {
+                    self.checkpoint = Some((path.clone(), door.clone()));
+                }
+                continue;
             }
-            6 => {
-                let (mode1, mode2) =
-                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::JumpIfFalse(mode1, mode2))
+            if !self.rooms.contains(&next.name) {
+                path.push(door.clone());
+                self.explore(next, path)?;
+                path.pop();
             }
-            7 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::LessThan(mode1, mode2, mode3))
+            self.walk(opposite(door))?;
+        }
+        Ok(())
+    }
+
+    fn weigh(&mut self, door: &str) -> Result<(Weight, String), Box<dyn Error>> {
+        self.droid.send_line(door);
+        let output = self.droid.read_until_prompt()?;
+        let weight = if output.text.contains("heavier than the detected") {
+            Weight::TooLight
+        } else if output.text.contains("lighter than the detected") {
+            Weight::TooHeavy
+        } else {
+            Weight::Right
+        };
+        Ok((weight, output.text))
+    }
+
+    /// Tries combinations of items on the floor. If a set of items is too heavy, so is every set
+    /// with those items in it, and if it's too light, so is every set of just some of them. So
+    /// combinations are tried from both ends at once, ie, all the items, then none of them, then
+    /// all but one, then just one, and so on: small sets that are too heavy rule out bigger ones,
+    /// and big sets that are too light rule out smaller ones.
+    fn unlock(&mut self) -> Result<String, Box<dyn Error>> {
+        let (path, door) = self
+            .checkpoint
+            .clone()
+            .ok_or("Didn't find the security checkpoint.")?;
+        for direction in &path {
+            self.walk(direction)?;
+        }
+
+        let items = self.inventory.clone();
+        let n = items.len();
+        if n > MAX_ITEMS {
+            return Err(format!(
+                "Too many items ({}) to try every combination; use --avoid to leave some behind.",
+                n
+            )
+            .into());
+        }
+        let mut combinations: Vec<u32> = (0..1 << n).collect();
+        combinations.sort_by_key(|combination| {
+            let ones = combination.count_ones() as usize;
+            (ones.min(n - ones), n - ones)
+        });
+        let mut heavy: Vec<u32> = vec![];
+        let mut light: Vec<u32> = vec![];
+        let mut carrying = (1 << n) - 1;
+        let mut tried = 0;
+        for combination in combinations {
+            if heavy.iter().any(|&h| includes(combination, h))
+                || light.iter().any(|&l| includes(l, combination))
+            {
+                continue;
             }
-            8 => {
-                let (mode1, mode2, mode3) =
-                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::Equal(mode1, mode2, mode3))
+
+            for (i, item) in items.iter().enumerate() {
+                let bit = 1 << i;
+                if carrying & bit != 0 && combination & bit == 0 {
+                    self.command(&format!("drop {}", item))?;
+                } else if carrying & bit == 0 && combination & bit != 0 {
+                    self.command(&format!("take {}", item))?;
+                }
             }
-            9 => {
-                let mode = (code / 100).try_into().map_err(|_| InvalidOpcode(code))?;
-                Ok(Opcode::RelativeBaseOffset(mode))
+            carrying = combination;
+
+            tried += 1;
+            match self.weigh(&door)? {
+                (Weight::TooHeavy, _) => heavy.push(combination),
+                (Weight::TooLight, _) => light.push(combination),
+                (Weight::Right, text) => {
+                    let carried: Vec<_> = items
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| combination & 1 << i != 0)
+                        .map(|(_, item)| item.as_str())
+                        .collect();
+                    println!(
+                        "Tried {} combinations; carrying {}",
+                        tried,
+                        carried.join(", ")
+                    );
+                    return password(&text)
+                        .ok_or_else(|| format!("No password in:\n{}", text).into());
+                }
             }
-            99 => Ok(Opcode::Halt),
-            _ => Err(InvalidOpcode(code)),
         }
+        Err("No combination of items is the right weight.".into())
     }
 }
 
-#[derive(Clone)]
-struct Intcode {
-    code: Vec<i64>,
-    ip: usize,
-    relative_base: isize,
+/// Whether every item in `subset` is in `set`, where each bit is one item.
+fn includes(set: u32, subset: u32) -> bool {
+    set & subset == subset
 }
 
-impl Intcode {
-    fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
-        let code = reader
-            .split(b',')
-            .map(|code| match code {
-                Ok(code) => {
-                    let s = str::from_utf8(&code)?;
-                    Ok(s.trim().parse()?)
-                }
-                Err(e) => Err(Box::new(e) as Box<dyn Error>),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Intcode {
-            code,
-            ip: 0,
-            relative_base: 0,
-        })
+/// The password comes at the end: "... by typing 12345 on the keypad at the main airlock."
+fn password(text: &str) -> Option<String> {
+    let start = text.find("typing ")? + "typing ".len();
+    let password: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    if password.is_empty() {
+        None
+    } else {
+        Some(password)
     }
+}
+
+/// An interactive game. Lines starting with a colon are commands for us instead of the droid:
+/// `:save <file>` saves the game, `:load <file>` loads a saved game, and `:undo` takes back the
+/// last command.
+struct Session {
+    droid: AsciiMachine,
 
-    fn read(&mut self, i: usize) -> i64 {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
+    /// What the droid said last, so that it can be shown again after `:load` or `:undo`.
+    output: String,
+
+    /// The game before each command, for `:undo`.
+    history: Vec<Snapshot>,
+
+    /// Commands to run before reading any from stdin, and whether to show what they do.
+    queued: VecDeque<(String, bool)>,
+
+    /// Whether the command just sent came from a replay, so what it does shouldn't be shown.
+    quiet: bool,
+    transcript: Option<BufWriter<File>>,
+}
+
+impl Session {
+    fn new(droid: AsciiMachine) -> Session {
+        Session {
+            droid,
+            output: String::new(),
+            history: vec![],
+            queued: VecDeque::new(),
+            quiet: false,
+            transcript: None,
         }
-        self.code[i]
     }
 
-    fn write(&mut self, i: usize, v: i64) {
-        if i >= self.code.len() {
-            self.code.resize(i + 1, 0);
+    /// Queues every line of a script. Blank lines and lines starting with `#` are skipped.
+    fn queue(&mut self, script: &str, show: bool) {
+        for line in script.lines().map(str::trim) {
+            if !line.is_empty() && !line.starts_with('#') {
+                self.queued.push_back((line.to_string(), show));
+            }
         }
-        self.code[i] = v;
     }
 
-    fn output_operand(
-        &mut self,
-        sp: usize,
-        mode: ParameterMode,
-    ) -> Result<usize, InvalidOutputMode> {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => Ok(v as usize),
-            ParameterMode::Immediate => Err(InvalidOutputMode(mode)),
-            ParameterMode::Relative => Ok((v as isize + self.relative_base) as usize),
+    /// Prints `text`, unless we're replaying a transcript. Either way, it goes in the transcript.
+    fn show(&mut self, text: &str) -> io::Result<()> {
+        if !self.quiet {
+            println!("{}", text);
         }
+        if let Some(transcript) = &mut self.transcript {
+            writeln!(transcript, "{}", text)?;
+            transcript.flush()?;
+        }
+        Ok(())
     }
 
-    fn operand(&mut self, sp: usize, mode: ParameterMode) -> i64 {
-        let v = self.code[sp];
-        match mode {
-            ParameterMode::Position => self.read(v as usize),
-            ParameterMode::Immediate => v,
-            ParameterMode::Relative => self.read((v as isize + self.relative_base) as usize),
+    /// Returns the next line from the script, or from stdin once the script runs out, or `None`
+    /// at the end of stdin.
+    fn read_line(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        let next = self.queued.pop_front();
+
+        // a replay just ended, so show where it left off
+        if self.quiet && !matches!(next, Some((_, false))) {
+            println!("{}", self.output);
+        }
+        self.quiet = matches!(next, Some((_, false)));
+        if let Some((line, show)) = next {
+            if show {
+                println!("{}", line);
+            }
+            return Ok(Some(line));
         }
-    }
 
-    fn operands2(&mut self, sp: usize, mode1: ParameterMode, mode2: ParameterMode) -> (i64, i64) {
-        (self.operand(sp, mode1), self.operand(sp + 1, mode2))
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(&['\r', '\n'][..]).to_string()))
     }
 
-    fn run(&mut self, input: &Vec<i64>) -> Result<(Vec<i64>, bool), Box<dyn Error>> {
-        let mut output: Vec<i64> = Vec::new();
-        let mut inputp = 0;
-        let mut halted = false;
-        loop {
-            let opcode: Opcode = self.read(self.ip).try_into()?;
-            self.ip = match opcode {
-                Opcode::Add(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 + op2);
-                    self.ip + 4
-                }
-                Opcode::Multiply(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, op1 * op2);
-                    self.ip + 4
-                }
-                Opcode::Input(mode) => {
-                    if inputp >= input.len() {
-                        // not enough input; suspend
-                        break;
-                    }
-
-                    let op = self.output_operand(self.ip + 1, mode)?;
-                    self.write(op, input[inputp]);
-                    inputp += 1;
-                    self.ip + 2
-                }
-                Opcode::Output(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    output.push(op);
-                    self.ip + 2
-                }
-                Opcode::JumpIfTrue(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 != 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::JumpIfFalse(mode1, mode2) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    if op1 == 0 {
-                        op2 as usize
-                    } else {
-                        self.ip + 3
-                    }
-                }
-                Opcode::LessThan(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 < op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::Equal(mode1, mode2, mode3) => {
-                    let (op1, op2) = self.operands2(self.ip + 1, mode1, mode2);
-                    let op3 = self.output_operand(self.ip + 3, mode3)?;
-                    self.write(op3 as usize, if op1 == op2 { 1 } else { 0 });
-                    self.ip + 4
-                }
-                Opcode::RelativeBaseOffset(mode) => {
-                    let op = self.operand(self.ip + 1, mode);
-                    self.relative_base += op as isize;
-                    self.ip + 2
-                }
-                Opcode::Halt => {
-                    halted = true;
-                    break;
+    /// Reads lines until one of them is a command for the droid, and sends it. Returns false if
+    /// there's nothing left to read.
+    fn read_input(&mut self) -> Result<bool, Box<dyn Error>> {
+        while let Some(line) = self.read_line()? {
+            if let Some(transcript) = &mut self.transcript {
+                writeln!(transcript, "> {}", line)?;
+            }
+            if let Some(command) = line.strip_prefix(':') {
+                let mut parts = command.trim().splitn(2, ' ');
+                let result = match (parts.next(), parts.next()) {
+                    (Some("save"), Some(path)) => self.save(path),
+                    (Some("load"), Some(path)) => self.load(path),
+                    (Some("undo"), None) => self.undo(),
+                    _ => Err("Commands are :save <file>, :load <file>, and :undo".into()),
+                };
+                if let Err(e) = result {
+                    self.show(&e.to_string())?;
                 }
-            };
+                continue;
+            }
+
+            self.history.push(self.snapshot());
+            self.droid.send_line(&line);
+            return Ok(true);
         }
-        Ok((output, halted))
+        Ok(false)
     }
-}
 
-fn print_output(output: &Vec<i64>) {
-    println!(
-        "{}",
-        output
+    /// The droid is always waiting for a command when we take a snapshot, so there's never any
+    /// pending input. The last output is kept in the snapshot so that it can be shown again.
+    fn snapshot(&self) -> Snapshot {
+        let mut snapshot = self.droid.program.snapshot();
+        snapshot.output = ascii(&self.output).collect();
+        snapshot
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        self.droid.program.restore(snapshot);
+        self.droid.input.clear();
+        self.output = snapshot
+            .output
             .iter()
             .map(|&c| char::from(c as u8))
-            .collect::<String>()
-    );
+            .collect();
+        let output = self.output.clone();
+        self.show(&output)
+    }
+
+    fn save(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.snapshot().save(File::create(path)?)?;
+        self.show("Saved.")?;
+        Ok(())
+    }
+
+    /// Loading can be undone, too.
+    fn load(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let snapshot = Snapshot::load(BufReader::new(File::open(path)?))?;
+        self.history.push(self.snapshot());
+        self.restore(&snapshot)?;
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), Box<dyn Error>> {
+        let snapshot = self.history.pop().ok_or("There's nothing to undo.")?;
+        self.restore(&snapshot)?;
+        Ok(())
+    }
+
+    fn play(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            let output = self.droid.read_until_prompt()?;
+            self.output = output.text;
+            let text = self.output.clone();
+            self.show(&text)?;
+            if output.state.is_halted() || !self.read_input()? {
+                return Ok(());
+            }
+        }
+    }
 }
 
-fn read_input(input: &mut Vec<i64>) -> Result<(), Box<dyn Error>> {
-    let stdio = io::stdin();
-    let mut s = String::new();
-    stdio.read_line(&mut s)?;
-    input.clear();
-    for c in s.bytes() {
-        input.push(c as i64);
+/// Use `--play` to play the game interactively: find items to make the droid heavier, then, at
+/// the security checkpoint, figure out which ones to drop to make the droid the right weight.
+/// `--script <file>` runs the commands in a file first, and `--replay <file>` quietly runs the
+/// commands in a transcript that `--transcript <file>` recorded, to get back to where it left
+/// off. Either way, the game carries on from stdin afterwards.
+fn play(program: Intcode, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut session = Session::new(AsciiMachine::new(program));
+    if let Some(path) = &options.replay {
+        let transcript = fs::read_to_string(path)?;
+        let commands: Vec<_> = transcript
+            .lines()
+            .filter_map(|line| line.strip_prefix("> "))
+            .collect();
+        session.queue(&commands.join("\n"), false);
+
+        // the game's introduction comes before the first command is replayed
+        session.quiet = true;
     }
-    Ok(())
+    if let Some(path) = &options.script {
+        session.queue(&fs::read_to_string(path)?, true);
+    }
+    if let Some(path) = &options.transcript {
+        session.transcript = Some(BufWriter::new(File::create(path)?));
+    }
+    session.play()
 }
 
-/// This puzzle is manual... run the program and interactively play the game. You'll need to find
-/// items to make your droid heavier. Then, when you find the security check point, you'll need to
-/// figure out what items to drop to make your droid the right weight. It'll take trail-and-error.
+/// Otherwise, the droid explores the ship, picks up everything that won't kill it (`--avoid`
+/// replaces the list of items that will), and tries combinations of items on the
+/// pressure-sensitive floor until it's let through.
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_options()?;
     let file = File::open("input.txt")?;
     let reader = BufReader::new(file);
-    let mut program = Intcode::load(reader)?;
-    let mut input = Vec::new();
-    loop {
-        let (output, halt) = program.run(&input)?;
-        print_output(&output);
-        if halt {
-            break;
-        }
-
-        read_input(&mut input)?;
+    let program = Intcode::load(reader)?;
+    if options.play {
+        return play(program, &options);
     }
 
+    let mut explorer = Explorer::new(program, options.avoid);
+    let output = explorer.droid.read_until_prompt()?;
+    let start = Room::parse(&output.text).ok_or("The droid didn't start in a room.")?;
+    explorer.explore(start, &mut vec![])?;
+    println!("Explored {} rooms.", explorer.rooms.len());
+
+    let password = explorer.unlock()?;
+    println!("Password: {}", password);
     Ok(())
 }
@@ -0,0 +1,92 @@
+//! Times the Intcode VM on a couple of real workloads: day 9's BOOST program in sensor boost mode,
+//! which runs a few hundred thousand instructions in one go, and day 19's tractor beam scan and
+//! search, which restores and re-runs a small program thousands of times.
+//!
+//! Run with `cargo bench` from the `intcode` directory. Each benchmark is skipped if its puzzle
+//! input isn't where it's expected to be.
+
+use intcode::{Intcode, Snapshot};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Loads a puzzle input from one of the day directories next to this crate.
+fn load(day: &str) -> Option<Intcode> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(day)
+        .join("input.txt");
+    let file = File::open(&path).ok()?;
+    Intcode::load(BufReader::new(file)).ok()
+}
+
+/// Calls `f` repeatedly for about a second and prints the average time per call.
+fn bench<F: FnMut() -> Result<(), Box<dyn Error>>>(
+    name: &str,
+    mut f: F,
+) -> Result<(), Box<dyn Error>> {
+    // warm up
+    f()?;
+
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < Duration::from_secs(1) {
+        f()?;
+        runs += 1;
+    }
+    let average = start.elapsed() / runs;
+    println!("{:<16} {:>10.3?} per run ({} runs)", name, average, runs);
+    Ok(())
+}
+
+fn beam(program: &mut Intcode, start: &Snapshot, x: i64, y: i64) -> Result<bool, Box<dyn Error>> {
+    program.restore(start);
+    let (output, _) = program.run_batch(&[x, y])?;
+    Ok(output[0] == 1)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match load("day09") {
+        Some(program) => bench("day09 boost", || {
+            let (output, _) = program.clone().run_batch(&[2])?;
+            assert_eq!(output.len(), 1);
+            Ok(())
+        })?,
+        None => println!("day09 boost      skipped: no input"),
+    }
+
+    match load("day19") {
+        Some(mut program) => {
+            let start = program.snapshot();
+            bench("day19 scan", || {
+                let mut count = 0;
+                for y in 0..50 {
+                    for x in 0..50 {
+                        if beam(&mut program, &start, x, y)? {
+                            count += 1;
+                        }
+                    }
+                }
+                assert!(count > 0);
+                Ok(())
+            })?;
+            bench("day19 search", || {
+                let (mut min, mut max) = (100, 10_000);
+                while min < max {
+                    let y = (min + max) / 2;
+                    if beam(&mut program, &start, 2 * y - 99, y + 99)? {
+                        max = y;
+                    } else {
+                        min = y + 1;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        None => println!("day19            skipped: no input"),
+    }
+
+    Ok(())
+}
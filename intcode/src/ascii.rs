@@ -0,0 +1,92 @@
+use crate::error::AsciiFault;
+use crate::io::ascii;
+use crate::machine::Intcode;
+use crate::state::RunState;
+use std::collections::VecDeque;
+use std::iter;
+
+/// Everything an ASCII program output between two prompts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsciiOutput {
+    /// The output that was ASCII text.
+    pub text: String,
+
+    /// Output that wasn't ASCII, in the order it was output. Programs use this to report an
+    /// answer, ie, the amount of dust collected on day 17 or the hull damage on day 21.
+    pub values: Vec<i64>,
+
+    /// Why the program stopped.
+    pub state: RunState,
+}
+
+impl AsciiOutput {
+    /// The last non-ASCII value the program output, if it output any.
+    pub fn value(&self) -> Option<i64> {
+        self.values.last().copied()
+    }
+}
+
+/// An adapter for programs that talk in lines of ASCII text, like the camera on day 17, the
+/// springdroid on day 21, and the droid on day 25.
+///
+/// ```
+/// # use intcode::{AsciiMachine, Intcode};
+/// // echoes a character back, then outputs 1000
+/// let program = Intcode::load("3,100,4,100,104,1000,99".as_bytes()).unwrap();
+/// let mut machine = AsciiMachine::new(program);
+/// machine.send_line("x");
+/// let output = machine.read_until_prompt().unwrap();
+/// assert_eq!(output.text, "x");
+/// assert_eq!(output.value(), Some(1000));
+/// ```
+pub struct AsciiMachine {
+    pub program: Intcode,
+
+    /// Input that has been sent but not read by the program yet.
+    pub input: VecDeque<i64>,
+}
+
+impl AsciiMachine {
+    pub fn new(program: Intcode) -> AsciiMachine {
+        AsciiMachine {
+            program,
+            input: VecDeque::new(),
+        }
+    }
+
+    /// Queues `line` as input, followed by a newline. The program reads it the next time it runs.
+    pub fn send_line(&mut self, line: &str) {
+        self.input.extend(ascii(line));
+        self.input.push_back(10);
+    }
+
+    /// Runs the program until it prompts for input that hasn't been sent yet, or halts, and
+    /// returns everything it output along the way. If it faults, what it output is part of the
+    /// error.
+    pub fn read_until_prompt(&mut self) -> Result<AsciiOutput, AsciiFault> {
+        let mut text = String::new();
+        let mut values = Vec::new();
+        let input = &mut self.input;
+        let state =
+            self.program
+                .run_io(&mut (iter::from_fn(|| input.pop_front()), |value: i64| {
+                    if (0..128).contains(&value) {
+                        text.push(char::from(value as u8));
+                    } else {
+                        values.push(value);
+                    }
+                }));
+        match state {
+            RunState::Fault(fault) => Err(AsciiFault {
+                fault,
+                text,
+                values,
+            }),
+            state => Ok(AsciiOutput {
+                text,
+                values,
+                state,
+            }),
+        }
+    }
+}
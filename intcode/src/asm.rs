@@ -0,0 +1,386 @@
+use crate::error::AssembleError;
+use crate::opcode::{Opcode, ParameterMode};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Every opcode the VM understands, used to look up mnemonics.
+const OPCODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+/// A term in an expression: a number or a reference to a label.
+enum Atom {
+    Number(i64),
+    Label(String),
+}
+
+/// A sum of atoms, resolved once every label's address is known.
+struct Expr(Vec<(i64, Atom)>);
+
+struct Operand {
+    mode: ParameterMode,
+    value: Expr,
+}
+
+enum Statement {
+    Instruction { code: i64, operands: Vec<Operand> },
+    Data(Vec<Expr>),
+}
+
+impl Statement {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction { operands, .. } => operands.len() + 1,
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+/// A cursor over a single line of source.
+struct Parser<'a> {
+    line: usize,
+    s: &'a str,
+}
+
+fn is_label_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: String) -> Result<T, AssembleError> {
+        Err(AssembleError {
+            line: self.line,
+            message,
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.s = self.s.trim_start();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s.chars().next()
+    }
+
+    /// Consumes `token` if the remaining source starts with it.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.s.starts_with(token) {
+            self.s = &self.s[token.len()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes a minus sign, but not the start of an arrow.
+    fn eat_minus(&mut self) -> bool {
+        self.skip_whitespace();
+        !self.s.starts_with("->") && self.eat("-")
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), AssembleError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{}`", token))
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.s.is_empty() || self.s.starts_with(';')
+    }
+
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let end = self.s.find(|c| !is_label_char(c)).unwrap_or(self.s.len());
+        let (word, rest) = self.s.split_at(end);
+        self.s = rest;
+        word
+    }
+
+    /// Parses the character after a backslash in a string or character literal.
+    fn escape(&mut self) -> Result<char, AssembleError> {
+        let c = self.next_char()?;
+        Ok(match c {
+            'n' => '\n',
+            't' => '\t',
+            '0' => '\0',
+            '\\' | '\'' | '"' => c,
+            _ => return self.error(format!("unknown escape `\\{}`", c)),
+        })
+    }
+
+    fn next_char(&mut self) -> Result<char, AssembleError> {
+        match self.peek() {
+            Some(c) => {
+                self.s = &self.s[c.len_utf8()..];
+                Ok(c)
+            }
+            None => self.error("unexpected end of line".to_string()),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Atom, AssembleError> {
+        if self.eat("'") {
+            let c = match self.next_char()? {
+                '\\' => self.escape()?,
+                c => c,
+            };
+            self.expect("'")?;
+            return Ok(Atom::Number(c as i64));
+        }
+
+        let word = self.word();
+        match word.chars().next() {
+            None => self.error("expected a number or label".to_string()),
+            Some(c) if c.is_ascii_digit() => match word.parse() {
+                Ok(n) => Ok(Atom::Number(n)),
+                Err(_) => self.error(format!("{} is not a valid number", word)),
+            },
+            Some(_) => Ok(Atom::Label(word.to_string())),
+        }
+    }
+
+    /// expr := ['-'] atom (('+' | '-') atom)*
+    fn expr(&mut self) -> Result<Expr, AssembleError> {
+        let mut sign = if self.eat_minus() { -1 } else { 1 };
+        let mut terms = vec![(sign, self.atom()?)];
+        loop {
+            sign = if self.eat("+") {
+                1
+            } else if self.eat_minus() {
+                -1
+            } else {
+                return Ok(Expr(terms));
+            };
+            terms.push((sign, self.atom()?));
+        }
+    }
+
+    /// operand := '#' expr | '[' 'rb' [('+' | '-') expr] ']' | '[' expr ']'
+    fn operand(&mut self) -> Result<Operand, AssembleError> {
+        if self.eat("#") {
+            return Ok(Operand {
+                mode: ParameterMode::Immediate,
+                value: self.expr()?,
+            });
+        }
+
+        self.expect("[")?;
+        self.skip_whitespace();
+        let relative = self.s.starts_with("rb") && !self.s[2..].starts_with(is_label_char);
+        let operand = if relative {
+            self.s = &self.s[2..];
+            let value = if self.eat("+") {
+                self.expr()?
+            } else if self.eat("-") {
+                let Expr(terms) = self.expr()?;
+                Expr(
+                    terms
+                        .into_iter()
+                        .map(|(sign, atom)| (-sign, atom))
+                        .collect(),
+                )
+            } else {
+                Expr(vec![(1, Atom::Number(0))])
+            };
+            Operand {
+                mode: ParameterMode::Relative,
+                value,
+            }
+        } else {
+            Operand {
+                mode: ParameterMode::Position,
+                value: self.expr()?,
+            }
+        };
+        self.expect("]")?;
+        Ok(operand)
+    }
+
+    fn string(&mut self) -> Result<Vec<Expr>, AssembleError> {
+        let mut values = Vec::new();
+        loop {
+            let c = match self.next_char()? {
+                '"' => return Ok(values),
+                '\\' => self.escape()?,
+                c => c,
+            };
+            values.push(Expr(vec![(1, Atom::Number(c as i64))]));
+        }
+    }
+
+    /// data := item (',' item)*, where each item is an expr or a string
+    fn data(&mut self) -> Result<Statement, AssembleError> {
+        let mut values = Vec::new();
+        loop {
+            if self.eat("\"") {
+                values.extend(self.string()?);
+            } else {
+                values.push(self.expr()?);
+            }
+            if !self.eat(",") {
+                return Ok(Statement::Data(values));
+            }
+        }
+    }
+
+    fn instruction(&mut self, mnemonic: &str) -> Result<Statement, AssembleError> {
+        let opcode = OPCODES
+            .iter()
+            .filter_map(|&code| Opcode::try_from(code).ok().map(|op| (code, op)))
+            .find(|(_, op)| op.mnemonic().eq_ignore_ascii_case(mnemonic));
+        let (code, opcode) = match opcode {
+            Some(opcode) => opcode,
+            None => return self.error(format!("unknown instruction `{}`", mnemonic)),
+        };
+
+        // operands are separated by commas, except for the destination of an instruction that
+        // writes, which follows an arrow
+        let count = opcode.modes().len();
+        let inputs = if opcode.writes() { count - 1 } else { count };
+        let mut operands = Vec::new();
+        for i in 0..count {
+            if i == inputs {
+                self.expect("->")?;
+            } else if i > 0 {
+                self.expect(",")?;
+            }
+            operands.push(self.operand()?);
+        }
+        if opcode.writes() && operands[count - 1].mode == ParameterMode::Immediate {
+            return self.error(format!("`{}` cannot write to an immediate", mnemonic));
+        }
+
+        Ok(Statement::Instruction { code, operands })
+    }
+}
+
+/// Parses a line into its labels and its statement, if it has one.
+fn parse_line(line: usize, s: &str) -> Result<(Vec<&str>, Option<Statement>), AssembleError> {
+    let mut parser = Parser { line, s };
+    let mut labels = Vec::new();
+    loop {
+        if parser.at_end() {
+            return Ok((labels, None));
+        }
+        let word = parser.word();
+        if word.is_empty() {
+            return parser.error(format!("unexpected `{}`", parser.s));
+        }
+        if parser.eat(":") {
+            labels.push(word);
+            continue;
+        }
+
+        let statement = if word.eq_ignore_ascii_case("data") {
+            parser.data()?
+        } else {
+            parser.instruction(word)?
+        };
+        if !parser.at_end() {
+            return parser.error(format!("unexpected `{}`", parser.s.trim()));
+        }
+        return Ok((labels, Some(statement)));
+    }
+}
+
+fn resolve(line: usize, expr: &Expr, labels: &HashMap<&str, usize>) -> Result<i64, AssembleError> {
+    let mut value: i64 = 0;
+    for (sign, atom) in &expr.0 {
+        let n = match atom {
+            Atom::Number(n) => *n,
+            Atom::Label(label) => match labels.get(label.as_str()) {
+                Some(&address) => address as i64,
+                None => {
+                    return Err(AssembleError {
+                        line,
+                        message: format!("undefined label `{}`", label),
+                    })
+                }
+            },
+        };
+        value = n
+            .checked_mul(*sign)
+            .and_then(|n| value.checked_add(n))
+            .ok_or_else(|| AssembleError {
+                line,
+                message: "value doesn't fit in 64 bits".to_string(),
+            })?;
+    }
+    Ok(value)
+}
+
+/// Assembles a program written in the same syntax that the disassembler prints, ie:
+///
+/// ```text
+/// ; outputs the numbers 10 through 1
+/// start:  OUT [count]
+///         ADD [count], #-1 -> [count]
+///         JT [count], #start
+///         HLT
+/// count:  DATA 10
+/// ```
+///
+/// Operands are `#value` for immediate mode, `[address]` for position mode, and `[rb+offset]` for
+/// relative mode. Values may be numbers, character literals like `'A'`, labels, or sums and
+/// differences of those. `DATA` emits its values directly, and strings in `DATA` are emitted as
+/// ASCII. Everything after a `;` is a comment.
+///
+/// A label that is a number asserts that the next statement starts at that address, so the
+/// output of the disassembler can be assembled again.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssembleError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = 0;
+    for (i, s) in source.lines().enumerate() {
+        let line = i + 1;
+        let (line_labels, statement) = parse_line(line, s)?;
+        for label in line_labels {
+            if label.starts_with(|c: char| c.is_ascii_digit()) {
+                if label.parse() != Ok(address) {
+                    return Err(AssembleError {
+                        line,
+                        message: format!("`{}:` is actually at address {}", label, address),
+                    });
+                }
+            } else if labels.insert(label, address).is_some() {
+                return Err(AssembleError {
+                    line,
+                    message: format!("label `{}` is already defined", label),
+                });
+            }
+        }
+        if let Some(statement) = statement {
+            address += statement.size();
+            statements.push((line, statement));
+        }
+    }
+
+    let mut code = Vec::with_capacity(address);
+    for (line, statement) in statements {
+        match statement {
+            Statement::Instruction { code: op, operands } => {
+                let mut modes = 0;
+                for operand in operands.iter().rev() {
+                    modes = modes * 10
+                        + match operand.mode {
+                            ParameterMode::Position => 0,
+                            ParameterMode::Immediate => 1,
+                            ParameterMode::Relative => 2,
+                        };
+                }
+                code.push(modes * 100 + op);
+                for operand in operands {
+                    code.push(resolve(line, &operand.value, &labels)?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in values {
+                    code.push(resolve(line, &value, &labels)?);
+                }
+            }
+        }
+    }
+    Ok(code)
+}
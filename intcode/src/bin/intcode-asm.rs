@@ -0,0 +1,18 @@
+//! Assembles an Intcode program and prints it in the comma-separated format that
+//! `Intcode::load` reads.
+//!
+//! Usage: `intcode-asm <source>`. See `assemble` for the syntax.
+
+use intcode::assemble;
+use std::env;
+use std::error::Error;
+use std::fs;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = env::args().nth(1).ok_or("Usage: intcode-asm <source>")?;
+    let source = fs::read_to_string(&path)?;
+    let code = assemble(&source)?;
+    let code: Vec<_> = code.iter().map(i64::to_string).collect();
+    println!("{}", code.join(","));
+    Ok(())
+}
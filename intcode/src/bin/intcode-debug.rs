@@ -0,0 +1,393 @@
+//! An interactive debugger for Intcode programs.
+//!
+//! Usage: `intcode-debug [program]`, where `program` defaults to `input.txt`. The program is
+//! loaded and paused at its first instruction. Type `help` at the prompt for a list of commands.
+//! Program output is printed as it's produced: ASCII values are printed as text, anything else is
+//! printed as a number on its own line. Input is queued with the `input` and `ascii` commands; if
+//! the program runs out of input, the debugger pauses.
+
+use intcode::{Instruction, Intcode, Opcode, RunState, Snapshot};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::iter;
+
+/// The most cells `x` shows at once, so that a typo doesn't print for practically forever.
+const MAX_EXAMINE: usize = 1000;
+
+const HELP: &str = "\
+Commands:
+  s, step [n]          execute n instructions (default 1)
+  c, continue          run until a breakpoint, watchpoint, input, or halt
+  b, break <addr>      break before executing the instruction at addr
+  b, break op <op>     break before executing any op (ie, `ADD` or `1`)
+  w, watch <addr>      break after the value at addr changes
+  d, delete <addr>     delete the breakpoint or watchpoint at addr
+  d, delete op <op>    delete the breakpoint on op
+  breaks               list breakpoints and watchpoints
+  r, regs              show ip and relative base
+  l, list [n]          disassemble n instructions around ip (default 10)
+  x <addr> [n]         show n memory cells starting at addr (default 1, at most 1000)
+  set <addr> <value>   write value to addr
+  i, input <v>,...     queue numbers as input
+  a, ascii <text>      queue text as ASCII input, followed by a newline
+  save <file>          save the program and any queued input to a file
+  load <file>          load a program saved with `save`
+  h, help              show this help
+  q, quit              exit";
+
+/// Why the debugger paused the program.
+enum Pause {
+    Breakpoint(usize),
+    OpcodeBreakpoint(i64),
+    Watchpoint(usize, i64, i64),
+    State(RunState),
+}
+
+struct Debugger {
+    program: Intcode,
+    input: VecDeque<i64>,
+    opcode_breakpoints: HashSet<i64>,
+    watchpoints: HashMap<usize, i64>,
+    halted: bool,
+}
+
+fn print_output(value: i64) {
+    if (0..128).contains(&value) {
+        print!("{}", char::from(value as u8));
+    } else {
+        println!("{}", value);
+    }
+    io::stdout().flush().ok();
+}
+
+/// Opcodes may be given as a number or as the mnemonic used in disassembly.
+fn parse_opcode(s: &str) -> Result<i64, Box<dyn Error>> {
+    if let Ok(code) = s.parse::<i64>() {
+        Opcode::try_from(code)?;
+        return Ok(code % 100);
+    }
+    [1, 2, 3, 4, 5, 6, 7, 8, 9, 99]
+        .iter()
+        .copied()
+        .find(|&code| {
+            Opcode::try_from(code)
+                .map(|op| op.mnemonic().eq_ignore_ascii_case(s))
+                .unwrap_or(false)
+        })
+        .ok_or_else(|| format!("{} is not an opcode.", s).into())
+}
+
+fn parse_address(s: Option<&str>) -> Result<usize, Box<dyn Error>> {
+    Ok(s.ok_or("Expected an address.")?.parse()?)
+}
+
+impl Debugger {
+    fn new(program: Intcode) -> Debugger {
+        Debugger {
+            program,
+            input: VecDeque::new(),
+            opcode_breakpoints: HashSet::new(),
+            watchpoints: HashMap::new(),
+            halted: false,
+        }
+    }
+
+    fn check_breakpoints(&self) -> Option<Pause> {
+        let ip = self.program.ip;
+        if self.program.breakpoints.contains(&ip) {
+            return Some(Pause::Breakpoint(ip));
+        }
+        let code = self.program.code.get(ip) % 100;
+        if self.opcode_breakpoints.contains(&code) {
+            return Some(Pause::OpcodeBreakpoint(code));
+        }
+        None
+    }
+
+    fn check_watchpoints(&mut self) -> Option<Pause> {
+        for (&address, old) in self.watchpoints.iter_mut() {
+            let new = self.program.code.get(address);
+            if *old != new {
+                let pause = Pause::Watchpoint(address, *old, new);
+                *old = new;
+                return Some(pause);
+            }
+        }
+        None
+    }
+
+    /// Executes one instruction, printing any output. Returns `Some` if the program can't
+    /// continue, or if it tripped a watchpoint.
+    fn step(&mut self) -> Option<Pause> {
+        if self.halted {
+            return Some(Pause::State(RunState::Halted {
+                ip: self.program.ip,
+            }));
+        }
+
+        let input = &mut self.input;
+        match self.program.step(&mut iter::from_fn(|| input.pop_front())) {
+            None => (),
+            Some(RunState::Output { value, .. }) => print_output(value),
+            Some(state) => {
+                self.halted = state.is_halted();
+                return Some(Pause::State(state));
+            }
+        }
+        self.check_watchpoints()
+    }
+
+    /// Runs until something pauses the program. Breakpoints on the current instruction are
+    /// ignored so that continuing from a breakpoint makes progress. Running 0 steps does nothing.
+    fn resume(&mut self, steps: Option<usize>) -> Option<Pause> {
+        if steps == Some(0) {
+            return None;
+        }
+        let mut count = 0;
+        loop {
+            if let Some(pause) = self.step() {
+                return Some(pause);
+            }
+            count += 1;
+            if steps == Some(count) {
+                return None;
+            }
+            if let Some(pause) = self.check_breakpoints() {
+                return Some(pause);
+            }
+        }
+    }
+
+    fn report(&self, pause: Option<Pause>) {
+        match pause {
+            None => (),
+            Some(Pause::Breakpoint(ip)) => println!("Breakpoint at {}", ip),
+            Some(Pause::OpcodeBreakpoint(code)) => println!("Breakpoint on opcode {}", code),
+            Some(Pause::Watchpoint(address, old, new)) => {
+                println!("Watchpoint: [{}] changed from {} to {}", address, old, new)
+            }
+            Some(Pause::State(state)) => println!("Program {}", state),
+        }
+        self.list(1);
+    }
+
+    fn registers(&self) {
+        println!(
+            "ip: {}  relative base: {}",
+            self.program.ip, self.program.relative_base
+        );
+    }
+
+    /// Disassembles `count` instructions starting at `address`. Instructions that can't be
+    /// decoded are printed as raw data.
+    fn list_at(&self, code: &[i64], mut address: usize, count: usize) {
+        for _ in 0..count {
+            if address >= code.len() {
+                break;
+            }
+            let marker = if address == self.program.ip {
+                "=>"
+            } else {
+                "  "
+            };
+            match Instruction::decode(code, address) {
+                Ok(instruction) => {
+                    println!("{} {}", marker, instruction);
+                    address += instruction.size();
+                }
+                Err(_) => {
+                    println!("{} {:04}: DATA {}", marker, address, code[address]);
+                    address += 1;
+                }
+            }
+        }
+    }
+
+    /// Addresses of the instructions between `start` and `ip`, if decoding from `start` lands
+    /// exactly on `ip`.
+    fn instructions_between(code: &[i64], mut start: usize, ip: usize) -> Option<Vec<usize>> {
+        let mut addresses = Vec::new();
+        while start < ip {
+            addresses.push(start);
+            start += Instruction::decode(code, start).ok()?.size();
+        }
+        if start == ip {
+            Some(addresses)
+        } else {
+            None
+        }
+    }
+
+    /// Disassembly can't run backwards, so we try decoding from a few cells before ip until we
+    /// find a starting point that lands on ip. Most of the time, one does.
+    fn list(&self, count: usize) {
+        let code = self.program.code.to_vec();
+        let ip = self.program.ip;
+        let before = (ip.saturating_sub(count * 2)..ip)
+            .find_map(|start| Debugger::instructions_between(&code, start, ip))
+            .unwrap_or_default();
+        let skip = before.len().saturating_sub(count / 2);
+        let start = before.get(skip).copied().unwrap_or(ip);
+        self.list_at(&code, start, count);
+    }
+
+    /// Prints `count` cells starting at `address`, stopping at the address limit, or after
+    /// `MAX_EXAMINE` cells.
+    fn examine(&self, address: usize, count: usize) -> Result<(), Box<dyn Error>> {
+        let address = self.program.code.address(address as i128)?;
+        let limit = self.program.code.limit();
+        let end = address
+            .checked_add(count.min(MAX_EXAMINE))
+            .map_or(limit, |end| end.min(limit));
+        for a in address..end {
+            println!("[{}] = {}", a, self.program.code.get(a));
+        }
+        if end - address < count {
+            println!("(showed {} of {} cells)", end - address, count);
+        }
+        Ok(())
+    }
+
+    fn list_breakpoints(&self) {
+        let mut breakpoints: Vec<_> = self.program.breakpoints.iter().collect();
+        breakpoints.sort();
+        for ip in breakpoints {
+            println!("break {}", ip);
+        }
+        let mut opcodes: Vec<_> = self.opcode_breakpoints.iter().collect();
+        opcodes.sort();
+        for code in opcodes {
+            println!("break op {}", code);
+        }
+        let mut watchpoints: Vec<_> = self.watchpoints.keys().collect();
+        watchpoints.sort();
+        for address in watchpoints {
+            println!("watch {}", address);
+        }
+    }
+
+    /// Executes a single command. Returns false if the debugger should exit.
+    fn command(&mut self, line: &str) -> Result<bool, Box<dyn Error>> {
+        let line = line.trim();
+        let (command, rest) = match line.find(' ') {
+            Some(i) => (&line[..i], line[i + 1..].trim()),
+            None => (line, ""),
+        };
+        let mut args = rest.split_whitespace();
+        match command {
+            "" => (),
+            "s" | "step" => {
+                let steps = args.next().map(|n| n.parse()).transpose()?.unwrap_or(1);
+                let pause = self.resume(Some(steps));
+                self.report(pause);
+            }
+            "c" | "continue" => {
+                let pause = self.resume(None);
+                self.report(pause);
+            }
+            "b" | "break" => match args.next() {
+                Some("op") => {
+                    let code = parse_opcode(args.next().ok_or("Expected an opcode.")?)?;
+                    self.opcode_breakpoints.insert(code);
+                }
+                address => {
+                    self.program.breakpoints.insert(parse_address(address)?);
+                }
+            },
+            "w" | "watch" => {
+                let address = parse_address(args.next())?;
+                self.watchpoints
+                    .insert(address, self.program.code.get(address));
+            }
+            "d" | "delete" => match args.next() {
+                Some("op") => {
+                    let code = parse_opcode(args.next().ok_or("Expected an opcode.")?)?;
+                    self.opcode_breakpoints.remove(&code);
+                }
+                address => {
+                    let address = parse_address(address)?;
+                    self.program.breakpoints.remove(&address);
+                    self.watchpoints.remove(&address);
+                }
+            },
+            "breaks" => self.list_breakpoints(),
+            "r" | "regs" => self.registers(),
+            "l" | "list" => {
+                let count = args.next().map(|n| n.parse()).transpose()?.unwrap_or(10);
+                self.list(count);
+            }
+            "x" => {
+                let address = parse_address(args.next())?;
+                let count = args.next().map(|n| n.parse()).transpose()?.unwrap_or(1);
+                self.examine(address, count)?;
+            }
+            "set" => {
+                let address = parse_address(args.next())?;
+                let value: i64 = args.next().ok_or("Expected a value.")?.parse()?;
+                let address = self.program.code.address(address as i128)?;
+                self.program.code[address] = value;
+            }
+            "i" | "input" => {
+                for value in rest.split(|c: char| c == ',' || c.is_whitespace()) {
+                    if !value.is_empty() {
+                        self.input.push_back(value.parse()?);
+                    }
+                }
+            }
+            "a" | "ascii" => {
+                self.input.extend(rest.bytes().map(i64::from));
+                self.input.push_back(10);
+            }
+            "save" => {
+                let mut snapshot = self.program.snapshot();
+                snapshot.input = self.input.iter().copied().collect();
+                snapshot.save(File::create(rest)?)?;
+            }
+            "load" => {
+                let snapshot = Snapshot::load(BufReader::new(File::open(rest)?))?;
+                self.program.restore(&snapshot);
+                self.input = snapshot.input.into_iter().collect();
+                self.halted = false;
+                self.registers();
+                self.list(1);
+            }
+            "h" | "help" => println!("{}", HELP),
+            "q" | "quit" => return Ok(false),
+            _ => println!("Unknown command {}; try `help`.", command),
+        }
+        Ok(true)
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "input.txt".to_string());
+    let file = File::open(&path)?;
+    let reader = BufReader::new(file);
+    let mut debugger = Debugger::new(Intcode::load(reader)?);
+    debugger.registers();
+    debugger.list(1);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(intcode) ");
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        match debugger.command(&line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+//! Prints a disassembly listing of an Intcode program.
+//!
+//! Usage: `intcode-disasm [program]`, where `program` defaults to `input.txt`. See `Listing` for
+//! how code is separated from data.
+
+use intcode::{Intcode, Listing};
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "input.txt".to_string());
+    let file = File::open(&path)?;
+    let reader = BufReader::new(file);
+    let program = Intcode::load(reader)?;
+    print!("{}", Listing::new(&program.code.to_vec()));
+    Ok(())
+}
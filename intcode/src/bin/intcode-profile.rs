@@ -0,0 +1,66 @@
+//! Runs an Intcode program and prints a profile of where it spent its time.
+//!
+//! Usage: `intcode-profile [--trace <file>] <program> [input]`, where `input` is a comma-separated
+//! list of numbers to give the program, ie, `intcode-profile ../day09/input.txt 2`. Anything the
+//! program outputs is printed before the profile. With `--trace`, every executed instruction is
+//! also written to `file` as a line of JSON; see `TraceLog` for the format.
+
+use intcode::{Event, Intcode, Profile, TraceLog, Tracer};
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::sync::{Arc, Mutex};
+
+const USAGE: &str = "Usage: intcode-profile [--trace <file>] <program> [input]";
+
+/// Passes each event on to both the profile and the trace log.
+struct Both<A, B>(A, B);
+
+impl<A: Tracer, B: Tracer> Tracer for Both<A, B> {
+    fn trace(&mut self, event: &Event) {
+        self.0.trace(event);
+        self.1.trace(event);
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1).peekable();
+    let trace = if args.peek().map(String::as_str) == Some("--trace") {
+        args.next();
+        Some(args.next().ok_or(USAGE)?)
+    } else {
+        None
+    };
+    let path = args.next().ok_or(USAGE)?;
+    let input = match args.next() {
+        Some(input) => input
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<Vec<i64>, _>>()?,
+        None => Vec::new(),
+    };
+
+    let file = File::open(&path)?;
+    let reader = BufReader::new(file);
+    let mut program = Intcode::load(reader)?;
+    let profile = Arc::new(Mutex::new(Profile::default()));
+    program.tracer = Some(match trace {
+        Some(trace) => {
+            let log = TraceLog::new(BufWriter::new(File::create(trace)?));
+            Box::new(Both(profile.clone(), log))
+        }
+        None => Box::new(profile.clone()),
+    });
+
+    let (output, state) = program.run_batch(&input)?;
+    for value in output {
+        println!("Output: {}", value);
+    }
+    println!("Program {}\n", state);
+
+    // dropping the tracer flushes the trace log
+    program.tracer = None;
+    print!("{}", profile.lock().map_err(|_| "Profile was poisoned.")?);
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Runs an Intcode program symbolically, and prints everything it outputs as a function of its
+//! input.
+//!
+//! Usage: `intcode-symbolic [--var <address>]... [--show <address>]... <program> [input]`, where
+//! `input` is a comma-separated list of numbers to give the program. The program runs on that
+//! input, but each input becomes a variable, `in0`, `in1`, and so on, and each output is printed
+//! as a function of them, along with anything that had to be assumed along the way, ie, which way
+//! a jump went. `--var` also makes the value at `address` a variable, and `--show` prints the
+//! value at `address` once the program stops. For day 2:
+//!
+//! ```text
+//! intcode-symbolic --var 1 --var 2 --show 0 ../day02/input.txt
+//! ```
+
+use intcode::{Intcode, Symbolic};
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+
+const USAGE: &str =
+    "Usage: intcode-symbolic [--var <address>]... [--show <address>]... <program> [input]";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1).peekable();
+    let mut vars = Vec::new();
+    let mut show = Vec::new();
+    while let Some(flag) = args.peek().cloned() {
+        match flag.as_str() {
+            "--var" => vars.push(args.nth(1).ok_or(USAGE)?.parse::<usize>()?),
+            "--show" => show.push(args.nth(1).ok_or(USAGE)?.parse::<usize>()?),
+            _ => break,
+        }
+    }
+    let path = args.next().ok_or(USAGE)?;
+    let input = match args.next() {
+        Some(input) => input
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<Vec<i64>, _>>()?,
+        None => Vec::new(),
+    };
+
+    let file = File::open(&path)?;
+    let reader = BufReader::new(file);
+    let mut symbolic = Symbolic::new(Intcode::load(reader)?);
+    for address in vars {
+        symbolic.var(address);
+    }
+
+    let (outputs, state) = symbolic.run(&input);
+    for output in outputs {
+        println!("Output: {}", output);
+    }
+    for address in show {
+        println!("[{}] = {}", address, symbolic.value_assuming(address));
+    }
+    println!("Program {}", state);
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+/// A limit on how long a single call to `run`, `run_io`, or `run_batch` may execute before it
+/// stops with `RunState::BudgetExhausted`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    /// Stop after executing this many instructions.
+    Instructions(u64),
+
+    /// Stop after running for this long. The clock is only checked every so often, so the VM may
+    /// run a little past the deadline.
+    Time(Duration),
+}
+
+/// How many instructions to execute between checks of the clock.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// Tracks how much of a budget has been used.
+pub(crate) enum Meter {
+    Unlimited,
+    Instructions(u64),
+    Time { deadline: Instant, countdown: u64 },
+}
+
+impl Meter {
+    pub(crate) fn new(budget: Option<Budget>) -> Meter {
+        match budget {
+            None => Meter::Unlimited,
+            Some(Budget::Instructions(n)) => Meter::Instructions(n),
+            Some(Budget::Time(duration)) => Meter::Time {
+                deadline: Instant::now() + duration,
+                countdown: 0,
+            },
+        }
+    }
+
+    /// Called before each instruction. Returns true if there's no budget left to execute it.
+    pub(crate) fn exhausted(&mut self) -> bool {
+        match self {
+            Meter::Unlimited => false,
+            Meter::Instructions(0) => true,
+            Meter::Instructions(n) => {
+                *n -= 1;
+                false
+            }
+            Meter::Time {
+                deadline,
+                countdown,
+            } => {
+                if *countdown == 0 {
+                    if Instant::now() >= *deadline {
+                        return true;
+                    }
+                    *countdown = CLOCK_INTERVAL;
+                }
+                *countdown -= 1;
+                false
+            }
+        }
+    }
+}
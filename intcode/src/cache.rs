@@ -0,0 +1,45 @@
+use crate::cell::Cell;
+use crate::error::{FaultKind, InvalidOpcode};
+use crate::opcode::Opcode;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// Opcodes that have already been decoded, by address.
+///
+/// Decoding an opcode means pulling its parameter modes apart with a handful of divisions, and
+/// most programs spend their time decoding the same few hundred instructions over and over. Each
+/// entry remembers the cell it was decoded from, so any write to that cell, whether it's the
+/// program modifying itself or a write through `Intcode::code`, invalidates the entry. Operands
+/// are still read from memory every time, so writes to them need no special handling.
+///
+/// Like memory, the cache is shared between clones until one of them adds to it, so restoring or
+/// cloning a machine that has already run doesn't start from a cold cache.
+#[derive(Clone, Default)]
+pub(crate) struct DecodeCache<C> {
+    entries: Arc<Vec<Option<(C, Opcode)>>>,
+}
+
+impl<C: Cell> DecodeCache<C> {
+    /// Decodes `code`, the value of the cell at `ip`. Only addresses below `len` are cached, so
+    /// that a program that jumps far away doesn't blow up the cache.
+    pub(crate) fn decode(&mut self, ip: usize, code: C, len: usize) -> Result<Opcode, FaultKind> {
+        if let Some(Some((cached, opcode))) = self.entries.get(ip) {
+            if *cached == code {
+                return Ok(*opcode);
+            }
+        }
+
+        let opcode = match code.to_i64() {
+            Some(code) => Opcode::try_from(code)?,
+            None => return Err(InvalidOpcode(code.to_i128()).into()),
+        };
+        if ip < len {
+            let entries = Arc::make_mut(&mut self.entries);
+            if entries.len() < len {
+                entries.resize(len, None);
+            }
+            entries[ip] = Some((code, opcode));
+        }
+        Ok(opcode)
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// The type of value stored in each memory cell. Intcode is specified in terms of `i64`, which is
+/// what `Intcode` uses unless told otherwise, but `Intcode<i128>` is there for programs whose
+/// arithmetic doesn't fit.
+pub trait Cell:
+    Copy + Default + Ord + Hash + Debug + Display + FromStr<Err = ParseIntError> + Send + Sync + 'static
+{
+    fn from_i64(value: i64) -> Self;
+
+    /// Widens the value to an `i128`, which every cell type fits in.
+    fn to_i128(self) -> i128;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_mul(self, other: Self) -> Self;
+
+    fn to_i64(self) -> Option<i64> {
+        let value = self.to_i128();
+        if value >= i128::from(i64::MIN) && value <= i128::from(i64::MAX) {
+            Some(value as i64)
+        } else {
+            None
+        }
+    }
+}
+
+impl Cell for i64 {
+    fn from_i64(value: i64) -> Self {
+        value
+    }
+
+    fn to_i128(self) -> i128 {
+        i128::from(self)
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        i64::checked_add(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        i64::checked_mul(self, other)
+    }
+
+    fn wrapping_add(self, other: Self) -> Self {
+        i64::wrapping_add(self, other)
+    }
+
+    fn wrapping_mul(self, other: Self) -> Self {
+        i64::wrapping_mul(self, other)
+    }
+}
+
+impl Cell for i128 {
+    fn from_i64(value: i64) -> Self {
+        i128::from(value)
+    }
+
+    fn to_i128(self) -> i128 {
+        self
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        i128::checked_add(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        i128::checked_mul(self, other)
+    }
+
+    fn wrapping_add(self, other: Self) -> Self {
+        i128::wrapping_add(self, other)
+    }
+
+    fn wrapping_mul(self, other: Self) -> Self {
+        i128::wrapping_mul(self, other)
+    }
+}
+
+/// What to do when an addition or multiplication overflows a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Arithmetic {
+    /// Stop with a fault. This is the default, since an overflow almost certainly means that the
+    /// program, or the VM, has a bug.
+    #[default]
+    Checked,
+
+    /// Wrap around, two's-complement style.
+    Wrapping,
+}
+
+impl Arithmetic {
+    pub fn add<C: Cell>(self, a: C, b: C) -> Option<C> {
+        match self {
+            Arithmetic::Checked => a.checked_add(b),
+            Arithmetic::Wrapping => Some(a.wrapping_add(b)),
+        }
+    }
+
+    pub fn mul<C: Cell>(self, a: C, b: C) -> Option<C> {
+        match self {
+            Arithmetic::Checked => a.checked_mul(b),
+            Arithmetic::Wrapping => Some(a.wrapping_mul(b)),
+        }
+    }
+}
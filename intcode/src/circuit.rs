@@ -0,0 +1,162 @@
+use crate::error::CircuitError;
+use crate::machine::Intcode;
+use crate::state::RunState;
+use std::collections::VecDeque;
+use std::iter;
+
+/// When a `Circuit` is done running.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Until {
+    /// Every machine has halted.
+    AllHalted,
+
+    /// The machine at this node has halted.
+    Halted(usize),
+
+    /// The machine at `node` has output at least `count` values.
+    Outputs { node: usize, count: usize },
+}
+
+/// Intcode machines wired together, like the amplifiers on day 7. Each machine is a node, and an
+/// edge from one node to another sends everything the first outputs to the second as input. A
+/// node with more than one edge out sends each output down all of them. Edges can loop back to an
+/// earlier node, which is how day 7's feedback loop is built.
+///
+/// ```
+/// # use intcode::{Circuit, Intcode};
+/// // adds its two inputs
+/// let program = Intcode::load("3,11,3,12,1,11,12,13,4,13,99,0,0,0".as_bytes()).unwrap();
+/// let mut circuit = Circuit::pipeline(&program, 3);
+/// circuit.input(0, 1)?.input(0, 2)?.input(1, 3)?.input(2, 4)?;
+/// circuit.run()?;
+/// assert_eq!(circuit.outputs[2], vec![10]);
+/// # Ok::<(), intcode::CircuitError>(())
+/// ```
+pub struct Circuit {
+    pub machines: Vec<Intcode>,
+
+    /// Input that has been sent to each node but not read yet.
+    pub inputs: Vec<VecDeque<i64>>,
+
+    /// Everything each node has output.
+    pub outputs: Vec<Vec<i64>>,
+
+    pub halted: Vec<bool>,
+    pub edges: Vec<(usize, usize)>,
+    pub until: Until,
+}
+
+impl Circuit {
+    /// `size` copies of `program`, with no edges, that run until they've all halted.
+    pub fn new(program: &Intcode, size: usize) -> Circuit {
+        Circuit {
+            machines: vec![program.clone(); size],
+            inputs: vec![VecDeque::new(); size],
+            outputs: vec![Vec::new(); size],
+            halted: vec![false; size],
+            edges: Vec::new(),
+            until: Until::AllHalted,
+        }
+    }
+
+    /// `size` copies of `program`, each sending its output to the next.
+    pub fn pipeline(program: &Intcode, size: usize) -> Circuit {
+        let mut circuit = Circuit::new(program, size);
+        circuit.edges.extend((1..size).map(|node| (node - 1, node)));
+        circuit
+    }
+
+    /// A pipeline where the last node sends its output back to the first.
+    pub fn feedback_loop(program: &Intcode, size: usize) -> Circuit {
+        let mut circuit = Circuit::pipeline(program, size);
+        if size > 0 {
+            circuit.edges.push((size - 1, 0));
+        }
+        circuit
+    }
+
+    /// Adds an edge sending the output of `from` to `to`.
+    pub fn connect(&mut self, from: usize, to: usize) -> Result<&mut Circuit, CircuitError> {
+        self.check(from)?.check(to)?;
+        self.edges.push((from, to));
+        Ok(self)
+    }
+
+    /// Queues `value` as input for `node`.
+    pub fn input(&mut self, node: usize, value: i64) -> Result<&mut Circuit, CircuitError> {
+        self.check(node)?;
+        self.inputs[node].push_back(value);
+        Ok(self)
+    }
+
+    fn check(&self, node: usize) -> Result<&Circuit, CircuitError> {
+        if node < self.machines.len() {
+            Ok(self)
+        } else {
+            Err(CircuitError::NoSuchNode(node))
+        }
+    }
+
+    fn done(&self) -> bool {
+        match self.until {
+            Until::AllHalted => self.halted.iter().all(|&halted| halted),
+            Until::Halted(node) => self.halted[node],
+            Until::Outputs { node, count } => self.outputs[node].len() >= count,
+        }
+    }
+
+    /// Runs each machine in turn, until it needs input that it doesn't have, and passes along
+    /// whatever it output, until the `until` rule is met. Output sent to a machine that has
+    /// halted is dropped.
+    pub fn run(&mut self) -> Result<(), CircuitError> {
+        match self.until {
+            Until::AllHalted => (),
+            Until::Halted(node) | Until::Outputs { node, .. } => {
+                self.check(node)?;
+            }
+        }
+        for &(from, to) in &self.edges {
+            self.check(from)?.check(to)?;
+        }
+
+        while !self.done() {
+            let mut progress = false;
+            for node in 0..self.machines.len() {
+                if self.halted[node] {
+                    continue;
+                }
+
+                let machine = &mut self.machines[node];
+                let ip = machine.ip;
+                let mut output = Vec::new();
+                let input = &mut self.inputs[node];
+                let state = machine
+                    .run_io(&mut (iter::from_fn(|| input.pop_front()), |value: i64| {
+                        output.push(value)
+                    }));
+                if let RunState::Halted { .. } = state {
+                    self.halted[node] = true;
+                }
+                progress |= self.halted[node] || machine.ip != ip || !output.is_empty();
+
+                for &(_, to) in self.edges.iter().filter(|&&(from, _)| from == node) {
+                    if !self.halted[to] {
+                        self.inputs[to].extend(&output);
+                    }
+                }
+                self.outputs[node].extend(output);
+                if let RunState::Fault(fault) = state {
+                    return Err(CircuitError::Fault { node, fault });
+                }
+                if self.done() {
+                    return Ok(());
+                }
+            }
+
+            if !progress {
+                return Err(CircuitError::Deadlock);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+use crate::cell::Cell;
+use crate::error::InvalidOpcode;
+use crate::opcode::{Opcode, ParameterMode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
+use std::fmt::{self, Display};
+
+/// A single decoded instruction, ready to be printed. Operands are displayed as `#5` for
+/// immediate mode, `[100]` for position mode, and `[rb+3]` for relative mode. If the instruction
+/// writes to memory, the destination is displayed after an arrow:
+///
+/// ```text
+/// 0042: ADD [rb+3], #5 -> [100]
+/// ```
+pub struct Instruction {
+    pub address: usize,
+    pub opcode: Opcode,
+    pub operands: Vec<i64>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `address`. Operands that run past the end of `code` are read as
+    /// zero, just like the VM would.
+    pub fn decode(code: &[i64], address: usize) -> Result<Instruction, InvalidOpcode> {
+        let opcode: Opcode = code.get(address).copied().unwrap_or(0).try_into()?;
+        let operands = (1..=opcode.modes().len())
+            .map(|i| code.get(address + i).copied().unwrap_or(0))
+            .collect();
+        Ok(Instruction {
+            address,
+            opcode,
+            operands,
+        })
+    }
+
+    /// The number of memory cells the instruction occupies, including the opcode itself.
+    pub fn size(&self) -> usize {
+        self.operands.len() + 1
+    }
+}
+
+/// Writes an operand in disassembly syntax.
+pub(crate) fn fmt_operand<C: Cell, W: fmt::Write>(
+    f: &mut W,
+    mode: &ParameterMode,
+    operand: C,
+) -> fmt::Result {
+    match mode {
+        ParameterMode::Position => write!(f, "[{}]", operand),
+        ParameterMode::Immediate => write!(f, "#{}", operand),
+        ParameterMode::Relative if operand < C::default() => write!(f, "[rb{}]", operand),
+        ParameterMode::Relative => write!(f, "[rb+{}]", operand),
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}: {}", self.address, self.opcode.mnemonic())?;
+        let modes = self.opcode.modes();
+        let inputs = if self.opcode.writes() {
+            modes.len() - 1
+        } else {
+            modes.len()
+        };
+
+        for (i, (mode, &operand)) in modes.iter().zip(self.operands.iter()).enumerate() {
+            if i == inputs {
+                write!(f, " -> ")?;
+            } else if i == 0 {
+                write!(f, " ")?;
+            } else {
+                write!(f, ", ")?;
+            }
+            fmt_operand(f, mode, operand)?;
+        }
+        Ok(())
+    }
+}
+
+impl Instruction {
+    /// If this is a jump with an immediate target, returns the target.
+    fn jump_target(&self) -> Option<usize> {
+        match &self.opcode {
+            Opcode::JumpIfTrue(_, ParameterMode::Immediate)
+            | Opcode::JumpIfFalse(_, ParameterMode::Immediate)
+                if self.operands[1] >= 0 =>
+            {
+                Some(self.operands[1] as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// True if execution can never continue with the next instruction: a halt, or a jump whose
+    /// condition is an immediate that always jumps.
+    fn stops(&self) -> bool {
+        match &self.opcode {
+            Opcode::Halt => true,
+            Opcode::JumpIfTrue(ParameterMode::Immediate, _) => self.operands[0] != 0,
+            Opcode::JumpIfFalse(ParameterMode::Immediate, _) => self.operands[0] == 0,
+            _ => false,
+        }
+    }
+
+    /// A value the instruction computes entirely from immediates and pushes onto the stack, ie,
+    /// `ADD #42, #0 -> [rb+1]`. Compiled Intcode pushes return addresses this way before calling a
+    /// function.
+    fn constant(&self) -> Option<i64> {
+        match &self.opcode {
+            Opcode::Add(
+                ParameterMode::Immediate,
+                ParameterMode::Immediate,
+                ParameterMode::Relative,
+            ) => self.operands[0].checked_add(self.operands[1]),
+            Opcode::Multiply(
+                ParameterMode::Immediate,
+                ParameterMode::Immediate,
+                ParameterMode::Relative,
+            ) => self.operands[0].checked_mul(self.operands[1]),
+            _ => None,
+        }
+    }
+
+    /// The absolute address the instruction writes to, if it can be known without running it.
+    fn write_address(&self) -> Option<usize> {
+        let modes = self.opcode.modes();
+        match modes.last() {
+            Some(ParameterMode::Position) if self.opcode.writes() => {
+                let address = *self.operands.last()?;
+                if address >= 0 {
+                    Some(address as usize)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A line of a `Listing`.
+pub enum Line {
+    /// An instruction that the disassembler believes is reachable.
+    Code {
+        instruction: Instruction,
+        /// Addresses of jumps that target this instruction.
+        jumped_from: Vec<usize>,
+        /// Addresses of instructions that write into this instruction.
+        modified_by: Vec<usize>,
+        /// Addresses inside this instruction that are also jump targets. This happens when the
+        /// program modifies itself, or when the heuristics guessed wrong.
+        entered_at: Vec<usize>,
+    },
+
+    /// A run of cells that are never executed, as far as the disassembler can tell.
+    Data {
+        address: usize,
+        values: Vec<i64>,
+        /// Addresses of jumps that target the first cell, even though it doesn't decode as an
+        /// instruction.
+        jumped_from: Vec<usize>,
+    },
+}
+
+/// A listing of a whole program, separated into code and data.
+///
+/// There's no way to tell code from data in Intcode, so the listing is built with heuristics:
+/// starting at address 0, the disassembler follows every instruction, and every jump with an
+/// immediate target, until it reaches a halt or an unconditional jump. Jumps through memory (ie,
+/// function returns) can't be followed, so any constant that an instruction computes from
+/// immediates and that points just past an unconditional jump is also assumed to be code: that's
+/// what a return address looks like. Everything that isn't reached is data.
+pub struct Listing {
+    pub lines: Vec<Line>,
+}
+
+impl Listing {
+    pub fn new(code: &[i64]) -> Listing {
+        // for each cell, the address of the instruction it belongs to
+        let mut owner: Vec<Option<usize>> = vec![None; code.len()];
+        let mut instructions = BTreeMap::new();
+        let mut jumped_from: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        let mut constants = BTreeSet::new();
+        let mut queue = vec![0];
+
+        loop {
+            while let Some(mut address) = queue.pop() {
+                while address < code.len() && owner[address].is_none() {
+                    let instruction = match Instruction::decode(code, address) {
+                        Ok(instruction) => instruction,
+                        Err(_) => break,
+                    };
+                    let next = address + instruction.size();
+                    if next > code.len() || owner[address..next].iter().any(Option::is_some) {
+                        break;
+                    }
+                    for cell in &mut owner[address..next] {
+                        *cell = Some(address);
+                    }
+
+                    if let Some(target) = instruction.jump_target() {
+                        jumped_from.entry(target).or_default().push(address);
+                        queue.push(target);
+                    }
+                    if let Some(value) = instruction.constant() {
+                        constants.insert(value);
+                    }
+
+                    let stops = instruction.stops();
+                    instructions.insert(address, instruction);
+                    if stops {
+                        break;
+                    }
+                    address = next;
+                }
+            }
+
+            // look for return addresses: constants that point just past an unconditional jump
+            queue = constants
+                .iter()
+                .filter(|&&value| value > 0 && (value as usize) < code.len())
+                .map(|&value| value as usize)
+                .filter(|&address| owner[address].is_none())
+                .filter(|&address| match owner[address - 1] {
+                    Some(start) => instructions[&start].stops() && start != 0,
+                    None => false,
+                })
+                .collect();
+            if queue.is_empty() {
+                break;
+            }
+        }
+
+        let mut modified_by: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (&address, instruction) in instructions.iter() {
+            if let Some(Some(target)) = instruction.write_address().and_then(|a| owner.get(a)) {
+                modified_by.entry(*target).or_default().push(address);
+            }
+        }
+        let mut entered_at: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &target in jumped_from.keys() {
+            if let Some(Some(start)) = owner.get(target) {
+                if *start != target {
+                    entered_at.entry(*start).or_default().push(target);
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut address = 0;
+        while address < code.len() {
+            if let Some(instruction) = instructions.remove(&address) {
+                address += instruction.size();
+                lines.push(Line::Code {
+                    jumped_from: jumped_from.remove(&instruction.address).unwrap_or_default(),
+                    modified_by: modified_by.remove(&instruction.address).unwrap_or_default(),
+                    entered_at: entered_at.remove(&instruction.address).unwrap_or_default(),
+                    instruction,
+                });
+                continue;
+            }
+
+            // data runs end at code, at jump targets, or after 8 values
+            let start = address;
+            address += 1;
+            while address < code.len()
+                && address - start < 8
+                && owner[address].is_none()
+                && !jumped_from.contains_key(&address)
+            {
+                address += 1;
+            }
+            lines.push(Line::Data {
+                address: start,
+                values: code[start..address].to_vec(),
+                jumped_from: jumped_from.remove(&start).unwrap_or_default(),
+            });
+        }
+
+        Listing { lines }
+    }
+}
+
+fn addresses(addresses: &[usize]) -> String {
+    addresses
+        .iter()
+        .map(|a| format!("{:04}", a))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn fmt_notes(f: &mut fmt::Formatter<'_>, notes: &[String]) -> fmt::Result {
+    if !notes.is_empty() {
+        write!(f, "  ; {}", notes.join("; "))?;
+    }
+    Ok(())
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut notes = Vec::new();
+        match self {
+            Line::Code {
+                instruction,
+                jumped_from,
+                modified_by,
+                entered_at,
+            } => {
+                write!(f, "{}", instruction)?;
+                if !jumped_from.is_empty() {
+                    notes.push(format!("from {}", addresses(jumped_from)));
+                }
+                if !modified_by.is_empty() {
+                    notes.push(format!("modified by {}", addresses(modified_by)));
+                }
+                if !entered_at.is_empty() {
+                    notes.push(format!("also entered at {}", addresses(entered_at)));
+                }
+            }
+            Line::Data {
+                address,
+                values,
+                jumped_from,
+            } => {
+                let values: Vec<_> = values.iter().map(i64::to_string).collect();
+                write!(f, "{:04}: DATA {}", address, values.join(", "))?;
+                if !jumped_from.is_empty() {
+                    notes.push(format!("invalid jump from {}", addresses(jumped_from)));
+                }
+            }
+        }
+        fmt_notes(f, &notes)
+    }
+}
+
+impl Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
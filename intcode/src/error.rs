@@ -0,0 +1,260 @@
+use crate::cell::Cell;
+use crate::opcode::ParameterMode;
+use std::error::Error;
+use std::fmt::{self, Display};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidParameterMode(pub i64);
+
+impl Display for InvalidParameterMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a valid parameter mode.", self.0)
+    }
+}
+
+impl Error for InvalidParameterMode {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidOpcode(pub i128);
+
+impl Display for InvalidOpcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a valid opcode.", self.0)
+    }
+}
+
+impl Error for InvalidOpcode {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidOutputMode(pub ParameterMode);
+
+impl Display for InvalidOutputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a valid parameter mode for output.", self.0)
+    }
+}
+
+impl Error for InvalidOutputMode {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+/// An error in assembly source, with the (1-based) line it occurred on.
+#[derive(Debug)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AssembleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidAddress(pub i128);
+
+impl Display for InvalidAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a valid address.", self.0)
+    }
+}
+
+impl Error for InvalidAddress {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+/// Why an instruction couldn't be executed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FaultKind {
+    InvalidOpcode(InvalidOpcode),
+    InvalidOutputMode(InvalidOutputMode),
+    InvalidAddress(InvalidAddress),
+
+    /// An addition, multiplication, or relative base adjustment overflowed, and the machine's
+    /// `Arithmetic` is `Checked`.
+    Overflow,
+}
+
+impl Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaultKind::InvalidOpcode(e) => e.fmt(f),
+            FaultKind::InvalidOutputMode(e) => e.fmt(f),
+            FaultKind::InvalidAddress(e) => e.fmt(f),
+            FaultKind::Overflow => write!(f, "Arithmetic overflow."),
+        }
+    }
+}
+
+impl From<InvalidOpcode> for FaultKind {
+    fn from(e: InvalidOpcode) -> FaultKind {
+        FaultKind::InvalidOpcode(e)
+    }
+}
+
+impl From<InvalidOutputMode> for FaultKind {
+    fn from(e: InvalidOutputMode) -> FaultKind {
+        FaultKind::InvalidOutputMode(e)
+    }
+}
+
+impl From<InvalidAddress> for FaultKind {
+    fn from(e: InvalidAddress) -> FaultKind {
+        FaultKind::InvalidAddress(e)
+    }
+}
+
+/// A fault: the instruction at `ip` couldn't be executed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fault {
+    pub ip: usize,
+    pub kind: FaultKind,
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fault at {}: {}", self.ip, self.kind)
+    }
+}
+
+impl Error for Fault {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            FaultKind::InvalidOpcode(e) => Some(e),
+            FaultKind::InvalidOutputMode(e) => Some(e),
+            FaultKind::InvalidAddress(e) => Some(e),
+            FaultKind::Overflow => None,
+        }
+    }
+}
+
+/// A program run with `Intcode::run_batch` faulted. Whatever it output before the fault is kept,
+/// since that's usually what's needed to debug it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchFault<C: Cell = i64> {
+    pub fault: Fault,
+    pub output: Vec<C>,
+}
+
+impl<C: Cell> Display for BatchFault<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.fault)?;
+        if !self.output.is_empty() {
+            let output: Vec<_> = self.output.iter().map(C::to_string).collect();
+            write!(f, " Output before the fault: {}", output.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Cell> Error for BatchFault<C> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.fault)
+    }
+}
+
+/// An ASCII program faulted. Whatever it output before the fault is kept, since that's usually
+/// the best clue to what went wrong.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsciiFault {
+    pub fault: Fault,
+    pub text: String,
+    pub values: Vec<i64>,
+}
+
+impl Display for AsciiFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.text.is_empty() {
+            write!(f, "{}", self.fault)
+        } else {
+            write!(f, "{} Output before the fault:\n{}", self.fault, self.text)
+        }
+    }
+}
+
+impl Error for AsciiFault {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.fault)
+    }
+}
+
+/// Why a `Network` stopped without its router stopping it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetworkError {
+    /// The computer at `address` faulted.
+    Fault { address: usize, fault: Fault },
+
+    /// Every computer was idle, and the router didn't send anything to wake them up.
+    Deadlock,
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Fault { address, fault } => {
+                write!(f, "Computer {} stopped. {}", address, fault)
+            }
+            NetworkError::Deadlock => write!(f, "The network is idle and has nothing to send."),
+        }
+    }
+}
+
+impl Error for NetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetworkError::Fault { fault, .. } => Some(fault),
+            NetworkError::Deadlock => None,
+        }
+    }
+}
+
+/// Why a `Circuit` stopped before its `Until` rule was met.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CircuitError {
+    /// The machine at `node` faulted.
+    Fault { node: usize, fault: Fault },
+
+    /// Every machine that hasn't halted is waiting for input that will never arrive.
+    Deadlock,
+
+    /// There is no machine at this node.
+    NoSuchNode(usize),
+}
+
+impl Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitError::Fault { node, fault } => write!(f, "Machine {} stopped. {}", node, fault),
+            CircuitError::Deadlock => write!(f, "Every machine is waiting for input."),
+            CircuitError::NoSuchNode(node) => write!(f, "There is no machine {}.", node),
+        }
+    }
+}
+
+impl Error for CircuitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CircuitError::Fault { fault, .. } => Some(fault),
+            CircuitError::Deadlock | CircuitError::NoSuchNode(_) => None,
+        }
+    }
+}
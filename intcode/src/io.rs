@@ -0,0 +1,83 @@
+use std::sync::mpsc::{Sender, SyncSender};
+
+/// Somewhere for the VM to send output. Output is sent one value at a time, as soon as the
+/// program produces it.
+pub trait Output<C = i64> {
+    fn output(&mut self, value: C);
+}
+
+impl<C> Output<C> for Vec<C> {
+    fn output(&mut self, value: C) {
+        self.push(value);
+    }
+}
+
+/// Any closure that accepts a value can be used to react to output as it's produced.
+impl<C, F: FnMut(C)> Output<C> for F {
+    fn output(&mut self, value: C) {
+        self(value);
+    }
+}
+
+/// Sends output down a channel, ie, to another `Intcode` running in a different thread. If the
+/// receiving side has hung up, output is dropped.
+impl<C> Output<C> for Sender<C> {
+    fn output(&mut self, value: C) {
+        self.send(value).ok();
+    }
+}
+
+impl<C> Output<C> for SyncSender<C> {
+    fn output(&mut self, value: C) {
+        self.send(value).ok();
+    }
+}
+
+/// Interprets output as ASCII text. Anything outside of the ASCII range is appended as a decimal
+/// number instead.
+impl Output for String {
+    fn output(&mut self, value: i64) {
+        if (0..128).contains(&value) {
+            self.push(char::from(value as u8));
+        } else {
+            self.push_str(&value.to_string());
+        }
+    }
+}
+
+/// Both sides of the VM's I/O: a source of input and a sink for output. The VM calls `input`
+/// whenever it executes an input instruction, and `output` whenever it executes an output
+/// instruction.
+///
+/// Any `(Iterator<Item = i64>, Output)` pair is an `Io`. For input, that means:
+///
+///   - a slice can be used with `slice.iter().copied()`
+///   - a channel can be used with `receiver.iter()`, which blocks until input arrives, or
+///     `receiver.try_iter()`, which suspends the VM if there's nothing waiting in the channel
+///   - ASCII text can be used with `ascii("some text\n")`
+///   - anything else can be used with `std::iter::from_fn`
+///
+/// Implement `Io` directly when input and output need to share state, such as a controller that
+/// decides on its next input based on the output it has seen.
+pub trait Io<C = i64> {
+    /// Returns the next input value, or `None` if there's no input available right now. Returning
+    /// `None` suspends the VM; it can be resumed later when there is more input.
+    fn input(&mut self) -> Option<C>;
+
+    fn output(&mut self, value: C);
+}
+
+impl<C, I: Iterator<Item = C>, O: Output<C>> Io<C> for (I, O) {
+    fn input(&mut self) -> Option<C> {
+        self.0.next()
+    }
+
+    fn output(&mut self, value: C) {
+        self.1.output(value);
+    }
+}
+
+/// Converts text into input for programs that speak ASCII.
+pub fn ascii(s: &str) -> impl Iterator<Item = i64> + '_ {
+    s.bytes().map(i64::from)
+}
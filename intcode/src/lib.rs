@@ -0,0 +1,49 @@
+//! The Intcode computer.
+//!
+//! Intcode shows up over and over again in the 2019 Advent of Code: it's introduced on day 2,
+//! gains parameter modes and I/O on day 5, learns to suspend for input on day 7, and is finally
+//! "complete" on day 9 when relative mode and large memory arrive. Every puzzle after that (days
+//! 11, 13, 15, 17, 19, 21, 23, and 25) hands you an Intcode program as your puzzle input.
+//!
+//! This crate is the complete day 9 computer, shared by all of those days so that a fix to the VM
+//! only needs to be made once.
+
+mod ascii;
+mod asm;
+mod budget;
+mod cache;
+mod cell;
+mod circuit;
+mod disasm;
+mod error;
+mod io;
+mod machine;
+mod memory;
+mod network;
+mod opcode;
+mod profile;
+mod snapshot;
+mod state;
+mod symbolic;
+mod trace;
+
+pub use crate::ascii::{AsciiMachine, AsciiOutput};
+pub use crate::asm::assemble;
+pub use crate::budget::Budget;
+pub use crate::cell::{Arithmetic, Cell};
+pub use crate::circuit::{Circuit, Until};
+pub use crate::disasm::{Instruction, Line, Listing};
+pub use crate::error::{
+    AsciiFault, AssembleError, BatchFault, CircuitError, Fault, FaultKind, InvalidAddress,
+    InvalidOpcode, InvalidOutputMode, InvalidParameterMode, NetworkError,
+};
+pub use crate::io::{ascii, Io, Output};
+pub use crate::machine::Intcode;
+pub use crate::memory::{Memory, DEFAULT_LIMIT};
+pub use crate::network::{Network, Packet, Route, Router, Schedule, NO_PACKET};
+pub use crate::opcode::{Opcode, ParameterMode};
+pub use crate::profile::Profile;
+pub use crate::snapshot::Snapshot;
+pub use crate::state::RunState;
+pub use crate::symbolic::{Assumption, Expr, Poly, Symbolic, SymbolicValue, Var};
+pub use crate::trace::{Event, TraceLog, Tracer};
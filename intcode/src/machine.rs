@@ -0,0 +1,356 @@
+use crate::budget::{Budget, Meter};
+use crate::cache::DecodeCache;
+use crate::cell::{Arithmetic, Cell};
+use crate::error::{BatchFault, Fault, FaultKind, InvalidAddress, InvalidOutputMode};
+use crate::io::Io;
+use crate::memory::Memory;
+use crate::opcode::{Opcode, ParameterMode};
+use crate::snapshot::Snapshot;
+use crate::state::RunState;
+use crate::trace::{Event, Tracer};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::io::BufRead;
+use std::iter;
+use std::str;
+
+/// An Intcode computer. Cloning is cheap: the clone shares memory with the original until one of
+/// them writes to it. Clones don't inherit the tracer.
+pub struct Intcode<C: Cell = i64> {
+    pub code: Memory<C>,
+    pub ip: usize,
+    pub relative_base: isize,
+
+    /// What to do when an addition or multiplication overflows. Defaults to faulting.
+    pub arithmetic: Arithmetic,
+
+    /// If set, each call to `run`, `run_io`, or `run_batch` stops with
+    /// `RunState::BudgetExhausted` once it has used up the budget.
+    pub budget: Option<Budget>,
+
+    /// `run` will stop with `RunState::Breakpoint` before executing an instruction at any of
+    /// these addresses.
+    pub breakpoints: HashSet<usize>,
+    resume_breakpoint: Option<usize>,
+
+    /// If set, the tracer is told about every instruction the VM executes. Tracing slows the VM
+    /// down quite a bit.
+    pub tracer: Option<Box<dyn Tracer<C> + Send>>,
+
+    decoded: DecodeCache<C>,
+}
+
+impl<C: Cell> Clone for Intcode<C> {
+    fn clone(&self) -> Intcode<C> {
+        Intcode {
+            code: self.code.clone(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            arithmetic: self.arithmetic,
+            budget: self.budget,
+            breakpoints: self.breakpoints.clone(),
+            resume_breakpoint: self.resume_breakpoint,
+            tracer: None,
+            decoded: self.decoded.clone(),
+        }
+    }
+}
+
+impl Intcode {
+    pub fn load<R: BufRead>(reader: R) -> Result<Intcode, Box<dyn Error>> {
+        Intcode::load_cells(reader)
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    /// Like `load`, but for cell types other than `i64`, ie, `Intcode::<i128>::load_cells`.
+    pub fn load_cells<R: BufRead>(reader: R) -> Result<Intcode<C>, Box<dyn Error>> {
+        let code = reader
+            .split(b',')
+            .map(|code| match code {
+                Ok(code) => {
+                    let s = str::from_utf8(&code)?;
+                    Ok(s.trim().parse()?)
+                }
+                Err(e) => Err(Box::new(e) as Box<dyn Error>),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Intcode {
+            code: Memory::from(code),
+            ip: 0,
+            relative_base: 0,
+            arithmetic: Arithmetic::default(),
+            budget: None,
+            breakpoints: HashSet::new(),
+            resume_breakpoint: None,
+            tracer: None,
+            decoded: DecodeCache::default(),
+        })
+    }
+
+    /// Takes a snapshot of memory and registers. The snapshot's `input` and `output` are empty;
+    /// it's up to the caller to fill them in with whatever I/O was pending.
+    pub fn snapshot(&self) -> Snapshot<C> {
+        Snapshot {
+            memory: self.code.clone(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Puts memory and registers back the way they were when `snapshot` was taken. Breakpoints
+    /// and the address limit are left alone. Any pending I/O in the snapshot is up to the caller.
+    pub fn restore(&mut self, snapshot: &Snapshot<C>) {
+        let limit = self.code.limit();
+        self.code = snapshot.memory.clone();
+        self.code.set_limit(limit);
+        self.ip = snapshot.ip;
+        self.relative_base = snapshot.relative_base;
+        self.resume_breakpoint = None;
+    }
+
+    /// Reads a cell that is part of the instruction at `ip`. Unlike a read from a program's
+    /// operand, the address isn't computed by the program, but it can still run past the limit.
+    fn fetch(&self, i: usize) -> Result<C, InvalidAddress> {
+        if i < self.code.limit() {
+            Ok(self.code[i])
+        } else {
+            Err(InvalidAddress(i as i128))
+        }
+    }
+
+    fn relative(&self, v: C) -> i128 {
+        v.to_i128() + self.relative_base as i128
+    }
+
+    fn output_operand(&mut self, sp: usize, mode: ParameterMode) -> Result<usize, FaultKind> {
+        let v = self.fetch(sp)?;
+        let address = match mode {
+            ParameterMode::Position => v.to_i128(),
+            ParameterMode::Immediate => return Err(InvalidOutputMode(mode).into()),
+            ParameterMode::Relative => self.relative(v),
+        };
+        Ok(self.code.address(address)?)
+    }
+
+    fn operand(&mut self, sp: usize, mode: ParameterMode) -> Result<C, InvalidAddress> {
+        let v = self.fetch(sp)?;
+        Ok(match mode {
+            ParameterMode::Position => self.code[self.code.address(v.to_i128())?],
+            ParameterMode::Immediate => v,
+            ParameterMode::Relative => self.code[self.code.address(self.relative(v))?],
+        })
+    }
+
+    fn operands2(
+        &mut self,
+        sp: usize,
+        mode1: ParameterMode,
+        mode2: ParameterMode,
+    ) -> Result<(C, C), InvalidAddress> {
+        Ok((self.operand(sp, mode1)?, self.operand(sp + 1, mode2)?))
+    }
+
+    /// Runs the program with the given input, collecting all of its output, until it halts or
+    /// until it needs more input than was given. If the program did not halt, calling `run_batch`
+    /// again with more input will resume where it left off. If the program faults, the output
+    /// it produced before the fault comes with the error.
+    pub fn run_batch(&mut self, input: &[C]) -> Result<(Vec<C>, RunState<C>), BatchFault<C>> {
+        let mut output = Vec::new();
+        let state = self.run_io(&mut (input.iter().copied(), |v| output.push(v)));
+        match state.into_result() {
+            Ok(state) => Ok((output, state)),
+            Err(fault) => Err(BatchFault { fault, output }),
+        }
+    }
+
+    /// Runs the program until it halts, needs more input, reaches a breakpoint, faults, or runs
+    /// out of budget, passing input and output through `io` one value at a time as the program
+    /// executes. Since output is passed to `io`, this never stops with `RunState::Output`.
+    pub fn run_io<T: Io<C> + ?Sized>(&mut self, io: &mut T) -> RunState<C> {
+        let mut meter = Meter::new(self.budget);
+        loop {
+            match self.run_metered(&mut iter::from_fn(|| io.input()), &mut meter) {
+                RunState::Output { value, .. } => io.output(value),
+                state => return state,
+            }
+        }
+    }
+
+    /// Runs the program until it halts, needs more input than `input` can provide, produces
+    /// output, reaches a breakpoint, faults, or runs out of budget.
+    pub fn run<I: Iterator<Item = C> + ?Sized>(&mut self, input: &mut I) -> RunState<C> {
+        self.run_metered(input, &mut Meter::new(self.budget))
+    }
+
+    fn run_metered<I: Iterator<Item = C> + ?Sized>(
+        &mut self,
+        input: &mut I,
+        meter: &mut Meter,
+    ) -> RunState<C> {
+        // if we stopped at a breakpoint last time, we need to get past it before checking
+        // breakpoints again
+        let mut resume = self.resume_breakpoint.take() == Some(self.ip);
+        loop {
+            // hashing the ip on every instruction is surprisingly expensive
+            if !resume && !self.breakpoints.is_empty() && self.breakpoints.contains(&self.ip) {
+                self.resume_breakpoint = Some(self.ip);
+                return RunState::Breakpoint { ip: self.ip };
+            }
+            resume = false;
+
+            if meter.exhausted() {
+                return RunState::BudgetExhausted { ip: self.ip };
+            }
+            if let Some(state) = self.step(input) {
+                return state;
+            }
+        }
+    }
+
+    /// Executes a single instruction. Returns `None` if the instruction completed and the VM is
+    /// ready to execute the next one. Breakpoints are ignored.
+    pub fn step<I: Iterator<Item = C> + ?Sized>(&mut self, input: &mut I) -> Option<RunState<C>> {
+        if self.tracer.is_some() {
+            return self.step_traced(input);
+        }
+        let ip = self.ip;
+        match self.execute(input) {
+            Ok(state) => state,
+            Err(kind) => Some(RunState::Fault(Fault { ip, kind })),
+        }
+    }
+
+    /// `step`, but tells the tracer about the instruction. This is kept out of `step` so that
+    /// the untraced path stays small.
+    fn step_traced<I: Iterator<Item = C> + ?Sized>(
+        &mut self,
+        input: &mut I,
+    ) -> Option<RunState<C>> {
+        let ip = self.ip;
+        let traced = self.inspect();
+        let state = match self.execute(input) {
+            Ok(state) => state,
+            Err(kind) => Some(RunState::Fault(Fault { ip, kind })),
+        };
+
+        // instructions that didn't run (ie, waiting for input) aren't traced
+        if let Some((mut event, destination)) = traced {
+            match state {
+                None | Some(RunState::Output { .. }) | Some(RunState::Halted { .. }) => {
+                    event.write = destination.map(|address| (address, self.code[address]));
+                    if let Some(tracer) = self.tracer.as_mut() {
+                        tracer.trace(&event);
+                    }
+                }
+                _ => (),
+            }
+        }
+        state
+    }
+
+    /// Decodes the instruction at `ip` for the tracer, before it's executed: its operands, the
+    /// values of the parameters it reads, and the address it will write to. Returns `None` if the
+    /// instruction is going to fault.
+    fn inspect(&mut self) -> Option<(Event<C>, Option<usize>)> {
+        let ip = self.ip;
+        let opcode = Opcode::try_from(self.fetch(ip).ok()?.to_i64()?).ok()?;
+        let modes = opcode.modes();
+        let mut operands = Vec::new();
+        let mut values = Vec::new();
+        let mut destination = None;
+        for (i, &&mode) in modes.iter().enumerate() {
+            let sp = ip + 1 + i;
+            operands.push(self.fetch(sp).ok()?);
+            if opcode.writes() && i == modes.len() - 1 {
+                destination = Some(self.output_operand(sp, mode).ok()?);
+            } else {
+                values.push(self.operand(sp, mode).ok()?);
+            }
+        }
+        let event = Event {
+            ip,
+            opcode,
+            operands,
+            values,
+            write: None,
+        };
+        Some((event, destination))
+    }
+
+    fn execute<I: Iterator<Item = C> + ?Sized>(
+        &mut self,
+        input: &mut I,
+    ) -> Result<Option<RunState<C>>, FaultKind> {
+        let ip = self.ip;
+        let code = self.fetch(ip)?;
+        let opcode = self.decoded.decode(ip, code, self.code.len())?;
+        self.ip = match opcode {
+            Opcode::Add(mode1, mode2, mode3) => {
+                let (op1, op2) = self.operands2(ip + 1, mode1, mode2)?;
+                let op3 = self.output_operand(ip + 3, mode3)?;
+                self.code[op3] = self.arithmetic.add(op1, op2).ok_or(FaultKind::Overflow)?;
+                ip + 4
+            }
+            Opcode::Multiply(mode1, mode2, mode3) => {
+                let (op1, op2) = self.operands2(ip + 1, mode1, mode2)?;
+                let op3 = self.output_operand(ip + 3, mode3)?;
+                self.code[op3] = self.arithmetic.mul(op1, op2).ok_or(FaultKind::Overflow)?;
+                ip + 4
+            }
+            Opcode::Input(mode) => {
+                let op = self.output_operand(ip + 1, mode)?;
+                match input.next() {
+                    Some(value) => self.code[op] = value,
+                    // not enough input; suspend
+                    None => return Ok(Some(RunState::NeedsInput { ip })),
+                }
+                ip + 2
+            }
+            Opcode::Output(mode) => {
+                let value = self.operand(ip + 1, mode)?;
+                self.ip = ip + 2;
+                return Ok(Some(RunState::Output { ip, value }));
+            }
+            Opcode::JumpIfTrue(mode1, mode2) => {
+                let (op1, op2) = self.operands2(ip + 1, mode1, mode2)?;
+                if op1 != C::default() {
+                    self.code.address(op2.to_i128())?
+                } else {
+                    ip + 3
+                }
+            }
+            Opcode::JumpIfFalse(mode1, mode2) => {
+                let (op1, op2) = self.operands2(ip + 1, mode1, mode2)?;
+                if op1 == C::default() {
+                    self.code.address(op2.to_i128())?
+                } else {
+                    ip + 3
+                }
+            }
+            Opcode::LessThan(mode1, mode2, mode3) => {
+                let (op1, op2) = self.operands2(ip + 1, mode1, mode2)?;
+                let op3 = self.output_operand(ip + 3, mode3)?;
+                self.code[op3] = C::from_i64(if op1 < op2 { 1 } else { 0 });
+                ip + 4
+            }
+            Opcode::Equal(mode1, mode2, mode3) => {
+                let (op1, op2) = self.operands2(ip + 1, mode1, mode2)?;
+                let op3 = self.output_operand(ip + 3, mode3)?;
+                self.code[op3] = C::from_i64(if op1 == op2 { 1 } else { 0 });
+                ip + 4
+            }
+            Opcode::RelativeBaseOffset(mode) => {
+                let op = self.operand(ip + 1, mode)?;
+                self.relative_base =
+                    isize::try_from(self.relative(op)).map_err(|_| FaultKind::Overflow)?;
+                ip + 2
+            }
+            Opcode::Halt => return Ok(Some(RunState::Halted { ip })),
+        };
+        Ok(None)
+    }
+}
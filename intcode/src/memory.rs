@@ -0,0 +1,226 @@
+use crate::cell::Cell;
+use crate::error::InvalidAddress;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+const PAGE_BITS: usize = 10;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+
+/// Writes up to this many pages past the end of the dense region grow the dense region instead of
+/// starting a sparse page. Intcode programs tend to keep their variables just past the end of the
+/// program, so this keeps them on the fast path.
+const DENSE_SLACK: usize = 16;
+
+/// The default limit on addresses. Far pages are sparse, so a large limit is cheap; it just
+/// catches programs that have gone off the rails.
+pub const DEFAULT_LIMIT: usize = 1 << 48;
+
+type Page<C> = Arc<Vec<C>>;
+
+/// The memory of an Intcode computer: an array of cells, all initially zero, from address 0 up to
+/// a configurable limit.
+///
+/// Memory is split into pages. Pages at the start of memory, where the program lives, are kept in
+/// a dense vector so that reads and writes are fast; pages far from the program are only created
+/// when they're written to. Pages are shared between clones until one of them writes to the page
+/// (copy-on-write), so cloning a machine to try something out only copies the pages that the clone
+/// actually changes.
+#[derive(Clone)]
+pub struct Memory<C = i64> {
+    dense: Vec<Page<C>>,
+    sparse: BTreeMap<usize, Page<C>>,
+    len: usize,
+    limit: usize,
+
+    /// Something for `index` to return a reference to for cells that haven't been written.
+    zero: C,
+}
+
+fn split(i: usize) -> (usize, usize) {
+    (i >> PAGE_BITS, i & (PAGE_SIZE - 1))
+}
+
+impl<C: Cell> Default for Memory<C> {
+    fn default() -> Memory<C> {
+        Memory {
+            dense: Vec::new(),
+            sparse: BTreeMap::new(),
+            len: 0,
+            limit: DEFAULT_LIMIT,
+            zero: C::default(),
+        }
+    }
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory::default()
+    }
+}
+
+impl<C: Cell> Memory<C> {
+    /// The length of the dense region: one past the highest address in it that was loaded or
+    /// written. Sparse pages aren't counted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Sets the address limit: `address` will reject anything at or past it.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    /// Converts an address computed by a program into an index into memory, checking that it's
+    /// neither negative nor past the limit.
+    pub fn address(&self, address: i128) -> Result<usize, InvalidAddress> {
+        if address < 0 || address >= self.limit as i128 {
+            Err(InvalidAddress(address))
+        } else {
+            Ok(address as usize)
+        }
+    }
+
+    /// Returns the value at `i`. Cells that have never been written are zero.
+    pub fn get(&self, i: usize) -> C {
+        self[i]
+    }
+
+    /// The slow path for writes: grows the dense region, or finds or creates a sparse page.
+    fn page_mut(&mut self, page: usize) -> &mut Vec<C> {
+        if page >= self.dense.len() + DENSE_SLACK {
+            let page = self
+                .sparse
+                .entry(page)
+                .or_insert_with(|| Arc::new(vec![C::default(); PAGE_SIZE]));
+            return Arc::make_mut(page);
+        }
+
+        // new pages all start out sharing the same zeroed page. If a sparse page has already been
+        // written, it becomes part of the dense region, and so do its contents.
+        let zeroes = Arc::new(vec![C::default(); PAGE_SIZE]);
+        while self.dense.len() <= page {
+            let start = self.dense.len() << PAGE_BITS;
+            let next = match self.sparse.remove(&self.dense.len()) {
+                Some(next) => {
+                    if let Some(last) = next.iter().rposition(|&value| value != C::default()) {
+                        self.len = self.len.max(start + last + 1);
+                    }
+                    next
+                }
+                None => zeroes.clone(),
+            };
+            self.dense.push(next);
+        }
+        Arc::make_mut(&mut self.dense[page])
+    }
+
+    /// Iterates over the dense region.
+    pub fn iter(&self) -> impl Iterator<Item = C> + '_ {
+        self.dense
+            .iter()
+            .flat_map(|page| page.iter())
+            .copied()
+            .take(self.len)
+    }
+
+    /// Copies the dense region into a `Vec`.
+    pub fn to_vec(&self) -> Vec<C> {
+        self.iter().collect()
+    }
+
+    /// Iterates over the sparse pages that have been written, as the address of the start of the
+    /// page and its contents.
+    pub fn sparse_pages(&self) -> impl Iterator<Item = (usize, &[C])> + '_ {
+        self.sparse
+            .iter()
+            .map(|(&page, cells)| (page << PAGE_BITS, &cells[..]))
+    }
+
+    /// The address and value of every non-zero cell outside of the dense region.
+    fn sparse_cells(&self) -> impl Iterator<Item = (usize, C)> + '_ {
+        self.sparse_pages().flat_map(|(start, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .filter(|(_, &value)| value != C::default())
+                .map(move |(i, &value)| (start + i, value))
+        })
+    }
+}
+
+impl<C: Cell> From<Vec<C>> for Memory<C> {
+    fn from(code: Vec<C>) -> Memory<C> {
+        let len = code.len();
+        let mut dense: Vec<_> = code
+            .chunks(PAGE_SIZE)
+            .map(|chunk| Arc::new(chunk.to_vec()))
+            .collect();
+        if let Some(page) = dense.last_mut() {
+            Arc::make_mut(page).resize(PAGE_SIZE, C::default());
+        }
+        Memory {
+            dense,
+            len,
+            ..Memory::default()
+        }
+    }
+}
+
+/// Reading never fails: cells that have never been written are zero.
+impl<C: Cell> Index<usize> for Memory<C> {
+    type Output = C;
+
+    fn index(&self, i: usize) -> &C {
+        let (page, offset) = split(i);
+        match self.dense.get(page) {
+            Some(page) => &page[offset],
+            None => self
+                .sparse
+                .get(&page)
+                .map_or(&self.zero, |page| &page[offset]),
+        }
+    }
+}
+
+/// Writing creates the page if necessary. The address limit is not checked; use `address` to
+/// validate addresses that come from a program.
+impl<C: Cell> IndexMut<usize> for Memory<C> {
+    fn index_mut(&mut self, i: usize) -> &mut C {
+        let (page, offset) = split(i);
+        if page < self.dense.len() + DENSE_SLACK && i >= self.len {
+            self.len = i + 1;
+        }
+        if page < self.dense.len() {
+            &mut Arc::make_mut(&mut self.dense[page])[offset]
+        } else {
+            &mut self.page_mut(page)[offset]
+        }
+    }
+}
+
+impl<C: Cell> PartialEq for Memory<C> {
+    fn eq(&self, other: &Memory<C>) -> bool {
+        self.len == other.len
+            && self.iter().eq(other.iter())
+            && self.sparse_cells().eq(other.sparse_cells())
+    }
+}
+
+impl<C: Cell> fmt::Debug for Memory<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memory")
+            .field("dense", &self.to_vec())
+            .field("sparse", &self.sparse_cells().collect::<Vec<_>>())
+            .finish()
+    }
+}
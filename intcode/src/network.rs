@@ -0,0 +1,393 @@
+use crate::error::NetworkError;
+use crate::io::Io;
+use crate::machine::Intcode;
+use crate::state::RunState;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+/// What a computer reads when it asks for a packet and there isn't one.
+pub const NO_PACKET: i64 = -1;
+
+/// A packet on the network. Computers send a packet by outputting its address, `x`, and `y`, and
+/// receive one by reading `x` and `y`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Packet {
+    pub address: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+/// What the network should do after its router has handled something.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Route<T> {
+    /// Deliver these packets, if any, and keep going. Packets to addresses that don't have a
+    /// computer are dropped.
+    Send(Vec<Packet>),
+
+    /// Stop the network; `Network::run` returns this value.
+    Stop(T),
+}
+
+/// Decides where packets go. Every packet a computer sends passes through the router, which can
+/// forward it, drop it, remember it, or stop the network. The router is also told when the whole
+/// network goes idle, so it can wake it up again, ie, the NAT on day 23.
+pub trait Router {
+    type Output;
+
+    /// Called with every packet that a computer sends. Return `Route::Send(vec![packet])` to
+    /// deliver it as is.
+    fn route(&mut self, packet: Packet) -> Route<Self::Output>;
+
+    /// Called when every computer is idle. If this doesn't send anything or stop the network,
+    /// nothing will ever happen again, so `Network::run` fails with `NetworkError::Deadlock`.
+    fn idle(&mut self) -> Route<Self::Output>;
+}
+
+/// How a `Network` runs its computers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schedule {
+    /// Runs each computer in turn on the calling thread, until it needs input. A computer that
+    /// has no packets waiting reads `NO_PACKET` once per turn. Every run of the network sends the
+    /// same packets in the same order.
+    RoundRobin,
+
+    /// Runs each computer on its own thread. Packets arrive in whatever order the threads happen
+    /// to run, so the network is only as deterministic as the programs on it.
+    Threaded,
+}
+
+/// A network of Intcode computers, like the one on day 23. Each computer's first input is its
+/// address, which is its index in `computers`. After that, it reads packets that other computers
+/// have sent it, or `NO_PACKET` if there aren't any, and sends packets by outputting them.
+///
+/// A computer is idle once it has read `NO_PACKET` `idle_reads` times in a row without sending
+/// anything, and asks for input again while there's still nothing for it. The network is idle
+/// when every computer is idle, or halted, and no packets are on their way.
+pub struct Network {
+    pub computers: Vec<Intcode>,
+    pub schedule: Schedule,
+    pub idle_reads: usize,
+}
+
+impl Network {
+    /// A network of `size` computers, all running `program`, scheduled round-robin. A computer
+    /// is idle after 2 empty reads.
+    pub fn new(program: &Intcode, size: usize) -> Network {
+        Network {
+            computers: vec![program.clone(); size],
+            schedule: Schedule::RoundRobin,
+            idle_reads: 2,
+        }
+    }
+
+    /// Runs the network until the router stops it, or a computer faults.
+    pub fn run<R: Router>(self, router: &mut R) -> Result<R::Output, NetworkError> {
+        match self.schedule {
+            Schedule::RoundRobin => self.run_round_robin(router),
+            Schedule::Threaded => self.run_threaded(router),
+        }
+    }
+
+    fn run_round_robin<R: Router>(mut self, router: &mut R) -> Result<R::Output, NetworkError> {
+        let mut nics: Vec<_> = (0..self.computers.len()).map(QueueNic::new).collect();
+        let mut halted = vec![false; self.computers.len()];
+        loop {
+            for (address, computer) in self.computers.iter_mut().enumerate() {
+                if halted[address] {
+                    continue;
+                }
+                nics[address].turn(self.idle_reads);
+                match computer.run_io(&mut nics[address]) {
+                    RunState::Fault(fault) => return Err(NetworkError::Fault { address, fault }),
+                    RunState::Halted { .. } => halted[address] = true,
+                    _ => (),
+                }
+
+                for packet in nics[address].outgoing.split_off(0) {
+                    match router.route(packet) {
+                        Route::Send(packets) => deliver_to_queues(&mut nics, &halted, packets),
+                        Route::Stop(result) => return Ok(result),
+                    }
+                }
+            }
+
+            let idle = nics
+                .iter()
+                .zip(halted.iter())
+                .all(|(nic, &halted)| halted || nic.is_idle(self.idle_reads));
+            if idle {
+                match router.idle() {
+                    Route::Send(packets) if packets.is_empty() => {
+                        return Err(NetworkError::Deadlock)
+                    }
+                    Route::Send(packets) => deliver_to_queues(&mut nics, &halted, packets),
+                    Route::Stop(result) => return Ok(result),
+                }
+            }
+        }
+    }
+
+    fn run_threaded<R: Router>(self, router: &mut R) -> Result<R::Output, NetworkError> {
+        let size = self.computers.len();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (messages, inbox) = mpsc::channel();
+        let mut incoming = Vec::new();
+        let mut threads = Vec::new();
+        for (address, mut computer) in self.computers.into_iter().enumerate() {
+            let (sender, receiver) = mpsc::channel();
+            incoming.push(sender);
+            let mut nic = ChannelNic {
+                address,
+                idle_reads: self.idle_reads,
+                pending: VecDeque::from(vec![address as i64]),
+                incoming: receiver,
+                received: 0,
+                empty_reads: 0,
+                partial: Vec::with_capacity(3),
+                messages: messages.clone(),
+                stop: stop.clone(),
+            };
+            threads.push(thread::spawn(move || {
+                let state = computer.run_io(&mut nic);
+                nic.messages.send(Message::Stopped(address, state)).ok();
+            }));
+        }
+        drop(messages);
+
+        let mut network = Switchboard {
+            incoming,
+            delivered: vec![0; size],
+            idle: vec![false; size],
+            halted: vec![false; size],
+        };
+        let result = network.run(router, &inbox);
+
+        // computers waiting for a packet wake up when their channel closes; the rest notice the
+        // stop flag the next time they read
+        stop.store(true, Ordering::Relaxed);
+        drop(network);
+        for thread in threads {
+            thread.join().ok();
+        }
+        result
+    }
+}
+
+/// Queues delivered packets for computers in a round-robin network.
+fn deliver_to_queues(nics: &mut [QueueNic], halted: &[bool], packets: Vec<Packet>) {
+    for packet in packets {
+        let address = packet.address as usize;
+        if packet.address >= 0 && address < nics.len() && !halted[address] {
+            nics[address].incoming.push_back(packet.x);
+            nics[address].incoming.push_back(packet.y);
+        }
+    }
+}
+
+/// Collects a computer's output into packets.
+fn packet(partial: &mut Vec<i64>, value: i64) -> Option<Packet> {
+    partial.push(value);
+    if partial.len() < 3 {
+        return None;
+    }
+    let packet = Packet {
+        address: partial[0],
+        x: partial[1],
+        y: partial[2],
+    };
+    partial.clear();
+    Some(packet)
+}
+
+/// The network interface for a computer in a round-robin network.
+struct QueueNic {
+    incoming: VecDeque<i64>,
+    outgoing: Vec<Packet>,
+    partial: Vec<i64>,
+    empty_reads: usize,
+    idle_reads: usize,
+    read_this_turn: bool,
+}
+
+impl QueueNic {
+    fn new(address: usize) -> QueueNic {
+        QueueNic {
+            incoming: VecDeque::from(vec![address as i64]),
+            outgoing: Vec::new(),
+            partial: Vec::with_capacity(3),
+            empty_reads: 0,
+            idle_reads: 0,
+            read_this_turn: false,
+        }
+    }
+
+    fn turn(&mut self, idle_reads: usize) {
+        self.idle_reads = idle_reads;
+        self.read_this_turn = false;
+    }
+
+    fn is_idle(&self, idle_reads: usize) -> bool {
+        self.incoming.is_empty() && self.empty_reads >= idle_reads
+    }
+}
+
+impl Io for QueueNic {
+    fn input(&mut self) -> Option<i64> {
+        if let Some(value) = self.incoming.pop_front() {
+            self.empty_reads = 0;
+            return Some(value);
+        }
+
+        // an idle computer would just read NO_PACKET forever, so don't bother running it
+        if self.read_this_turn || self.empty_reads >= self.idle_reads {
+            return None;
+        }
+        self.read_this_turn = true;
+        self.empty_reads += 1;
+        Some(NO_PACKET)
+    }
+
+    fn output(&mut self, value: i64) {
+        self.empty_reads = 0;
+        if let Some(packet) = packet(&mut self.partial, value) {
+            self.outgoing.push(packet);
+        }
+    }
+}
+
+/// What a computer's thread tells the switchboard.
+enum Message {
+    Packet(usize, Packet),
+
+    /// The computer is idle, having received this many packets.
+    Idle(usize, u64),
+
+    Stopped(usize, RunState),
+}
+
+/// The network interface for a computer running on its own thread.
+struct ChannelNic {
+    address: usize,
+    idle_reads: usize,
+    pending: VecDeque<i64>,
+    incoming: Receiver<(i64, i64)>,
+    received: u64,
+    empty_reads: usize,
+    partial: Vec<i64>,
+    messages: Sender<Message>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ChannelNic {
+    fn receive(&mut self, (x, y): (i64, i64)) -> Option<i64> {
+        self.received += 1;
+        self.empty_reads = 0;
+        self.pending.push_back(y);
+        Some(x)
+    }
+}
+
+impl Io for ChannelNic {
+    fn input(&mut self) -> Option<i64> {
+        if self.stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        if let Some(value) = self.pending.pop_front() {
+            return Some(value);
+        }
+
+        match self.incoming.try_recv() {
+            Ok(packet) => self.receive(packet),
+            Err(TryRecvError::Disconnected) => None,
+
+            // an idle computer would just read NO_PACKET forever, so tell the switchboard and
+            // wait for a packet instead
+            Err(TryRecvError::Empty) if self.empty_reads >= self.idle_reads => {
+                let idle = Message::Idle(self.address, self.received);
+                self.messages.send(idle).ok()?;
+                let packet = self.incoming.recv().ok()?;
+                self.receive(packet)
+            }
+            Err(TryRecvError::Empty) => {
+                self.empty_reads += 1;
+                thread::yield_now();
+                Some(NO_PACKET)
+            }
+        }
+    }
+
+    fn output(&mut self, value: i64) {
+        self.empty_reads = 0;
+        if let Some(packet) = packet(&mut self.partial, value) {
+            self.messages
+                .send(Message::Packet(self.address, packet))
+                .ok();
+        }
+    }
+}
+
+/// The threaded network's view of its computers: where to send their packets, and which ones
+/// are idle.
+struct Switchboard {
+    incoming: Vec<Sender<(i64, i64)>>,
+    delivered: Vec<u64>,
+    idle: Vec<bool>,
+    halted: Vec<bool>,
+}
+
+impl Switchboard {
+    fn deliver(&mut self, packets: Vec<Packet>) {
+        for packet in packets {
+            let address = packet.address as usize;
+            if packet.address >= 0 && address < self.incoming.len() && !self.halted[address] {
+                self.incoming[address].send((packet.x, packet.y)).ok();
+                self.delivered[address] += 1;
+                self.idle[address] = false;
+            }
+        }
+    }
+
+    fn run<R: Router>(
+        &mut self,
+        router: &mut R,
+        inbox: &Receiver<Message>,
+    ) -> Result<R::Output, NetworkError> {
+        loop {
+            match inbox.recv() {
+                Ok(Message::Packet(from, packet)) => {
+                    self.idle[from] = false;
+                    match router.route(packet) {
+                        Route::Send(packets) => self.deliver(packets),
+                        Route::Stop(result) => return Ok(result),
+                    }
+                }
+
+                // if a packet was delivered after the computer went idle, it isn't idle anymore
+                Ok(Message::Idle(address, received)) => {
+                    self.idle[address] = received == self.delivered[address];
+                }
+                Ok(Message::Stopped(address, state)) => match state.into_result() {
+                    Err(fault) => return Err(NetworkError::Fault { address, fault }),
+                    Ok(_) => {
+                        self.halted[address] = true;
+                        self.idle[address] = true;
+                    }
+                },
+                Err(_) => return Err(NetworkError::Deadlock),
+            }
+
+            if self.idle.iter().all(|&idle| idle) {
+                match router.idle() {
+                    Route::Send(packets) if packets.is_empty() => {
+                        return Err(NetworkError::Deadlock)
+                    }
+                    Route::Send(packets) => self.deliver(packets),
+                    Route::Stop(result) => return Ok(result),
+                }
+            }
+        }
+    }
+}
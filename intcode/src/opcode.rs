@@ -0,0 +1,171 @@
+use crate::error::{InvalidOpcode, InvalidParameterMode};
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{self, Display};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl Display for ParameterMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ParameterMode::Position => "'position'",
+                ParameterMode::Immediate => "'immediate'",
+                ParameterMode::Relative => "'relative'",
+            }
+        )
+    }
+}
+
+impl TryFrom<i64> for ParameterMode {
+    type Error = InvalidParameterMode;
+
+    fn try_from(code: i64) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(ParameterMode::Position),
+            1 => Ok(ParameterMode::Immediate),
+            2 => Ok(ParameterMode::Relative),
+            _ => Err(InvalidParameterMode(code)),
+        }
+    }
+}
+
+impl ParameterMode {
+    fn modes2(code: i64) -> Result<(Self, Self), InvalidParameterMode> {
+        Ok(((code % 10).try_into()?, ((code / 10) % 10).try_into()?))
+    }
+
+    fn modes3(code: i64) -> Result<(Self, Self, Self), InvalidParameterMode> {
+        Ok((
+            (code % 10).try_into()?,
+            ((code / 10) % 10).try_into()?,
+            ((code / 100) % 10).try_into()?,
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Opcode {
+    Add(ParameterMode, ParameterMode, ParameterMode),
+    Multiply(ParameterMode, ParameterMode, ParameterMode),
+    Input(ParameterMode),
+    Output(ParameterMode),
+    JumpIfTrue(ParameterMode, ParameterMode),
+    JumpIfFalse(ParameterMode, ParameterMode),
+    LessThan(ParameterMode, ParameterMode, ParameterMode),
+    Equal(ParameterMode, ParameterMode, ParameterMode),
+    RelativeBaseOffset(ParameterMode),
+    Halt,
+}
+
+impl TryFrom<i64> for Opcode {
+    type Error = InvalidOpcode;
+
+    fn try_from(code: i64) -> Result<Self, Self::Error> {
+        match code % 100 {
+            1 => {
+                let (mode1, mode2, mode3) =
+                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::Add(mode1, mode2, mode3))
+            }
+            2 => {
+                let (mode1, mode2, mode3) =
+                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::Multiply(mode1, mode2, mode3))
+            }
+            3 => {
+                let mode = (code / 100)
+                    .try_into()
+                    .map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::Input(mode))
+            }
+            4 => {
+                let mode = (code / 100)
+                    .try_into()
+                    .map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::Output(mode))
+            }
+            5 => {
+                let (mode1, mode2) =
+                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::JumpIfTrue(mode1, mode2))
+            }
+            6 => {
+                let (mode1, mode2) =
+                    ParameterMode::modes2(code / 100).map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::JumpIfFalse(mode1, mode2))
+            }
+            7 => {
+                let (mode1, mode2, mode3) =
+                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::LessThan(mode1, mode2, mode3))
+            }
+            8 => {
+                let (mode1, mode2, mode3) =
+                    ParameterMode::modes3(code / 100).map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::Equal(mode1, mode2, mode3))
+            }
+            9 => {
+                let mode = (code / 100)
+                    .try_into()
+                    .map_err(|_| InvalidOpcode(code.into()))?;
+                Ok(Opcode::RelativeBaseOffset(mode))
+            }
+            99 => Ok(Opcode::Halt),
+            _ => Err(InvalidOpcode(code.into())),
+        }
+    }
+}
+
+impl Opcode {
+    /// The short name used for the opcode in disassembly, ie, `ADD` or `JT`.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Add(..) => "ADD",
+            Opcode::Multiply(..) => "MUL",
+            Opcode::Input(_) => "IN",
+            Opcode::Output(_) => "OUT",
+            Opcode::JumpIfTrue(..) => "JT",
+            Opcode::JumpIfFalse(..) => "JF",
+            Opcode::LessThan(..) => "LT",
+            Opcode::Equal(..) => "EQ",
+            Opcode::RelativeBaseOffset(_) => "ARB",
+            Opcode::Halt => "HLT",
+        }
+    }
+
+    /// The parameter modes of each of the opcode's parameters, in order.
+    pub fn modes(&self) -> Vec<&ParameterMode> {
+        match self {
+            Opcode::Add(mode1, mode2, mode3)
+            | Opcode::Multiply(mode1, mode2, mode3)
+            | Opcode::LessThan(mode1, mode2, mode3)
+            | Opcode::Equal(mode1, mode2, mode3) => vec![mode1, mode2, mode3],
+            Opcode::JumpIfTrue(mode1, mode2) | Opcode::JumpIfFalse(mode1, mode2) => {
+                vec![mode1, mode2]
+            }
+            Opcode::Input(mode) | Opcode::Output(mode) | Opcode::RelativeBaseOffset(mode) => {
+                vec![mode]
+            }
+            Opcode::Halt => vec![],
+        }
+    }
+
+    /// True if the opcode's last parameter is an address the opcode writes to.
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Add(..)
+                | Opcode::Multiply(..)
+                | Opcode::Input(_)
+                | Opcode::LessThan(..)
+                | Opcode::Equal(..)
+        )
+    }
+}
@@ -0,0 +1,111 @@
+use crate::opcode::Opcode;
+use crate::trace::{Event, Tracer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display};
+
+/// How many of the hottest addresses to show when a profile is displayed.
+const HOTTEST: usize = 10;
+
+/// A `Tracer` that counts what the program spends its time doing: how many times each address
+/// was executed, how many times each opcode was executed, how much I/O the program did, and how
+/// many instructions it executed between inputs. Interactive programs tend to do all of their
+/// work between inputs, ie, the day 13 arcade game reads the joystick once per frame.
+///
+/// Displaying a profile prints a report.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub instructions: u64,
+    pub addresses: HashMap<usize, u64>,
+    pub opcodes: BTreeMap<&'static str, u64>,
+    pub inputs: u64,
+    pub outputs: u64,
+
+    /// The number of instructions executed before the first input, between each pair of inputs,
+    /// and since the last input.
+    pub between_inputs: Vec<u64>,
+}
+
+impl Profile {
+    /// The `n` most executed addresses and how many times each was executed, most executed first.
+    pub fn hottest(&self, n: usize) -> Vec<(usize, u64)> {
+        let mut addresses: Vec<_> = self
+            .addresses
+            .iter()
+            .map(|(&address, &count)| (address, count))
+            .collect();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        addresses.truncate(n);
+        addresses
+    }
+}
+
+impl<C> Tracer<C> for Profile {
+    fn trace(&mut self, event: &Event<C>) {
+        self.instructions += 1;
+        *self.addresses.entry(event.ip).or_default() += 1;
+        *self.opcodes.entry(event.opcode.mnemonic()).or_default() += 1;
+        if self.between_inputs.is_empty() {
+            self.between_inputs.push(0);
+        }
+        match event.opcode {
+            Opcode::Input(_) => {
+                self.inputs += 1;
+                self.between_inputs.push(0);
+            }
+            Opcode::Output(_) => self.outputs += 1,
+            _ => (),
+        }
+        if let Some(count) = self.between_inputs.last_mut() {
+            *count += 1;
+        }
+    }
+}
+
+fn percent(count: u64, total: u64) -> f64 {
+    100.0 * count as f64 / total.max(1) as f64
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} instructions, {} inputs, {} outputs",
+            self.instructions, self.inputs, self.outputs
+        )?;
+
+        if self.between_inputs.len() > 1 {
+            let max = self.between_inputs.iter().max().copied().unwrap_or(0);
+            writeln!(
+                f,
+                "{:.1} instructions between inputs on average, at most {}",
+                self.instructions as f64 / self.between_inputs.len() as f64,
+                max
+            )?;
+        }
+
+        writeln!(f, "\nOpcodes:")?;
+        let mut opcodes: Vec<_> = self.opcodes.iter().collect();
+        opcodes.sort_by(|a, b| b.1.cmp(a.1));
+        for (mnemonic, &count) in opcodes {
+            writeln!(
+                f,
+                "  {:<4} {:>12} {:>6.2}%",
+                mnemonic,
+                count,
+                percent(count, self.instructions)
+            )?;
+        }
+
+        writeln!(f, "\nHottest addresses:")?;
+        for (address, count) in self.hottest(HOTTEST) {
+            writeln!(
+                f,
+                "  {:04} {:>12} {:>6.2}%",
+                address,
+                count,
+                percent(count, self.instructions)
+            )?;
+        }
+        Ok(())
+    }
+}
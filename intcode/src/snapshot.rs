@@ -0,0 +1,173 @@
+use crate::cell::Cell;
+use crate::memory::Memory;
+use std::error::Error;
+use std::io::{BufRead, Write};
+
+const HEADER: &str = "intcode snapshot 1";
+
+/// The most values a field of a snapshot can expand to. That's far more than any program's memory
+/// is likely to need, but keeps a huge run in a corrupt file from using up all of memory.
+const MAX_VALUES: usize = 1 << 24;
+
+/// Everything needed to put a program back the way it was: its memory and registers, plus any
+/// input it hadn't consumed yet and any output that hadn't been handled yet. The VM doesn't know
+/// about either of those, so `Intcode::snapshot` leaves them empty, and callers that queue I/O
+/// have to fill them in themselves.
+///
+/// Taking a snapshot is cheap since memory is copy-on-write. Snapshots can also be saved to a
+/// file, in a text format that looks like this:
+///
+/// ```text
+/// intcode snapshot 1
+/// ip 10
+/// relative_base 2000
+/// input 110,111,114,116,104,10
+/// output
+/// memory 109,2000,3,100,0*96,42
+/// ```
+///
+/// `memory` is written in the same comma-separated format as a puzzle input, except that runs of
+/// the same value are written as `value*count`. Pages of memory that are far away from the program
+/// are written separately, after the address they start at, ie, `page 1048576 0*16,7`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot<C: Cell = i64> {
+    pub memory: Memory<C>,
+    pub ip: usize,
+    pub relative_base: isize,
+    pub input: Vec<C>,
+    pub output: Vec<C>,
+}
+
+fn write_values<C: Cell, W: Write, I: Iterator<Item = C>>(
+    writer: &mut W,
+    name: &str,
+    values: I,
+) -> Result<(), Box<dyn Error>> {
+    let mut items = Vec::new();
+    let mut values = values.peekable();
+    while let Some(value) = values.next() {
+        let mut count = 1;
+        while values.peek() == Some(&value) {
+            values.next();
+            count += 1;
+        }
+
+        // a run has to be longer than 2 to be shorter than just writing the values out
+        if count > 2 {
+            items.push(format!("{}*{}", value, count));
+        } else {
+            items.extend((0..count).map(|_| value.to_string()));
+        }
+    }
+    if items.is_empty() {
+        writeln!(writer, "{}", name)?;
+    } else {
+        writeln!(writer, "{} {}", name, items.join(","))?;
+    }
+    Ok(())
+}
+
+/// Reads a comma-separated list of values, expanding runs. There can't be more than `limit` values
+/// in all, nor more than `MAX_VALUES`.
+fn read_values<C: Cell>(s: &str, limit: usize) -> Result<Vec<C>, Box<dyn Error>> {
+    let limit = limit.min(MAX_VALUES);
+    let mut values = Vec::new();
+    for value in s.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        let (value, count) = match value.find('*') {
+            Some(i) => (value[..i].parse()?, value[i + 1..].parse()?),
+            None => (value.parse()?, 1),
+        };
+        let len = values
+            .len()
+            .checked_add(count)
+            .filter(|&len| len <= limit)
+            .ok_or_else(|| format!("More than {} values in a snapshot field.", limit))?;
+        values.resize(len, value);
+    }
+    Ok(values)
+}
+
+impl Snapshot {
+    pub fn load<R: BufRead>(reader: R) -> Result<Snapshot, Box<dyn Error>> {
+        Snapshot::load_cells(reader)
+    }
+}
+
+impl<C: Cell> Snapshot<C> {
+    pub fn save<W: Write>(&self, mut writer: W) -> Result<(), Box<dyn Error>> {
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "ip {}", self.ip)?;
+        writeln!(writer, "relative_base {}", self.relative_base)?;
+        write_values(&mut writer, "input", self.input.iter().copied())?;
+        write_values(&mut writer, "output", self.output.iter().copied())?;
+        write_values(&mut writer, "memory", self.memory.iter())?;
+        for (start, cells) in self.memory.sparse_pages() {
+            // trailing zeroes don't need to be written
+            let len = cells
+                .iter()
+                .rposition(|&value| value != C::default())
+                .map_or(0, |i| i + 1);
+            if len > 0 {
+                let name = format!("page {}", start);
+                write_values(&mut writer, &name, cells[..len].iter().copied())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `load`, but for cell types other than `i64`.
+    pub fn load_cells<R: BufRead>(reader: R) -> Result<Snapshot<C>, Box<dyn Error>> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?;
+        if header.as_ref().map(|line| line.trim()) != Some(HEADER) {
+            return Err("Not an Intcode snapshot.".into());
+        }
+
+        let mut snapshot = Snapshot {
+            memory: Memory::default(),
+            ip: 0,
+            relative_base: 0,
+            input: Vec::new(),
+            output: Vec::new(),
+        };
+        let limit = snapshot.memory.limit();
+        for line in lines {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.find(' ') {
+                Some(i) => (&line[..i], &line[i + 1..]),
+                None => (line, ""),
+            };
+            match key {
+                "ip" => snapshot.ip = value.trim().parse()?,
+                "relative_base" => snapshot.relative_base = value.trim().parse()?,
+                "input" => snapshot.input = read_values(value, limit)?,
+                "output" => snapshot.output = read_values(value, limit)?,
+                "memory" => {
+                    let values = read_values(value, limit)?;
+                    for (i, value) in values.into_iter().enumerate() {
+                        snapshot.memory[i] = value;
+                    }
+                }
+                "page" => {
+                    let (start, values) = match value.find(' ') {
+                        Some(i) => (&value[..i], &value[i + 1..]),
+                        None => (value, ""),
+                    };
+                    let start: usize = start.parse()?;
+                    if start >= limit {
+                        return Err(format!("Page {} is past the address limit.", start).into());
+                    }
+                    for (i, value) in read_values(values, limit - start)?.into_iter().enumerate() {
+                        snapshot.memory[start + i] = value;
+                    }
+                }
+                _ => return Err(format!("Unknown snapshot field {}.", key).into()),
+            }
+        }
+        Ok(snapshot)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::cell::Cell;
+use crate::error::Fault;
+use std::fmt::{self, Display};
+
+/// Why the VM stopped running. Every state carries the instruction pointer of the instruction
+/// that caused the VM to stop.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunState<C = i64> {
+    /// The program executed the halt instruction. Running the VM again will just halt again.
+    Halted { ip: usize },
+
+    /// The program is waiting for input. Running the VM again with more input will resume the
+    /// input instruction at `ip`.
+    NeedsInput { ip: usize },
+
+    /// The output instruction at `ip` produced `value`. The VM is ready to continue with the next
+    /// instruction.
+    Output { ip: usize, value: C },
+
+    /// The VM reached a breakpoint. The instruction at `ip` has not been executed yet; running
+    /// the VM again will execute it.
+    Breakpoint { ip: usize },
+
+    /// The machine's budget ran out before the instruction at `ip` was executed. Running the VM
+    /// again will continue with a fresh budget.
+    BudgetExhausted { ip: usize },
+
+    /// The instruction at `fault.ip` could not be executed. Running the VM again will fault again.
+    Fault(Fault),
+}
+
+impl<C: Cell> RunState<C> {
+    pub fn ip(&self) -> usize {
+        match *self {
+            RunState::Halted { ip }
+            | RunState::NeedsInput { ip }
+            | RunState::Output { ip, .. }
+            | RunState::Breakpoint { ip }
+            | RunState::BudgetExhausted { ip }
+            | RunState::Fault(Fault { ip, .. }) => ip,
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        matches!(self, RunState::Halted { .. })
+    }
+
+    /// Turns a `Fault` into an `Err` so it can be propagated with `?`.
+    pub fn into_result(self) -> Result<RunState<C>, Fault> {
+        match self {
+            RunState::Fault(fault) => Err(fault),
+            state => Ok(state),
+        }
+    }
+}
+
+impl<C: Cell> Display for RunState<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunState::Halted { ip } => write!(f, "halted at {}", ip),
+            RunState::NeedsInput { ip } => write!(f, "waiting for input at {}", ip),
+            RunState::Output { ip, value } => write!(f, "output {} at {}", value, ip),
+            RunState::Breakpoint { ip } => write!(f, "breakpoint at {}", ip),
+            RunState::BudgetExhausted { ip } => write!(f, "out of budget at {}", ip),
+            RunState::Fault(Fault { ip, kind }) => write!(f, "fault at {}: {}", ip, kind),
+        }
+    }
+}
@@ -0,0 +1,658 @@
+use crate::budget::Meter;
+use crate::machine::Intcode;
+use crate::opcode::{Opcode, ParameterMode};
+use crate::state::RunState;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+/// Something a symbolic program's values can depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Var {
+    /// The nth value the program read, counting from 0. Displayed as `in0`, `in1`, ...
+    Input(usize),
+
+    /// The value a memory cell had before the program started, ie, day 2's noun and verb.
+    /// Displayed as `[address]`.
+    Cell(usize),
+}
+
+impl Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Var::Input(n) => write!(f, "in{}", n),
+            Var::Cell(address) => write!(f, "[{}]", address),
+        }
+    }
+}
+
+/// A value computed by a program, as a function of its `Var`s. Sums and products are kept as
+/// polynomials, so that a program that computes the same thing two different ways ends up with
+/// the same expression, and things that cancel out disappear.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Expr {
+    Const(i64),
+    Var(Var),
+    Poly(Poly),
+
+    /// 1 if the first is less than the second, otherwise 0.
+    Less(Rc<Expr>, Rc<Expr>),
+
+    /// 1 if they're equal, otherwise 0.
+    Equal(Rc<Expr>, Rc<Expr>),
+}
+
+/// The most terms a product of polynomials is multiplied out to. Bigger products are left as a
+/// product of their factors, so that expressions don't blow up.
+const MAX_TERMS: usize = 64;
+
+/// `constant + coefficient * factor * factor * ... + ...`. Factors are sorted, and are never
+/// constants or polynomials themselves, unless a polynomial was too big to multiply out.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Poly {
+    pub constant: i64,
+    pub terms: BTreeMap<Vec<Rc<Expr>>, i64>,
+}
+
+impl Poly {
+    fn from_expr(expr: &Rc<Expr>) -> Poly {
+        match &**expr {
+            Expr::Const(value) => Poly {
+                constant: *value,
+                terms: BTreeMap::new(),
+            },
+            Expr::Poly(poly) => poly.clone(),
+            _ => Poly {
+                constant: 0,
+                terms: vec![(vec![expr.clone()], 1)].into_iter().collect(),
+            },
+        }
+    }
+
+    fn add_term(&mut self, factors: Vec<Rc<Expr>>, coefficient: i64) -> Option<()> {
+        if factors.is_empty() {
+            self.constant = self.constant.checked_add(coefficient)?;
+            return Some(());
+        }
+        let sum = self
+            .terms
+            .get(&factors)
+            .unwrap_or(&0)
+            .checked_add(coefficient)?;
+        if sum == 0 {
+            self.terms.remove(&factors);
+        } else {
+            self.terms.insert(factors, sum);
+        }
+        Some(())
+    }
+
+    fn add(mut self, other: &Poly) -> Option<Poly> {
+        self.constant = self.constant.checked_add(other.constant)?;
+        for (factors, &coefficient) in &other.terms {
+            self.add_term(factors.clone(), coefficient)?;
+        }
+        Some(self)
+    }
+
+    fn mul(&self, other: &Poly) -> Option<Poly> {
+        let terms = |poly: &Poly| {
+            let constant = Some((Vec::new(), poly.constant)).filter(|&(_, c)| c != 0);
+            poly.terms
+                .iter()
+                .map(|(factors, &coefficient)| (factors.clone(), coefficient))
+                .chain(constant)
+                .collect::<Vec<_>>()
+        };
+        let (a, b) = (terms(self), terms(other));
+        if a.len() * b.len() > MAX_TERMS {
+            return None;
+        }
+
+        let mut product = Poly::default();
+        for (a_factors, a_coefficient) in &a {
+            for (b_factors, b_coefficient) in &b {
+                let mut factors: Vec<_> = a_factors.iter().chain(b_factors).cloned().collect();
+                factors.sort();
+
+                // comparisons are 0 or 1, so multiplying one by itself doesn't change it
+                factors.dedup_by(|a, b| a == b && matches!(**a, Expr::Less(..) | Expr::Equal(..)));
+                product.add_term(factors, a_coefficient.checked_mul(*b_coefficient)?)?;
+            }
+        }
+        Some(product)
+    }
+
+    /// The simplest expression for the polynomial.
+    fn into_expr(self) -> Rc<Expr> {
+        if self.terms.is_empty() {
+            return Rc::new(Expr::Const(self.constant));
+        }
+        if self.constant == 0 && self.terms.len() == 1 {
+            let (factors, &coefficient) = self.terms.iter().next().unwrap();
+            if coefficient == 1 && factors.len() == 1 {
+                return factors[0].clone();
+            }
+        }
+        Rc::new(Expr::Poly(self))
+    }
+}
+
+impl Display for Poly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (factors, &coefficient) in &self.terms {
+            let sign = if coefficient < 0 { "-" } else { "+" };
+            match (first, coefficient) {
+                (true, 1) => (),
+                (true, -1) => write!(f, "-")?,
+                (true, _) => write!(f, "{} * ", coefficient)?,
+                (false, _) if coefficient.abs() == 1 => write!(f, " {} ", sign)?,
+                (false, _) => write!(f, " {} {} * ", sign, (coefficient as i128).abs())?,
+            }
+            for (i, factor) in factors.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " * ")?;
+                }
+                match **factor {
+                    Expr::Const(_) | Expr::Var(_) => write!(f, "{}", factor)?,
+                    _ => write!(f, "({})", factor)?,
+                }
+            }
+            first = false;
+        }
+        match (first, self.constant) {
+            (true, constant) => write!(f, "{}", constant),
+            (false, 0) => Ok(()),
+            (false, constant) if constant < 0 => write!(f, " - {}", -(constant as i128)),
+            (false, constant) => write!(f, " + {}", constant),
+        }
+    }
+}
+
+impl Expr {
+    pub fn as_const(&self) -> Option<i64> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn add(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match Poly::from_expr(&a).add(&Poly::from_expr(&b)) {
+            Some(sum) => sum.into_expr(),
+
+            // a coefficient overflowed, so add them the long way
+            None => Rc::new(Expr::Poly(Poly {
+                constant: 0,
+                terms: vec![(vec![a], 1), (vec![b], 1)].into_iter().collect(),
+            })),
+        }
+    }
+
+    fn mul(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match Poly::from_expr(&a).mul(&Poly::from_expr(&b)) {
+            Some(product) => product.into_expr(),
+
+            // too big to multiply out, or a coefficient overflowed
+            None => {
+                let mut factors = vec![a, b];
+                factors.sort();
+                Rc::new(Expr::Poly(Poly {
+                    constant: 0,
+                    terms: vec![(factors, 1)].into_iter().collect(),
+                }))
+            }
+        }
+    }
+
+    fn less(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match (a.as_const(), b.as_const()) {
+            (Some(x), Some(y)) => Rc::new(Expr::Const(if x < y { 1 } else { 0 })),
+            _ => Rc::new(Expr::Less(a, b)),
+        }
+    }
+
+    fn equal(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match (a.as_const(), b.as_const()) {
+            (Some(x), Some(y)) => Rc::new(Expr::Const(if x == y { 1 } else { 0 })),
+            _ if a == b => Rc::new(Expr::Const(1)),
+            _ => Rc::new(Expr::Equal(a, b)),
+        }
+    }
+
+    /// Evaluates the expression, given the values of its vars. Returns `None` if a var doesn't
+    /// have a value, or if the arithmetic overflows.
+    pub fn eval<F: Fn(Var) -> Option<i64>>(&self, vars: &F) -> Option<i64> {
+        Some(match self {
+            Expr::Const(value) => *value,
+            Expr::Var(var) => vars(*var)?,
+            Expr::Poly(poly) => {
+                let mut sum = poly.constant;
+                for (factors, &coefficient) in &poly.terms {
+                    let mut product = coefficient;
+                    for factor in factors {
+                        product = product.checked_mul(factor.eval(vars)?)?;
+                    }
+                    sum = sum.checked_add(product)?;
+                }
+                sum
+            }
+            Expr::Less(a, b) => (a.eval(vars)? < b.eval(vars)?) as i64,
+            Expr::Equal(a, b) => (a.eval(vars)? == b.eval(vars)?) as i64,
+        })
+    }
+
+    /// Whether the expression is a constant plus vars times constants.
+    pub fn is_linear(&self) -> bool {
+        self.as_linear().is_some()
+    }
+
+    /// If the expression is linear, its constant, and the coefficient of each var in it.
+    pub fn as_linear(&self) -> Option<(i64, BTreeMap<Var, i64>)> {
+        match self {
+            Expr::Const(value) => Some((*value, BTreeMap::new())),
+            Expr::Var(var) => Some((0, vec![(*var, 1)].into_iter().collect())),
+            Expr::Poly(poly) => {
+                let mut coefficients = BTreeMap::new();
+                for (factors, &coefficient) in &poly.terms {
+                    match &factors[..] {
+                        [factor] => match **factor {
+                            Expr::Var(var) => coefficients.insert(var, coefficient),
+                            _ => return None,
+                        },
+                        _ => return None,
+                    };
+                }
+                Some((poly.constant, coefficients))
+            }
+            Expr::Less(..) | Expr::Equal(..) => None,
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::Var(var) => write!(f, "{}", var),
+            Expr::Poly(poly) => write!(f, "{}", poly),
+            Expr::Less(a, b) => write!(f, "{} < {}", Operand(a), Operand(b)),
+            Expr::Equal(a, b) => write!(f, "{} == {}", Operand(a), Operand(b)),
+        }
+    }
+}
+
+/// Displays an expression that is one side of a comparison, parenthesized if it's a comparison
+/// itself, so that `(a < b) == c` doesn't read as `a < (b == c)`.
+struct Operand<'a>(&'a Expr);
+
+impl Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Expr::Less(..) | Expr::Equal(..) => write!(f, "({})", self.0),
+            expr => write!(f, "{}", expr),
+        }
+    }
+}
+
+/// Something that had to be true for the program to do what it did. When a program jumps, or
+/// reads or writes an address, that depends on its vars, the symbolic VM goes the way the
+/// concrete VM did and assumes the vars are such that it would go that way again.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Assumption {
+    Equals(Rc<Expr>, i64),
+    NonZero(Rc<Expr>),
+}
+
+impl Assumption {
+    /// Whether the assumption holds for the given values of the vars.
+    pub fn holds<F: Fn(Var) -> Option<i64>>(&self, vars: &F) -> Option<bool> {
+        Some(match self {
+            Assumption::Equals(expr, value) => expr.eval(vars)? == *value,
+            Assumption::NonZero(expr) => expr.eval(vars)? != 0,
+        })
+    }
+}
+
+impl Display for Assumption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assumption::NonZero(expr) => match &**expr {
+                Expr::Less(..) | Expr::Equal(..) => write!(f, "{}", expr),
+                _ => write!(f, "{} != 0", expr),
+            },
+            Assumption::Equals(expr, 0) => match &**expr {
+                Expr::Less(a, b) => write!(f, "{} >= {}", Operand(a), Operand(b)),
+                Expr::Equal(a, b) => write!(f, "{} != {}", Operand(a), Operand(b)),
+                _ => write!(f, "{} == 0", expr),
+            },
+            Assumption::Equals(expr, value) => write!(f, "{} == {}", Operand(expr), value),
+        }
+    }
+}
+
+/// A value, and the assumptions it depends on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolicValue {
+    pub expr: Rc<Expr>,
+    pub assumptions: Vec<Assumption>,
+}
+
+impl SymbolicValue {
+    fn constant(value: i64) -> SymbolicValue {
+        SymbolicValue {
+            expr: Rc::new(Expr::Const(value)),
+            assumptions: Vec::new(),
+        }
+    }
+
+    fn is_concrete(&self) -> bool {
+        self.expr.as_const().is_some() && self.assumptions.is_empty()
+    }
+
+    /// Combines two values with `op`; the result depends on everything either of them did.
+    fn combine(
+        &self,
+        other: &SymbolicValue,
+        op: fn(Rc<Expr>, Rc<Expr>) -> Rc<Expr>,
+    ) -> SymbolicValue {
+        let mut assumptions = self.assumptions.clone();
+        assume_all(&mut assumptions, &other.assumptions);
+        SymbolicValue {
+            expr: op(self.expr.clone(), other.expr.clone()),
+            assumptions,
+        }
+    }
+}
+
+impl Display for SymbolicValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expr)?;
+        for (i, assumption) in self.assumptions.iter().enumerate() {
+            let separator = if i == 0 { ", assuming" } else { " and" };
+            write!(f, "{} {}", separator, assumption)?;
+        }
+        Ok(())
+    }
+}
+
+fn assume(assumptions: &mut Vec<Assumption>, assumption: Assumption) {
+    if !assumptions.contains(&assumption) {
+        assumptions.push(assumption);
+    }
+}
+
+fn assume_all(assumptions: &mut Vec<Assumption>, more: &[Assumption]) {
+    for assumption in more {
+        assume(assumptions, assumption.clone());
+    }
+}
+
+/// Concolic execution: runs a program on concrete input, like the VM always does, while keeping
+/// track of how each value it computes depends on its input and on any memory cells marked as
+/// vars. Anything the program outputs is reported as an expression over those vars, along with
+/// whatever had to be assumed to get there.
+///
+/// When the program jumps based on a var, or uses one as an address, the symbolic VM follows the
+/// concrete VM, and assumes that the var has a value that would send it the same way. Jumps and
+/// writes affect everything that happens afterwards, so those assumptions apply to every output
+/// from then on. Reads only affect the value read, so a read through an address that depends on
+/// a var is only assumed by values that depend on it. Day 2's programs read from the noun and
+/// verb addresses, but then throw away what they read.
+///
+/// ```
+/// # use intcode::{Intcode, Symbolic};
+/// // outputs 3 * in0 + in1
+/// let program = Intcode::load("3,15,3,16,1002,15,3,15,1,15,16,15,4,15,99,0,0".as_bytes()).unwrap();
+/// let mut symbolic = Symbolic::new(program);
+/// let (outputs, _) = symbolic.run(&[5, 7]);
+/// assert_eq!(outputs[0].to_string(), "3 * in0 + in1");
+/// ```
+pub struct Symbolic {
+    pub program: Intcode,
+
+    /// What had to be true for the program to get where it is.
+    pub assumptions: Vec<Assumption>,
+
+    /// Cells whose values depend on vars, or on assumptions. Every other cell has its concrete
+    /// value.
+    shadow: HashMap<usize, SymbolicValue>,
+
+    /// The relative base, if it depends on vars.
+    relative_base: Option<SymbolicValue>,
+    inputs: usize,
+}
+
+/// What an instruction is about to do, worked out before the concrete VM executes it.
+struct Effects {
+    write: Option<(usize, SymbolicValue)>,
+    output: Option<SymbolicValue>,
+    relative_base: Option<SymbolicValue>,
+    assumptions: Vec<Assumption>,
+    input: bool,
+}
+
+impl Symbolic {
+    pub fn new(program: Intcode) -> Symbolic {
+        Symbolic {
+            program,
+            assumptions: Vec::new(),
+            shadow: HashMap::new(),
+            relative_base: None,
+            inputs: 0,
+        }
+    }
+
+    /// Marks the value currently at `address` as a var, `Var::Cell(address)`.
+    pub fn var(&mut self, address: usize) {
+        let value = SymbolicValue {
+            expr: Rc::new(Expr::Var(Var::Cell(address))),
+            assumptions: Vec::new(),
+        };
+        self.shadow.insert(address, value);
+    }
+
+    /// The symbolic value at `address`.
+    pub fn value(&self, address: usize) -> SymbolicValue {
+        match self.shadow.get(&address) {
+            Some(value) => value.clone(),
+            None => SymbolicValue::constant(self.program.code[address]),
+        }
+    }
+
+    /// The symbolic value at `address`, including the assumptions the program has made so far.
+    pub fn value_assuming(&self, address: usize) -> SymbolicValue {
+        let mut value = self.value(address);
+        let mut assumptions = self.assumptions.clone();
+        assume_all(&mut assumptions, &value.assumptions);
+        value.assumptions = assumptions;
+        value
+    }
+
+    /// Runs the program until it halts, needs more input than `input`, faults, or runs out of
+    /// budget. Each value in `input` becomes the next `Var::Input`. Returns everything the program
+    /// output, each with every assumption it depends on.
+    pub fn run(&mut self, input: &[i64]) -> (Vec<SymbolicValue>, RunState) {
+        let mut input = input.iter().copied();
+        let mut outputs = Vec::new();
+        let mut meter = Meter::new(self.program.budget);
+        loop {
+            if meter.exhausted() {
+                return (
+                    outputs,
+                    RunState::BudgetExhausted {
+                        ip: self.program.ip,
+                    },
+                );
+            }
+
+            let effects = self.effects();
+            let state = self.program.step(&mut input);
+            match state {
+                None | Some(RunState::Output { .. }) | Some(RunState::Halted { .. }) => {
+                    if let Some(effects) = effects {
+                        if let Some(output) = self.commit(effects) {
+                            outputs.push(output);
+                        }
+                    }
+                }
+                _ => (),
+            }
+
+            match state {
+                None | Some(RunState::Output { .. }) => (),
+                Some(state) => return (outputs, state),
+            }
+        }
+    }
+
+    /// Applies an instruction's effects once the concrete VM has executed it.
+    fn commit(&mut self, effects: Effects) -> Option<SymbolicValue> {
+        assume_all(&mut self.assumptions, &effects.assumptions);
+        if let Some((address, value)) = effects.write {
+            if value.is_concrete() {
+                self.shadow.remove(&address);
+            } else {
+                self.shadow.insert(address, value);
+            }
+        }
+        if let Some(relative_base) = effects.relative_base {
+            self.relative_base = Some(relative_base).filter(|rb| !rb.is_concrete());
+        }
+        if effects.input {
+            self.inputs += 1;
+        }
+        effects.output.map(|mut output| {
+            let mut assumptions = self.assumptions.clone();
+            assume_all(&mut assumptions, &output.assumptions);
+            output.assumptions = assumptions;
+            output
+        })
+    }
+
+    fn relative_base(&self) -> SymbolicValue {
+        self.relative_base
+            .clone()
+            .unwrap_or_else(|| SymbolicValue::constant(self.program.relative_base as i64))
+    }
+
+    /// The address a parameter refers to, symbolically, and concretely if it's valid.
+    fn address(&self, sp: usize, mode: ParameterMode) -> (SymbolicValue, Option<usize>) {
+        let operand = self.value(sp);
+        let concrete = self.program.code[sp] as i128;
+        match mode {
+            ParameterMode::Relative => {
+                let address = concrete + self.program.relative_base as i128;
+                (
+                    self.relative_base().combine(&operand, Expr::add),
+                    self.program.code.address(address).ok(),
+                )
+            }
+            _ => (operand, self.program.code.address(concrete).ok()),
+        }
+    }
+
+    /// Works out what the instruction at `ip` is going to do. Returns `None` if it's going to
+    /// fault.
+    fn effects(&self) -> Option<Effects> {
+        let ip = self.program.ip;
+        let mut effects = Effects {
+            write: None,
+            output: None,
+            relative_base: None,
+            assumptions: Vec::new(),
+            input: false,
+        };
+        let code = self.program.code[ip];
+        effects.depend(&self.value(ip), Assumption::Equals, code);
+
+        // the parameters the instruction reads, symbolically and concretely
+        let opcode = Opcode::try_from(code).ok()?;
+        let modes = opcode.modes();
+        let mut values = Vec::new();
+        let mut destination = None;
+        for (i, &&mode) in modes.iter().enumerate() {
+            let sp = ip + 1 + i;
+            if mode == ParameterMode::Immediate {
+                values.push((self.value(sp), self.program.code[sp]));
+                continue;
+            }
+
+            let (address, concrete) = self.address(sp, mode);
+            let concrete = concrete?;
+            if opcode.writes() && i == modes.len() - 1 {
+                // a write could have gone anywhere, so everything depends on where it went
+                effects.depend(&address, Assumption::Equals, concrete as i64);
+                destination = Some(concrete);
+            } else {
+                // a read only affects the value that was read
+                let mut value = self.value(concrete);
+                if !address.is_concrete() {
+                    assume_all(&mut value.assumptions, &address.assumptions);
+                    let assumption = Assumption::Equals(address.expr, concrete as i64);
+                    assume(&mut value.assumptions, assumption);
+                }
+                values.push((value, self.program.code[concrete]));
+            }
+        }
+
+        let value = match opcode {
+            Opcode::Add(..) => Some(values[0].0.combine(&values[1].0, Expr::add)),
+            Opcode::Multiply(..) => Some(values[0].0.combine(&values[1].0, Expr::mul)),
+            Opcode::LessThan(..) => Some(values[0].0.combine(&values[1].0, Expr::less)),
+            Opcode::Equal(..) => Some(values[0].0.combine(&values[1].0, Expr::equal)),
+            Opcode::Input(_) => {
+                effects.input = true;
+                Some(SymbolicValue {
+                    expr: Rc::new(Expr::Var(Var::Input(self.inputs))),
+                    assumptions: Vec::new(),
+                })
+            }
+            Opcode::Output(_) => {
+                effects.output = Some(values[0].0.clone());
+                None
+            }
+            Opcode::JumpIfTrue(..) | Opcode::JumpIfFalse(..) => {
+                let (condition, concrete) = &values[0];
+                if *concrete == 0 {
+                    effects.depend(condition, Assumption::Equals, 0);
+                } else {
+                    effects.depend(condition, |expr, _| Assumption::NonZero(expr), 0);
+                }
+                if matches!(opcode, Opcode::JumpIfTrue(..)) == (*concrete != 0) {
+                    let (target, concrete) = &values[1];
+                    effects.depend(target, Assumption::Equals, *concrete);
+                }
+                None
+            }
+            Opcode::RelativeBaseOffset(_) => {
+                effects.relative_base = Some(self.relative_base().combine(&values[0].0, Expr::add));
+                None
+            }
+            Opcode::Halt => None,
+        };
+        effects.write = destination.zip(value);
+        Some(effects)
+    }
+}
+
+impl Effects {
+    /// If `value` isn't concrete, the instruction only does what it's about to do if
+    /// `assumption(value, concrete)` holds, so everything from now on depends on it.
+    fn depend<F: Fn(Rc<Expr>, i64) -> Assumption>(
+        &mut self,
+        value: &SymbolicValue,
+        assumption: F,
+        concrete: i64,
+    ) {
+        if !value.is_concrete() {
+            assume_all(&mut self.assumptions, &value.assumptions);
+            assume(
+                &mut self.assumptions,
+                assumption(value.expr.clone(), concrete),
+            );
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cell::Cell;
+use crate::disasm::fmt_operand;
+use crate::opcode::Opcode;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// An instruction that the VM just executed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event<C = i64> {
+    pub ip: usize,
+    pub opcode: Opcode,
+
+    /// The instruction's operands as they appear in memory, before parameter modes are applied.
+    pub operands: Vec<C>,
+
+    /// The values of the parameters that the instruction reads, after parameter modes are
+    /// applied. For an output instruction, this is the value that was output.
+    pub values: Vec<C>,
+
+    /// The address that the instruction wrote to, and the value it wrote. For an input
+    /// instruction, this is the value that was input.
+    pub write: Option<(usize, C)>,
+}
+
+/// Something that wants to know about every instruction the VM executes. Set `Intcode::tracer`
+/// to start tracing.
+///
+/// The VM owns its tracer, so to get at the results afterwards, share the tracer with an
+/// `Arc<Mutex<_>>`:
+///
+/// ```
+/// # use intcode::{Intcode, Profile};
+/// # use std::sync::{Arc, Mutex};
+/// let mut program = Intcode::load("104,42,99".as_bytes()).unwrap();
+/// let profile = Arc::new(Mutex::new(Profile::default()));
+/// program.tracer = Some(Box::new(profile.clone()));
+/// program.run_batch(&[]).unwrap();
+/// assert_eq!(profile.lock().unwrap().outputs, 1);
+/// ```
+pub trait Tracer<C = i64> {
+    fn trace(&mut self, event: &Event<C>);
+}
+
+impl<C: Clone> Tracer<C> for Vec<Event<C>> {
+    fn trace(&mut self, event: &Event<C>) {
+        self.push(event.clone());
+    }
+}
+
+impl<C, T: Tracer<C>> Tracer<C> for Arc<Mutex<T>> {
+    fn trace(&mut self, event: &Event<C>) {
+        if let Ok(mut tracer) = self.lock() {
+            tracer.trace(event);
+        }
+    }
+}
+
+/// Writes each event as a line of JSON, ie:
+///
+/// ```text
+/// {"ip":42,"op":"ADD","operands":["[rb+3]","#5","[100]"],"values":[7,5],"write":[100,12]}
+/// ```
+///
+/// `operands` are written in the same syntax as the disassembler uses. Tracing writes a lot, so
+/// wrap files in a `BufWriter`. If writing fails, the rest of the trace is dropped so that the
+/// program can keep running.
+pub struct TraceLog<W: Write> {
+    writer: W,
+    failed: bool,
+}
+
+impl<W: Write> TraceLog<W> {
+    pub fn new(writer: W) -> TraceLog<W> {
+        TraceLog {
+            writer,
+            failed: false,
+        }
+    }
+}
+
+fn json_values<C: Cell>(values: &[C]) -> String {
+    let values: Vec<_> = values.iter().map(C::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+impl<C: Cell, W: Write> Tracer<C> for TraceLog<W> {
+    fn trace(&mut self, event: &Event<C>) {
+        if self.failed {
+            return;
+        }
+
+        let operands: Vec<_> = event
+            .opcode
+            .modes()
+            .into_iter()
+            .zip(event.operands.iter())
+            .map(|(mode, &operand)| {
+                let mut s = String::new();
+                fmt_operand(&mut s, mode, operand).ok();
+                format!("\"{}\"", s)
+            })
+            .collect();
+        let write = match event.write {
+            Some((address, value)) => format!("[{},{}]", address, value),
+            None => "null".to_string(),
+        };
+        let result = writeln!(
+            self.writer,
+            "{{\"ip\":{},\"op\":\"{}\",\"operands\":[{}],\"values\":{},\"write\":{}}}",
+            event.ip,
+            event.opcode.mnemonic(),
+            operands.join(","),
+            json_values(&event.values),
+            write
+        );
+        self.failed = result.is_err();
+    }
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{assembled, load};
+use intcode::{AsciiMachine, RunState};
+
+#[test]
+fn echoes_lines() {
+    // prompts with `>`, echoes a line back, then outputs 300 before prompting again
+    let mut machine = AsciiMachine::new(assembled(
+        "
+        loop:   OUT #'>'
+        read:   IN -> [c]
+                EQ [c], #10 -> [t]
+                JT [t], #done
+                OUT [c]
+                JT #1, #read
+        done:   OUT #300
+                JT #1, #loop
+        c:      DATA 0
+        t:      DATA 0
+        ",
+    ));
+    let output = machine.read_until_prompt().unwrap();
+    assert_eq!(output.text, ">");
+    assert!(output.values.is_empty());
+    assert_eq!(output.value(), None);
+    assert_eq!(output.state, RunState::NeedsInput { ip: 2 });
+
+    machine.send_line("hi");
+    machine.send_line("there");
+    let output = machine.read_until_prompt().unwrap();
+    assert_eq!(output.text, "hi>there>");
+    assert_eq!(output.values, vec![300, 300]);
+    assert!(machine.input.is_empty());
+}
+
+#[test]
+fn separates_the_answer_from_the_text() {
+    let mut machine = AsciiMachine::new(load("104,72,104,105,104,10,104,200,104,19357180,99"));
+    let output = machine.read_until_prompt().unwrap();
+    assert_eq!(output.text, "Hi\n");
+    assert_eq!(output.values, vec![200, 19357180]);
+    assert_eq!(output.value(), Some(19357180));
+    assert!(output.state.is_halted());
+}
+
+#[test]
+fn faults_are_errors() {
+    let mut machine = AsciiMachine::new(load("104,65,104,300,42"));
+    let error = machine.read_until_prompt().unwrap_err();
+    assert_eq!(error.fault.ip, 4);
+    assert_eq!(error.text, "A");
+    assert_eq!(error.values, vec![300]);
+    assert_eq!(
+        error.to_string(),
+        "Fault at 4: 42 is not a valid opcode. Output before the fault:\nA"
+    );
+}
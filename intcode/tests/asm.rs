@@ -0,0 +1,105 @@
+mod common;
+
+use common::assembled;
+use intcode::{assemble, Listing};
+
+fn error(source: &str) -> String {
+    assemble(source).unwrap_err().to_string()
+}
+
+#[test]
+fn encodes_modes() {
+    assert_eq!(
+        assemble("ADD [rb+3], #5 -> [100]").unwrap(),
+        vec![1201, 3, 5, 100]
+    );
+    assert_eq!(
+        assemble("mul [rb-1], [rb] -> [rb+2]\nhlt").unwrap(),
+        vec![22202, -1, 0, 2, 99]
+    );
+    assert_eq!(assemble("IN -> [rb+1]").unwrap(), vec![203, 1]);
+}
+
+#[test]
+fn countdown() {
+    let mut program = assembled(
+        "; outputs the numbers 3 through 1
+        start:  OUT [count]
+                ADD [count], #-1 -> [count]
+                JT [count], #start
+                HLT
+        count:  DATA 3",
+    );
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    assert_eq!(output, vec![3, 2, 1]);
+}
+
+#[test]
+fn strings_and_characters() {
+    assert_eq!(
+        assemble(r#"DATA "a,\"b\"\n", 'c', '\n', -1"#).unwrap(),
+        vec![97, 44, 34, 98, 34, 10, 99, 10, -1]
+    );
+
+    // prints a string by walking the relative base along it until it reaches a zero
+    let mut program = assembled(
+        r#"        ARB #text
+        loop:   JF [rb], #done
+                OUT [rb]
+                ARB #1
+                JT #1, #loop
+        done:   HLT
+        text:   DATA "hi\n", 0"#,
+    );
+    let (output, _) = program.run_batch(&[]).unwrap();
+    assert_eq!(output, vec![104, 105, 10]);
+}
+
+#[test]
+fn label_arithmetic() {
+    assert_eq!(
+        assemble("JT #1, #end+1\nend: DATA end - 1, end+end").unwrap(),
+        vec![1105, 1, 4, 2, 6]
+    );
+}
+
+#[test]
+fn round_trips_disassembly() {
+    let code = vec![
+        3, 20, 1005, 20, 9, 104, 0, 99, 42, 21101, 7, 0, 0, 1106, 0, 16, 99, 204, -1, 99, 0,
+    ];
+    let listing = Listing::new(&code).to_string();
+    assert_eq!(assemble(&listing).unwrap(), code);
+}
+
+#[test]
+fn errors() {
+    assert_eq!(error("NOP"), "line 1: unknown instruction `NOP`");
+    assert_eq!(
+        error("\nJT #1, #nowhere"),
+        "line 2: undefined label `nowhere`"
+    );
+    assert_eq!(
+        error("ADD #1, #2 -> #3"),
+        "line 1: `ADD` cannot write to an immediate"
+    );
+    assert_eq!(
+        error("a: HLT\na: HLT"),
+        "line 2: label `a` is already defined"
+    );
+    assert_eq!(
+        error("HLT\n0002: HLT"),
+        "line 2: `0002:` is actually at address 1"
+    );
+    assert_eq!(error("ADD #1, #2, [3]"), "line 1: expected `->`");
+    assert_eq!(error("OUT #1 #2"), "line 1: unexpected `#2`");
+    assert_eq!(
+        error("HLT\nDATA 9223372036854775807+1"),
+        "line 2: value doesn't fit in 64 bits"
+    );
+    assert_eq!(
+        error("DATA -9223372036854775807-2"),
+        "line 1: value doesn't fit in 64 bits"
+    );
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::load;
+use intcode::{Budget, RunState};
+use std::iter;
+use std::time::{Duration, Instant};
+
+/// Adds 1 to [100] forever.
+const COUNTER: &str = "1001,100,1,100,1105,1,0";
+
+#[test]
+fn instruction_budget_is_exact() {
+    let mut program = load(COUNTER);
+    program.budget = Some(Budget::Instructions(10));
+    match program.run(&mut iter::empty()) {
+        RunState::BudgetExhausted { ip: 0 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert_eq!(program.code[100], 5);
+
+    // running again resumes where it left off, with a fresh budget
+    program.budget = Some(Budget::Instructions(3));
+    match program.run(&mut iter::empty()) {
+        RunState::BudgetExhausted { ip: 4 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert_eq!(program.code[100], 7);
+}
+
+#[test]
+fn budget_covers_the_whole_run_io_call() {
+    // outputs 1 forever
+    let mut program = load("104,1,1105,1,0");
+    program.budget = Some(Budget::Instructions(10));
+    let mut output = Vec::new();
+    match program.run_io(&mut (iter::empty(), |v: i64| output.push(v))) {
+        RunState::BudgetExhausted { ip: 0 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert_eq!(output, vec![1; 5]);
+
+    program.budget = Some(Budget::Instructions(3));
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert_eq!(output, vec![1, 1]);
+    assert_eq!(state, RunState::BudgetExhausted { ip: 2 });
+}
+
+#[test]
+fn time_budget() {
+    let mut program = load(COUNTER);
+    program.budget = Some(Budget::Time(Duration::from_millis(20)));
+    let start = Instant::now();
+    match program.run(&mut iter::empty()) {
+        RunState::BudgetExhausted { .. } => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(program.code[100] > 0);
+}
+
+#[test]
+fn programs_that_finish_in_time_are_unaffected() {
+    let mut program = load("1101,2,3,0,4,0,99");
+    program.budget = Some(Budget::Instructions(3));
+    assert_eq!(
+        program.run_batch(&[]).unwrap(),
+        (vec![5], RunState::Halted { ip: 6 })
+    );
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::assembled;
+
+#[test]
+fn self_modifying_code_is_decoded_again() {
+    // runs `op` twice: the first time it's an ADD, and then the program turns it into a MUL
+    let mut program = assembled(
+        "
+        op:     ADD #3, #4 -> [result]
+                OUT [result]
+                JT [done], #end
+                ADD #1, #0 -> [done]
+                ADD #1102, #0 -> [op]
+                JT #1, #op
+        end:    HLT
+        result: DATA 0
+        done:   DATA 0
+        ",
+    );
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    assert_eq!(output, vec![7, 12]);
+}
+
+#[test]
+fn writes_through_code_are_decoded_again() {
+    let mut program = assembled("ADD #3, #4 -> [9]\nOUT [9]\nHLT");
+    assert_eq!(program.run_batch(&[]).unwrap().0, vec![7]);
+
+    program.ip = 0;
+    program.code[0] = 1102;
+    assert_eq!(program.run_batch(&[]).unwrap().0, vec![12]);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::load;
+use intcode::{Circuit, CircuitError, Until};
+
+/// Outputs its input plus 1, forever.
+const INCREMENT: &str = "3,11,1001,11,1,11,4,11,1105,1,0,0";
+
+#[test]
+fn pipeline_matches_day_7_example() {
+    let program = load("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
+    let mut circuit = Circuit::pipeline(&program, 5);
+    for (node, &phase) in [4, 3, 2, 1, 0].iter().enumerate() {
+        circuit.input(node, phase).unwrap();
+    }
+    circuit.input(0, 0).unwrap();
+    circuit.run().unwrap();
+    assert_eq!(circuit.outputs[4], vec![43210]);
+    assert!(circuit.halted.iter().all(|&halted| halted));
+}
+
+#[test]
+fn feedback_loop_matches_day_7_example() {
+    let program = load(
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+    );
+    let mut circuit = Circuit::feedback_loop(&program, 5);
+    for (node, &phase) in [9, 8, 7, 6, 5].iter().enumerate() {
+        circuit.input(node, phase).unwrap();
+    }
+    circuit.input(0, 0).unwrap();
+    circuit.run().unwrap();
+    assert_eq!(circuit.outputs[4].last(), Some(&139629729));
+}
+
+#[test]
+fn fans_output_out_to_every_edge() {
+    let mut circuit = Circuit::new(&load(INCREMENT), 3);
+    circuit.connect(0, 1).unwrap();
+    circuit.connect(0, 2).unwrap();
+    circuit.input(0, 10).unwrap();
+    circuit.until = Until::Outputs { node: 2, count: 1 };
+    circuit.run().unwrap();
+    assert_eq!(circuit.outputs, vec![vec![11], vec![12], vec![12]]);
+}
+
+#[test]
+fn stops_after_enough_output() {
+    // a loop of incrementers never halts
+    let mut circuit = Circuit::feedback_loop(&load(INCREMENT), 2);
+    circuit.input(0, 0).unwrap();
+    circuit.until = Until::Outputs { node: 1, count: 3 };
+    circuit.run().unwrap();
+    assert_eq!(circuit.outputs[0], vec![1, 3, 5]);
+    assert_eq!(circuit.outputs[1], vec![2, 4, 6]);
+
+    // and picks up where it left off
+    circuit.until = Until::Outputs { node: 1, count: 4 };
+    circuit.run().unwrap();
+    assert_eq!(circuit.outputs[1], vec![2, 4, 6, 8]);
+}
+
+#[test]
+fn stops_when_a_node_halts() {
+    let mut circuit = Circuit::new(&load(INCREMENT), 2);
+    circuit.machines[1] = load("104,7,99");
+    circuit.until = Until::Halted(1);
+    circuit.run().unwrap();
+    assert_eq!(circuit.halted, vec![false, true]);
+    assert_eq!(circuit.outputs[1], vec![7]);
+}
+
+#[test]
+fn detects_deadlock() {
+    let mut circuit = Circuit::feedback_loop(&load(INCREMENT), 3);
+    assert_eq!(circuit.run(), Err(CircuitError::Deadlock));
+}
+
+#[test]
+fn reports_which_node_faulted() {
+    let mut circuit = Circuit::pipeline(&load(INCREMENT), 3);
+    circuit.machines[2] = load("3,0,4,0,98");
+    circuit.input(0, 1).unwrap();
+    match circuit.run() {
+        Err(CircuitError::Fault { node, .. }) => assert_eq!(node, 2),
+        result => panic!("expected a fault, got {:?}", result),
+    }
+    assert_eq!(circuit.outputs[2], vec![3]);
+}
+
+#[test]
+fn rejects_missing_nodes() {
+    let mut circuit = Circuit::pipeline(&load(INCREMENT), 2);
+    assert_eq!(
+        circuit.connect(1, 2).err(),
+        Some(CircuitError::NoSuchNode(2))
+    );
+    assert_eq!(circuit.input(5, 0).err(), Some(CircuitError::NoSuchNode(5)));
+    assert_eq!(circuit.edges, vec![(0, 1)]);
+
+    circuit.until = Until::Outputs { node: 2, count: 1 };
+    assert_eq!(circuit.run(), Err(CircuitError::NoSuchNode(2)));
+    circuit.until = Until::Halted(3);
+    assert_eq!(circuit.run(), Err(CircuitError::NoSuchNode(3)));
+    circuit.until = Until::AllHalted;
+    circuit.edges.push((4, 0));
+    assert_eq!(circuit.run(), Err(CircuitError::NoSuchNode(4)));
+}
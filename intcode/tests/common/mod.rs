@@ -0,0 +1,19 @@
+//! Helpers shared by the integration tests. Each test file only uses some of them.
+#![allow(dead_code)]
+
+use intcode::{assemble, Intcode};
+
+/// Loads a comma separated program, the same way a puzzle input is loaded.
+pub fn load(program: &str) -> Intcode {
+    Intcode::load(program.as_bytes()).unwrap()
+}
+
+/// Assembles `source` and loads it the same way a puzzle input is loaded.
+pub fn assembled(source: &str) -> Intcode {
+    let code: Vec<_> = assemble(source)
+        .unwrap()
+        .iter()
+        .map(i64::to_string)
+        .collect();
+    Intcode::load(code.join(",").as_bytes()).unwrap()
+}
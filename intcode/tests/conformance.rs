@@ -0,0 +1,237 @@
+//! Conformance tests built from the example programs in the puzzle descriptions for days 2, 5, 7,
+//! and 9.
+
+mod common;
+
+use common::load;
+use intcode::Intcode;
+
+fn run(program: &str, input: &[i64]) -> Vec<i64> {
+    let mut program = load(program);
+    let (output, state) = program.run_batch(input).unwrap();
+    assert!(state.is_halted());
+    output
+}
+
+fn memory_after(program: &str) -> Vec<i64> {
+    let mut program = load(program);
+    let (_, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    program.code.to_vec()
+}
+
+#[test]
+fn day02_add_and_multiply() {
+    assert_eq!(
+        memory_after("1,9,10,3,2,3,11,0,99,30,40,50"),
+        vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]
+    );
+    assert_eq!(memory_after("1,0,0,0,99"), vec![2, 0, 0, 0, 99]);
+    assert_eq!(memory_after("2,3,0,3,99"), vec![2, 3, 0, 6, 99]);
+    assert_eq!(memory_after("2,4,4,5,99,0"), vec![2, 4, 4, 5, 99, 9801]);
+    assert_eq!(
+        memory_after("1,1,1,4,99,5,6,0,99"),
+        vec![30, 1, 1, 4, 2, 5, 6, 0, 99]
+    );
+}
+
+#[test]
+fn day02_values_wider_than_32_bits() {
+    assert_eq!(
+        memory_after("1,5,6,0,99,3000000000,4000000000"),
+        vec![7000000000, 5, 6, 0, 99, 3000000000, 4000000000]
+    );
+    assert_eq!(
+        memory_after("1002,5,-3,5,99,4000000000"),
+        vec![1002, 5, -3, 5, 99, -12000000000]
+    );
+}
+
+#[test]
+fn day05_input_output() {
+    for i in &[-5, 0, 42] {
+        assert_eq!(run("3,0,4,0,99", &[*i]), vec![*i]);
+    }
+}
+
+#[test]
+fn input_modes() {
+    // relative mode input writes to the relative base plus the offset
+    let mut program = load("109,5,203,2,4,7,99,0");
+    let (output, state) = program.run_batch(&[42]).unwrap();
+    assert_eq!(output, vec![42]);
+    assert!(state.is_halted());
+
+    // an input can't be written to an immediate value
+    let mut program = load("103,0,99");
+    assert!(program.run_batch(&[42]).is_err());
+}
+
+#[test]
+fn day05_parameter_modes() {
+    assert_eq!(memory_after("1002,4,3,4,33"), vec![1002, 4, 3, 4, 99]);
+    assert_eq!(memory_after("1101,100,-1,4,0"), vec![1101, 100, -1, 4, 99]);
+}
+
+#[test]
+fn day05_comparisons() {
+    let equal_position = "3,9,8,9,10,9,4,9,99,-1,8";
+    let less_position = "3,9,7,9,10,9,4,9,99,-1,8";
+    let equal_immediate = "3,3,1108,-1,8,3,4,3,99";
+    let less_immediate = "3,3,1107,-1,8,3,4,3,99";
+    for i in 6..=10 {
+        let equal = if i == 8 { 1 } else { 0 };
+        let less = if i < 8 { 1 } else { 0 };
+        assert_eq!(run(equal_position, &[i]), vec![equal]);
+        assert_eq!(run(less_position, &[i]), vec![less]);
+        assert_eq!(run(equal_immediate, &[i]), vec![equal]);
+        assert_eq!(run(less_immediate, &[i]), vec![less]);
+    }
+}
+
+#[test]
+fn day05_jumps() {
+    let position = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
+    let immediate = "3,3,1105,-1,9,1101,0,0,12,4,12,99,1";
+    for i in -1..=2 {
+        let expected = if i == 0 { 0 } else { 1 };
+        assert_eq!(run(position, &[i]), vec![expected]);
+        assert_eq!(run(immediate, &[i]), vec![expected]);
+    }
+}
+
+#[test]
+fn day05_larger_example() {
+    let program = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+                   1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+                   999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+    assert_eq!(run(program, &[7]), vec![999]);
+    assert_eq!(run(program, &[8]), vec![1000]);
+    assert_eq!(run(program, &[9]), vec![1001]);
+}
+
+fn amplifiers(program: &str, phase_settings: &[i64]) -> i64 {
+    let program = load(program);
+    let mut amps: Vec<Intcode> = phase_settings
+        .iter()
+        .map(|&phase| {
+            let mut amp = program.clone();
+            let (output, state) = amp.run_batch(&[phase]).unwrap();
+            assert!(output.is_empty() && !state.is_halted());
+            amp
+        })
+        .collect();
+
+    let mut signal = 0;
+    loop {
+        let mut halted = false;
+        for amp in amps.iter_mut() {
+            let (output, state) = amp.run_batch(&[signal]).unwrap();
+            signal = output[0];
+            halted = state.is_halted();
+        }
+        if halted {
+            return signal;
+        }
+    }
+}
+
+#[test]
+fn day07_amplifiers() {
+    assert_eq!(
+        amplifiers(
+            "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+            &[4, 3, 2, 1, 0]
+        ),
+        43210
+    );
+    assert_eq!(
+        amplifiers(
+            "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+            &[0, 1, 2, 3, 4]
+        ),
+        54321
+    );
+    assert_eq!(
+        amplifiers(
+            "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,\
+             1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0",
+            &[1, 0, 4, 3, 2]
+        ),
+        65210
+    );
+}
+
+#[test]
+fn day07_feedback_loop() {
+    assert_eq!(
+        amplifiers(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+             27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+            &[9, 8, 7, 6, 5]
+        ),
+        139629729
+    );
+    assert_eq!(
+        amplifiers(
+            "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,\
+             -5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,\
+             53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10",
+            &[9, 7, 8, 5, 6]
+        ),
+        18216
+    );
+}
+
+#[test]
+fn day09_quine() {
+    let program = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let expected: Vec<i64> = program.split(',').map(|i| i.parse().unwrap()).collect();
+    assert_eq!(run(program, &[]), expected);
+}
+
+#[test]
+fn day09_large_numbers() {
+    let output = run("1102,34915192,34915192,7,4,7,99,0", &[]);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].to_string().len(), 16);
+
+    assert_eq!(run("104,1125899906842624,99", &[]), vec![1125899906842624]);
+}
+
+#[test]
+fn day09_relative_base() {
+    // 109,19 moves the relative base from 2000 to 2019; 204,-34 then outputs address 1985
+    let mut program = load("109,19,204,-34,99");
+    program.relative_base = 2000;
+    program.code[1985] = 1985;
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    assert_eq!(program.relative_base, 2019);
+    assert_eq!(output, vec![1985]);
+}
+
+#[test]
+fn suspends_for_input() {
+    let mut program = load("3,0,4,0,99");
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert!(output.is_empty());
+    assert!(!state.is_halted());
+    assert_eq!(program.ip, 0);
+
+    let (output, state) = program.run_batch(&[7]).unwrap();
+    assert_eq!(output, vec![7]);
+    assert!(state.is_halted());
+}
+
+#[test]
+fn invalid_opcode() {
+    let mut program = load("42");
+    assert!(program.run_batch(&[]).is_err());
+}
+
+#[test]
+fn immediate_mode_output_is_invalid() {
+    let mut program = load("11101,1,1,0,99");
+    assert!(program.run_batch(&[]).is_err());
+}
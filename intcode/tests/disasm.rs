@@ -0,0 +1,129 @@
+use intcode::{Instruction, Listing};
+
+fn disassemble(code: &[i64], address: usize) -> String {
+    Instruction::decode(code, address).unwrap().to_string()
+}
+
+#[test]
+fn operand_modes() {
+    assert_eq!(
+        disassemble(&[0, 42, 1201, 3, 5, 100], 2),
+        "0002: ADD [rb+3], #5 -> [100]"
+    );
+    assert_eq!(
+        disassemble(&[22202, -1, 4, -2], 0),
+        "0000: MUL [rb-1], [rb+4] -> [rb-2]"
+    );
+}
+
+#[test]
+fn every_opcode() {
+    let program = [
+        3, 100, 4, 100, 1105, 1, 0, 1006, 100, 0, 1107, 1, 2, 100, 8, 1, 2, 100, 109, 5, 99,
+    ];
+    let mut address = 0;
+    let mut lines = Vec::new();
+    while address < program.len() {
+        let instruction = Instruction::decode(&program, address).unwrap();
+        lines.push(instruction.to_string());
+        address += instruction.size();
+    }
+    assert_eq!(
+        lines,
+        vec![
+            "0000: IN -> [100]",
+            "0002: OUT [100]",
+            "0004: JT #1, #0",
+            "0007: JF [100], #0",
+            "0010: LT #1, #2 -> [100]",
+            "0014: EQ [1], [2] -> [100]",
+            "0018: ARB #5",
+            "0020: HLT",
+        ]
+    );
+}
+
+#[test]
+fn operands_past_the_end_are_zero() {
+    assert_eq!(disassemble(&[1101, 7], 0), "0000: ADD #7, #0 -> [0]");
+}
+
+#[test]
+fn invalid_opcode() {
+    assert!(Instruction::decode(&[42], 0).is_err());
+    assert!(Instruction::decode(&[30001], 0).is_err());
+}
+
+fn listing(code: &[i64]) -> Vec<String> {
+    Listing::new(code)
+        .lines
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn data_after_halt() {
+    assert_eq!(
+        listing(&[4, 6, 99, 1, 2, 3, 104]),
+        vec!["0000: OUT [6]", "0002: HLT", "0003: DATA 1, 2, 3, 104"]
+    );
+}
+
+#[test]
+fn jump_targets() {
+    // 0: jump over the data if input is non-zero
+    let code = [3, 20, 1005, 20, 9, 104, 0, 99, 42, 104, 1, 99];
+    assert_eq!(
+        listing(&code),
+        vec![
+            "0000: IN -> [20]",
+            "0002: JT [20], #9",
+            "0005: OUT #0",
+            "0007: HLT",
+            "0008: DATA 42",
+            "0009: OUT #1  ; from 0002",
+            "0011: HLT",
+        ]
+    );
+}
+
+#[test]
+fn return_addresses() {
+    // 0: call the function at 9 with a return address of 7, then halt
+    let code = [21101, 7, 0, 0, 1106, 0, 9, 99, 0, 204, 1, 2106, 0, 0];
+    assert_eq!(
+        listing(&code),
+        vec![
+            "0000: ADD #7, #0 -> [rb+0]",
+            "0004: JF #0, #9",
+            "0007: HLT",
+            "0008: DATA 0",
+            "0009: OUT [rb+1]  ; from 0004",
+            "0011: JF #0, [rb+0]",
+        ]
+    );
+}
+
+#[test]
+fn self_modifying_code() {
+    // 0: overwrite the halt at 8 with `OUT #8`
+    let code = [1101, 104, 0, 8, 1101, 0, 8, 9, 99, 0, 99];
+    assert_eq!(
+        listing(&code),
+        vec![
+            "0000: ADD #104, #0 -> [8]",
+            "0004: ADD #0, #8 -> [9]",
+            "0008: HLT  ; modified by 0000",
+            "0009: DATA 0, 99",
+        ]
+    );
+}
+
+#[test]
+fn invalid_jump_target() {
+    assert_eq!(
+        listing(&[1105, 1, 3, 42]),
+        vec!["0000: JT #1, #3", "0003: DATA 42  ; invalid jump from 0000"]
+    );
+}
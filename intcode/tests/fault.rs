@@ -0,0 +1,128 @@
+mod common;
+
+use common::load;
+use intcode::{
+    Arithmetic, Fault, FaultKind, Intcode, InvalidAddress, InvalidOpcode, InvalidOutputMode,
+    ParameterMode, RunState,
+};
+use std::iter;
+
+/// Runs the program, ignoring its output, and expects it to fault.
+fn fault(program: &mut Intcode) -> Fault {
+    match program.run_io(&mut (iter::empty(), |_: i64| ())) {
+        RunState::Fault(fault) => fault,
+        state => panic!("unexpected {}", state),
+    }
+}
+
+#[test]
+fn overflow_faults_by_default() {
+    let mut program = load("1101,1,1,0,1102,9223372036854775807,2,0,99");
+    assert_eq!(
+        fault(&mut program),
+        Fault {
+            ip: 4,
+            kind: FaultKind::Overflow
+        }
+    );
+
+    // the faulting instruction isn't executed, so running again faults again
+    assert_eq!(program.code[0], 2);
+    assert_eq!(fault(&mut program).ip, 4);
+
+    let mut program = load("1101,9223372036854775807,1,0,99");
+    assert_eq!(fault(&mut program).kind, FaultKind::Overflow);
+}
+
+#[test]
+fn wrapping_arithmetic() {
+    let mut program = load("1101,9223372036854775807,1,0,4,0,1102,4611686018427387904,4,0,4,0,99");
+    program.arithmetic = Arithmetic::Wrapping;
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    assert_eq!(output, vec![i64::MIN, 0]);
+}
+
+#[test]
+fn i128_cells() {
+    let program = "1102,9223372036854775807,2,0,4,0,99";
+    let mut program = Intcode::<i128>::load_cells(program.as_bytes()).unwrap();
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    assert_eq!(output, vec![i128::from(i64::MAX) * 2]);
+
+    // values that don't fit in an i64 still work as input and output
+    let mut program = Intcode::<i128>::load_cells(&b"3,0,1002,0,3,0,4,0,99"[..]).unwrap();
+    let (output, _) = program.run_batch(&[1 << 100]).unwrap();
+    assert_eq!(output, vec![3 << 100]);
+}
+
+#[test]
+fn typed_faults() {
+    assert_eq!(
+        fault(&mut load("104,1,42")),
+        Fault {
+            ip: 2,
+            kind: FaultKind::InvalidOpcode(InvalidOpcode(42))
+        }
+    );
+    assert_eq!(
+        fault(&mut load("11101,1,1,0")).kind,
+        FaultKind::InvalidOutputMode(InvalidOutputMode(ParameterMode::Immediate))
+    );
+    assert_eq!(
+        fault(&mut load("109,-1,204,0")),
+        Fault {
+            ip: 2,
+            kind: FaultKind::InvalidAddress(InvalidAddress(-1))
+        }
+    );
+}
+
+#[test]
+fn relative_base_overflow() {
+    let mut program =
+        load("109,9223372036854775807,109,9223372036854775807,109,9223372036854775807,99");
+    program.relative_base = 1;
+    assert_eq!(fault(&mut program).kind, FaultKind::Overflow);
+}
+
+#[test]
+fn running_off_the_end_of_memory() {
+    // 0 is an invalid opcode, so a program that runs off its end faults rather than panicking
+    let mut program = load("1101,1,1,10");
+    assert_eq!(
+        fault(&mut program).kind,
+        FaultKind::InvalidOpcode(InvalidOpcode(0))
+    );
+
+    // operands past the address limit are invalid addresses
+    let mut program = load("1101,1");
+    program.code.set_limit(3);
+    assert_eq!(
+        fault(&mut program),
+        Fault {
+            ip: 0,
+            kind: FaultKind::InvalidAddress(InvalidAddress(3))
+        }
+    );
+}
+
+#[test]
+fn batch_faults_keep_the_output() {
+    let error = load("104,7,104,8,42").run_batch(&[]).unwrap_err();
+    assert_eq!(error.output, vec![7, 8]);
+    assert_eq!(error.fault.ip, 4);
+    assert_eq!(
+        error.to_string(),
+        "Fault at 4: 42 is not a valid opcode. Output before the fault: 7,8"
+    );
+}
+
+#[test]
+fn faults_convert_to_errors() {
+    let mut program = load("42");
+    let error: Box<dyn std::error::Error> = program.run_batch(&[]).unwrap_err().into();
+    assert_eq!(error.to_string(), "Fault at 0: 42 is not a valid opcode.");
+    assert!(error.source().is_some());
+}
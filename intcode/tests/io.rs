@@ -0,0 +1,106 @@
+mod common;
+
+use common::load;
+use intcode::{ascii, Io};
+use std::iter;
+use std::sync::mpsc::channel;
+use std::thread;
+
+// adds 1 to every input and outputs it, forever
+const INCREMENT: &str = "3,9,1001,9,1,9,4,9,1105,1,0";
+
+#[test]
+fn output_is_streamed() {
+    let mut program = load(INCREMENT);
+    let mut seen = Vec::new();
+    let state = program.run_io(&mut ([1, 2, 3].iter().copied(), |v: i64| seen.push(v)));
+    assert!(!state.is_halted());
+    assert_eq!(seen, vec![2, 3, 4]);
+}
+
+#[test]
+fn resumes_after_running_out_of_input() {
+    let mut program = load(INCREMENT);
+    let mut io = (iter::empty(), Vec::new());
+    assert!(!program.run_io(&mut io).is_halted());
+    assert!(io.1.is_empty());
+
+    let mut io = (iter::once(41), Vec::new());
+    assert!(!program.run_io(&mut io).is_halted());
+    assert_eq!(io.1, vec![42]);
+}
+
+#[test]
+fn controller_reacts_to_each_output() {
+    // counts up from 0 by feeding every output back in as the next input
+    struct Counter {
+        last: i64,
+        seen: Vec<i64>,
+    }
+
+    impl Io for Counter {
+        fn input(&mut self) -> Option<i64> {
+            if self.last < 5 {
+                Some(self.last)
+            } else {
+                None
+            }
+        }
+
+        fn output(&mut self, value: i64) {
+            self.last = value;
+            self.seen.push(value);
+        }
+    }
+
+    let mut program = load(INCREMENT);
+    let mut counter = Counter {
+        last: 0,
+        seen: Vec::new(),
+    };
+    program.run_io(&mut counter).into_result().unwrap();
+    assert_eq!(counter.seen, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn channels_connect_machines() {
+    let (tx1, rx1) = channel();
+    let (tx2, rx2) = channel();
+    let (tx3, rx3) = channel();
+    let mut a = load(INCREMENT);
+    let mut b = load(INCREMENT);
+
+    let a = thread::spawn(move || a.run_io(&mut (rx1.iter(), tx2)).is_halted());
+    let b = thread::spawn(move || b.run_io(&mut (rx2.iter(), tx3)).is_halted());
+    tx1.send(1).unwrap();
+    tx1.send(10).unwrap();
+    assert_eq!(rx3.recv().unwrap(), 3);
+    assert_eq!(rx3.recv().unwrap(), 12);
+
+    // hanging up suspends the first machine, which hangs up on the second
+    drop(tx1);
+    assert!(!a.join().unwrap());
+    assert!(!b.join().unwrap());
+}
+
+#[test]
+fn try_iter_suspends_on_empty_channel() {
+    let (tx, rx) = channel();
+    let mut program = load(INCREMENT);
+    let mut output = Vec::new();
+    tx.send(5).unwrap();
+    assert!(!program
+        .run_io(&mut (rx.try_iter(), |v: i64| output.push(v)))
+        .is_halted());
+    assert_eq!(output, vec![6]);
+}
+
+#[test]
+fn ascii_in_and_out() {
+    // echoes input until it sees a newline, then outputs 1000 and halts
+    let echo = "3,100,4,100,1008,100,10,101,1006,101,0,104,1000,99";
+    let mut program = load(echo);
+    let mut io = (ascii("hi!\n"), String::new());
+    assert!(program.run_io(&mut io).is_halted());
+    assert_eq!(io.1, "hi!\n1000");
+}
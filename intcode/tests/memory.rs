@@ -0,0 +1,98 @@
+mod common;
+
+use common::load;
+use intcode::{FaultKind, InvalidAddress, Memory, RunState, DEFAULT_LIMIT};
+
+#[test]
+fn unwritten_cells_are_zero() {
+    let mut memory: Memory = Memory::from(vec![1, 2, 3]);
+    assert_eq!(memory.len(), 3);
+    assert_eq!(memory[2], 3);
+    assert_eq!(memory[3], 0);
+    assert_eq!(memory.get(1_000_000_000_000), 0);
+
+    // writing just past the program grows the dense region
+    memory[4000] = -1;
+    assert_eq!(memory[4000], -1);
+    assert_eq!(memory.len(), 4001);
+    assert_eq!(memory.to_vec()[3..4000], vec![0; 3997][..]);
+}
+
+#[test]
+fn far_writes_are_sparse() {
+    let mut memory: Memory = Memory::from(vec![1, 2, 3]);
+    memory[1_000_000_000_000] = 42;
+    assert_eq!(memory[1_000_000_000_000], 42);
+    assert_eq!(memory[999_999_999_999], 0);
+    assert_eq!(memory.len(), 3);
+    let pages: Vec<_> = memory.sparse_pages().map(|(start, _)| start).collect();
+    assert_eq!(pages, vec![1_000_000_000_000 / 1024 * 1024]);
+}
+
+#[test]
+fn sparse_pages_join_the_dense_region() {
+    let mut memory = Memory::new();
+    memory[100_000] = 5;
+    assert_eq!(memory.len(), 0);
+
+    // grow the dense region until it reaches the sparse page
+    for i in (500..101_000).step_by(1000) {
+        memory[i] = 1;
+    }
+    assert_eq!(memory.len(), 100_501);
+    assert_eq!(memory[100_000], 5);
+    assert_eq!(memory.sparse_pages().count(), 0);
+}
+
+#[test]
+fn clones_are_copy_on_write() {
+    let mut a: Memory = Memory::from(vec![0; 3000]);
+    a[1 << 30] = 3;
+    let mut b = a.clone();
+    a[10] = 1;
+    b[2500] = 2;
+    b[1 << 30] = 4;
+    assert_eq!((a[10], a[2500], a[1 << 30]), (1, 0, 3));
+    assert_eq!((b[10], b[2500], b[1 << 30]), (0, 2, 4));
+}
+
+#[test]
+fn far_writes_from_a_program() {
+    // writes to address 10^12, then reads it back
+    let mut program = load("1101,6,7,1000000000000,4,1000000000000,99");
+    let (output, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    assert_eq!(output, vec![13]);
+}
+
+#[test]
+fn negative_addresses_are_invalid() {
+    let mut program = load("4,-1,99");
+    match program.run(&mut std::iter::empty()) {
+        RunState::Fault(fault) => {
+            assert_eq!(fault.ip, 0);
+            assert_eq!(fault.kind, FaultKind::InvalidAddress(InvalidAddress(-1)));
+            assert_eq!(fault.to_string(), "Fault at 0: -1 is not a valid address.");
+        }
+        state => panic!("unexpected {}", state),
+    }
+
+    // relative mode, writing
+    assert!(load("109,-5,21101,1,1,2,99").run_batch(&[]).is_err());
+
+    // jumping
+    assert!(load("1105,1,-3").run_batch(&[]).is_err());
+}
+
+#[test]
+fn address_limit() {
+    let mut program = load("1101,1,1,100,99");
+    assert_eq!(program.code.limit(), DEFAULT_LIMIT);
+    program.code.set_limit(100);
+    assert!(program.clone().run_batch(&[]).is_err());
+
+    program.code.set_limit(101);
+    let (_, state) = program.run_batch(&[]).unwrap();
+    assert!(state.is_halted());
+    assert_eq!(program.code[100], 2);
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use common::assembled;
+use intcode::{Intcode, Network, NetworkError, Packet, Route, Router, Schedule};
+
+const SCHEDULES: [Schedule; 2] = [Schedule::RoundRobin, Schedule::Threaded];
+
+/// Computer 0 sends (0, 0) to computer 1. Every computer that receives a packet passes it on to
+/// the next address, adding 1 to x.
+fn relay() -> Intcode {
+    assembled(
+        "
+                IN -> [addr]
+                ADD [addr], #1 -> [next]
+                JT [addr], #wait
+                OUT #1
+                OUT #0
+                OUT #0
+        wait:   IN -> [x]
+                EQ [x], #-1 -> [t]
+                JT [t], #wait
+                IN -> [y]
+                ADD [x], #1 -> [x]
+                OUT [next]
+                OUT [x]
+                OUT [y]
+                JT #1, #wait
+        addr:   DATA 0
+        next:   DATA 0
+        x:      DATA 0
+        y:      DATA 0
+        t:      DATA 0
+        ",
+    )
+}
+
+/// Waits for packets, and sends each one to address 99, with x replaced by its own address.
+fn reply() -> Intcode {
+    assembled(
+        "
+                IN -> [addr]
+        wait:   IN -> [x]
+                EQ [x], #-1 -> [t]
+                JT [t], #wait
+                IN -> [y]
+                OUT #99
+                OUT [addr]
+                OUT [y]
+                JT #1, #wait
+        addr:   DATA 0
+        x:      DATA 0
+        y:      DATA 0
+        t:      DATA 0
+        ",
+    )
+}
+
+/// Stops the network when a packet is sent to `address`, and never wakes it up.
+struct StopAt {
+    address: i64,
+}
+
+impl Router for StopAt {
+    type Output = Packet;
+
+    fn route(&mut self, packet: Packet) -> Route<Packet> {
+        if packet.address == self.address {
+            Route::Stop(packet)
+        } else {
+            Route::Send(vec![packet])
+        }
+    }
+
+    fn idle(&mut self) -> Route<Packet> {
+        Route::Send(Vec::new())
+    }
+}
+
+/// Wakes the network up by sending (0, 42) to computer 2, then stops at the reply.
+#[derive(Default)]
+struct WakeUp {
+    idle: usize,
+}
+
+impl Router for WakeUp {
+    type Output = (usize, Packet);
+
+    fn route(&mut self, packet: Packet) -> Route<(usize, Packet)> {
+        Route::Stop((self.idle, packet))
+    }
+
+    fn idle(&mut self) -> Route<(usize, Packet)> {
+        self.idle += 1;
+        Route::Send(vec![Packet {
+            address: 2,
+            x: 0,
+            y: 42,
+        }])
+    }
+}
+
+#[test]
+fn relays_packets() {
+    for &schedule in &SCHEDULES {
+        let mut network = Network::new(&relay(), 5);
+        network.schedule = schedule;
+        let packet = network.run(&mut StopAt { address: 5 }).unwrap();
+        assert_eq!(
+            packet,
+            Packet {
+                address: 5,
+                x: 4,
+                y: 0
+            },
+            "{:?}",
+            schedule
+        );
+    }
+}
+
+#[test]
+fn router_wakes_up_an_idle_network() {
+    for &schedule in &SCHEDULES {
+        let mut network = Network::new(&reply(), 4);
+        network.schedule = schedule;
+        let (idle, packet) = network.run(&mut WakeUp::default()).unwrap();
+        assert_eq!(idle, 1, "{:?}", schedule);
+        assert_eq!(
+            packet,
+            Packet {
+                address: 99,
+                x: 2,
+                y: 42
+            },
+            "{:?}",
+            schedule
+        );
+    }
+}
+
+#[test]
+fn drops_packets_to_missing_computers() {
+    // the relay's last packet goes to address 3, which doesn't exist
+    for &schedule in &SCHEDULES {
+        let mut network = Network::new(&relay(), 3);
+        network.schedule = schedule;
+        let result = network.run(&mut StopAt { address: 99 });
+        assert_eq!(result, Err(NetworkError::Deadlock), "{:?}", schedule);
+    }
+}
+
+#[test]
+fn idle_network_deadlocks() {
+    for &schedule in &SCHEDULES {
+        let mut network = Network::new(&reply(), 10);
+        network.schedule = schedule;
+        let result = network.run(&mut StopAt { address: 99 });
+        assert_eq!(result, Err(NetworkError::Deadlock), "{:?}", schedule);
+    }
+}
+
+#[test]
+fn halted_network_deadlocks() {
+    for &schedule in &SCHEDULES {
+        let mut network = Network::new(&assembled("IN -> [0]\nHLT"), 3);
+        network.schedule = schedule;
+        let result = network.run(&mut StopAt { address: 99 });
+        assert_eq!(result, Err(NetworkError::Deadlock), "{:?}", schedule);
+    }
+}
+
+#[test]
+fn reports_which_computer_faulted() {
+    // computer 2 jumps into its data
+    let program = assembled(
+        "
+                IN -> [addr]
+                EQ [addr], #2 -> [t]
+                JT [t], #bad
+        wait:   IN -> [t]
+                JT #1, #wait
+        bad:    DATA 42
+        addr:   DATA 0
+        t:      DATA 0
+        ",
+    );
+    for &schedule in &SCHEDULES {
+        let mut network = Network::new(&program, 4);
+        network.schedule = schedule;
+        match network.run(&mut StopAt { address: 99 }) {
+            Err(NetworkError::Fault { address, .. }) => assert_eq!(address, 2, "{:?}", schedule),
+            result => panic!("{:?}: expected a fault, got {:?}", schedule, result),
+        }
+    }
+}
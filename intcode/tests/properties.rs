@@ -0,0 +1,583 @@
+//! Property tests: random programs are run on every way the crate has of running a program, and
+//! each has to agree with a small reference interpreter written straight from the puzzle
+//! descriptions. The programs are random but valid-looking, so most of them get somewhere before
+//! halting, faulting, or running out of input; some of them modify their own code.
+//!
+//! Failures print the seed of the failing case, so it can be reproduced with `case(seed)`.
+
+use intcode::{
+    Arithmetic, Event, FaultKind, Intcode, Opcode, ParameterMode, RunState, Symbolic, Var,
+    DEFAULT_LIMIT,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+
+const CASES: u64 = 2000;
+
+/// Programs that haven't stopped after this many instructions are assumed to loop forever, and
+/// are skipped.
+const STEPS: usize = 10_000;
+
+/// The number of data cells after the code.
+const DATA: usize = 16;
+
+/// xorshift64*, so that the tests don't need any crates.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // xorshift gets stuck on 0
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// A number in `low..=high`.
+    fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next() % (high - low + 1) as u64) as i64
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    /// Mostly small numbers, but sometimes big enough to overflow.
+    fn value(&mut self) -> i64 {
+        match self.below(20) {
+            0 => self.next() as i64,
+            1 => *[i64::MIN, i64::MAX, i64::MAX / 2, -1]
+                .get(self.below(4))
+                .unwrap(),
+            _ => self.range(-50, 50),
+        }
+    }
+}
+
+/// A random program, and its input.
+struct Case {
+    seed: u64,
+    code: Vec<i64>,
+    input: Vec<i64>,
+}
+
+impl Case {
+    fn text(&self) -> String {
+        let code: Vec<_> = self.code.iter().map(i64::to_string).collect();
+        code.join(",")
+    }
+}
+
+/// Generates a program of random instructions, followed by some data. Operands mostly point at
+/// the data, and jumps mostly go to the start of an instruction.
+fn case(seed: u64) -> Case {
+    let mut rng = Rng::new(seed);
+    let mut opcodes = Vec::new();
+    for _ in 0..rng.range(1, 30) {
+        let opcode = match rng.below(20) {
+            0..=7 => [1, 2, 7, 8][rng.below(4)],
+            8..=9 => 3,
+            10..=12 => 4,
+            13..=15 => [5, 6][rng.below(2)],
+            16..=18 => 9,
+            _ => 99,
+        };
+        opcodes.push(opcode);
+    }
+    opcodes.push(99);
+
+    let parameters = |opcode| match opcode {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 | 9 => 1,
+        _ => 0,
+    };
+    let mut starts = Vec::new();
+    let mut data = 0;
+    for &opcode in &opcodes {
+        starts.push(data as i64);
+        data += 1 + parameters(opcode);
+    }
+
+    let mut code = Vec::new();
+    for &opcode in &opcodes {
+        let writes = matches!(opcode, 1 | 2 | 3 | 7 | 8);
+        let jumps = matches!(opcode, 5 | 6);
+        let mut modes = 0;
+        let mut operands = Vec::new();
+        for i in 0..parameters(opcode) {
+            let write = writes && i == parameters(opcode) - 1;
+            let mode = match rng.below(20) {
+                0 if write => 1,
+                _ if write => [0, 0, 2][rng.below(3)],
+                _ => rng.below(3) as i64,
+            };
+            modes += mode * 10i64.pow(i as u32);
+            operands.push(match mode {
+                // mostly the data, but sometimes the code
+                0 if rng.chance(90) => (data + rng.below(DATA)) as i64,
+                0 => rng.below(data) as i64,
+                1 if jumps && i == 1 && rng.chance(80) => starts[rng.below(starts.len())],
+                1 if opcode == 9 => rng.range(-3, 3),
+                1 => rng.value(),
+                _ => (data + rng.below(DATA)) as i64 + rng.range(-8, 4),
+            });
+        }
+        code.push(modes * 100 + opcode);
+        code.extend(operands);
+    }
+    for _ in 0..DATA {
+        code.push(rng.value());
+    }
+
+    let input = (0..rng.below(6)).map(|_| rng.value()).collect();
+    Case { seed, code, input }
+}
+
+/// How a program stopped, in terms that every VM can agree on.
+#[derive(Clone, Debug, PartialEq)]
+enum Stop {
+    Halted,
+    NeedsInput,
+    Fault(&'static str),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Outcome {
+    output: Vec<i64>,
+    stop: Stop,
+    ip: usize,
+
+    /// The number of instructions that were executed: ones that faulted or waited for input
+    /// don't count.
+    executed: usize,
+}
+
+fn fault_name(kind: &FaultKind) -> &'static str {
+    match kind {
+        FaultKind::InvalidOpcode(_) => "invalid opcode",
+        FaultKind::InvalidOutputMode(_) => "invalid output mode",
+        FaultKind::InvalidAddress(_) => "invalid address",
+        FaultKind::Overflow => "overflow",
+    }
+}
+
+/// The reference interpreter: as simple as possible, with no decoding, no pages, and no caches.
+struct Reference {
+    memory: HashMap<i128, i64>,
+    ip: i128,
+    relative_base: i128,
+}
+
+impl Reference {
+    fn new(code: &[i64]) -> Reference {
+        Reference {
+            memory: code
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (i as i128, v))
+                .collect(),
+            ip: 0,
+            relative_base: 0,
+        }
+    }
+
+    fn check(address: i128) -> Result<i128, &'static str> {
+        if address < 0 || address >= DEFAULT_LIMIT as i128 {
+            Err("invalid address")
+        } else {
+            Ok(address)
+        }
+    }
+
+    fn get(&self, address: i128) -> Result<i64, &'static str> {
+        Ok(*self.memory.get(&Reference::check(address)?).unwrap_or(&0))
+    }
+
+    fn parameter(&self, n: i128, mode: i64) -> Result<i64, &'static str> {
+        let operand = self.get(self.ip + n)?;
+        match mode {
+            0 => self.get(operand as i128),
+            1 => Ok(operand),
+            _ => self.get(operand as i128 + self.relative_base),
+        }
+    }
+
+    fn address(&self, n: i128, mode: i64) -> Result<i128, &'static str> {
+        let operand = self.get(self.ip + n)? as i128;
+        match mode {
+            0 => Reference::check(operand),
+            1 => Err("invalid output mode"),
+            _ => Reference::check(operand + self.relative_base),
+        }
+    }
+
+    /// Executes one instruction. Returns what stopped the program, if it stopped.
+    fn step(
+        &mut self,
+        input: &mut dyn Iterator<Item = i64>,
+        output: &mut Vec<i64>,
+    ) -> Result<Option<Stop>, &'static str> {
+        let code = self.get(self.ip)?;
+        let opcode = code % 100;
+        let modes = reference_decode(code).ok_or("invalid opcode")?.1;
+        let parameters = modes.len() as i128;
+
+        let mut next = self.ip + 1 + parameters;
+        match opcode {
+            1 | 2 | 7 | 8 => {
+                let a = self.parameter(1, modes[0])?;
+                let b = self.parameter(2, modes[1])?;
+                let to = self.address(3, modes[2])?;
+                let value = match opcode {
+                    1 => a.checked_add(b).ok_or("overflow")?,
+                    2 => a.checked_mul(b).ok_or("overflow")?,
+                    7 => (a < b) as i64,
+                    _ => (a == b) as i64,
+                };
+                self.memory.insert(to, value);
+            }
+            3 => {
+                let to = self.address(1, modes[0])?;
+                match input.next() {
+                    Some(value) => self.memory.insert(to, value),
+                    None => return Ok(Some(Stop::NeedsInput)),
+                };
+            }
+            4 => output.push(self.parameter(1, modes[0])?),
+            5 | 6 => {
+                let condition = self.parameter(1, modes[0])?;
+                let target = self.parameter(2, modes[1])?;
+                if (condition != 0) == (opcode == 5) {
+                    next = Reference::check(target as i128)?;
+                }
+            }
+            9 => {
+                let offset = self.parameter(1, modes[0])?;
+                self.relative_base = isize::try_from(self.relative_base + offset as i128)
+                    .map_err(|_| "overflow")? as i128;
+            }
+            _ => return Ok(Some(Stop::Halted)),
+        }
+        self.ip = next;
+        Ok(None)
+    }
+
+    /// Runs the program, or returns `None` if it's still going after `STEPS` instructions.
+    fn run(&mut self, input: &[i64]) -> Option<Outcome> {
+        let mut input = input.iter().copied();
+        let mut output = Vec::new();
+        for executed in 0..STEPS {
+            let (stop, executed) = match self.step(&mut input, &mut output) {
+                Ok(None) => continue,
+                Ok(Some(Stop::Halted)) => (Stop::Halted, executed + 1),
+                Ok(Some(stop)) => (stop, executed),
+                Err(fault) => (Stop::Fault(fault), executed),
+            };
+            return Some(Outcome {
+                output,
+                stop,
+                ip: self.ip as usize,
+                executed,
+            });
+        }
+        None
+    }
+}
+
+fn stopped<C: std::fmt::Debug>(state: &RunState<C>) -> (Stop, usize) {
+    match state {
+        RunState::Halted { ip } => (Stop::Halted, *ip),
+        RunState::NeedsInput { ip } => (Stop::NeedsInput, *ip),
+        RunState::Fault(fault) => (Stop::Fault(fault_name(&fault.kind)), fault.ip),
+        state => panic!("unexpected state {:?}", state),
+    }
+}
+
+/// Runs a program to completion with `run_io`.
+fn run<C: intcode::Cell>(program: &mut Intcode<C>, input: &[i64]) -> Outcome {
+    let mut output = Vec::new();
+    let state = program.run_io(&mut (input.iter().map(|&v| C::from_i64(v)), |v: C| {
+        output.push(v.to_i64().unwrap())
+    }));
+    let (stop, ip) = stopped(&state);
+    Outcome {
+        output,
+        stop,
+        ip,
+        executed: 0,
+    }
+}
+
+/// Every address the reference interpreter knows about has the same value in `program`.
+fn same_memory(program: &Intcode, reference: &Reference) -> bool {
+    reference
+        .memory
+        .iter()
+        .all(|(&address, &value)| program.code[address as usize] == value)
+}
+
+fn check(case: &Case) -> bool {
+    let mut reference = Reference::new(&case.code);
+    let expected = match reference.run(&case.input) {
+        Some(outcome) => outcome,
+        None => return false,
+    };
+    let overflowed = expected.stop == Stop::Fault("overflow");
+    let fail = |vm: &str, actual: &dyn std::fmt::Debug| {
+        panic!(
+            "{} disagrees with the reference on case {}\nprogram: {}\ninput: {:?}\nexpected: {:?}\nactual: {:?}",
+            vm,
+            case.seed,
+            case.text(),
+            case.input,
+            expected,
+            actual
+        )
+    };
+    let without_count = |outcome: &Outcome| Outcome {
+        executed: 0,
+        ..outcome.clone()
+    };
+    let program = Intcode::load(case.text().as_bytes()).unwrap();
+
+    // the plain VM
+    let mut plain = program.clone();
+    let actual = run(&mut plain, &case.input);
+    if actual != without_count(&expected) {
+        fail("run_io", &actual);
+    }
+    if plain.relative_base as i128 != reference.relative_base || !same_memory(&plain, &reference) {
+        fail("run_io's memory", &plain.code);
+    }
+
+    // traced, which takes a different path through the VM, and counts instructions
+    let mut traced = program.clone();
+    let events = Arc::new(Mutex::new(Vec::<Event>::new()));
+    traced.tracer = Some(Box::new(events.clone()));
+    let mut actual = run(&mut traced, &case.input);
+    actual.executed = events.lock().unwrap().len();
+    if actual != expected {
+        fail("the traced VM", &actual);
+    }
+
+    // one step at a time, restoring a snapshot into a fresh VM after every output
+    let mut stepped = program.clone();
+    let mut input = case.input.iter().copied();
+    let mut actual = Outcome {
+        output: Vec::new(),
+        stop: Stop::Halted,
+        ip: 0,
+        executed: 0,
+    };
+    loop {
+        match stepped.step(&mut input) {
+            None => (),
+            Some(RunState::Output { value, .. }) => {
+                actual.output.push(value);
+                let snapshot = stepped.snapshot();
+                stepped = program.clone();
+                stepped.restore(&snapshot);
+            }
+            Some(state) => {
+                let (stop, ip) = stopped(&state);
+                actual.stop = stop;
+                actual.ip = ip;
+                break;
+            }
+        }
+    }
+    if actual != without_count(&expected) {
+        fail("step with snapshots", &actual);
+    }
+
+    // stopping at breakpoints along the way doesn't change anything
+    let mut interrupted = program.clone();
+    let mut rng = Rng::new(case.seed);
+    for _ in 0..3 {
+        interrupted.breakpoints.insert(rng.below(case.code.len()));
+    }
+    let mut input = case.input.iter().copied();
+    let mut output = Vec::new();
+    let state = loop {
+        match interrupted.run_io(&mut (&mut input, |v| output.push(v))) {
+            RunState::Breakpoint { .. } => continue,
+            state => break state,
+        }
+    };
+    let (stop, ip) = stopped(&state);
+    let actual = Outcome {
+        output,
+        stop,
+        ip,
+        executed: 0,
+    };
+    if actual != without_count(&expected) {
+        fail("breakpoints", &actual);
+    }
+
+    // symbolically, where every output has to evaluate to what the reference output, and every
+    // assumption has to hold
+    let mut symbolic = Symbolic::new(program.clone());
+    let (outputs, state) = symbolic.run(&case.input);
+    let vars = |var| match var {
+        Var::Input(n) => case.input.get(n).copied(),
+        Var::Cell(_) => None,
+    };
+    let output: Vec<_> = outputs
+        .iter()
+        .map(|value| {
+            let holds = value
+                .assumptions
+                .iter()
+                .all(|a| a.holds(&vars) == Some(true));
+            value.expr.eval(&vars).filter(|_| holds)
+        })
+        .collect();
+    let (stop, ip) = stopped(&state);
+    if output != expected.output.iter().map(|&v| Some(v)).collect::<Vec<_>>()
+        || (stop, ip) != (expected.stop.clone(), expected.ip)
+    {
+        fail("the symbolic VM", &(outputs, state));
+    }
+
+    // other arithmetic only agrees when nothing overflows
+    if !overflowed {
+        let mut wrapping = program.clone();
+        wrapping.arithmetic = Arithmetic::Wrapping;
+        let actual = run(&mut wrapping, &case.input);
+        if actual != without_count(&expected) {
+            fail("wrapping arithmetic", &actual);
+        }
+
+        let mut wide = Intcode::<i128>::load_cells(case.text().as_bytes()).unwrap();
+        let actual = run(&mut wide, &case.input);
+        if actual != without_count(&expected) {
+            fail("i128 cells", &actual);
+        }
+    }
+    true
+}
+
+#[test]
+fn vms_agree_with_the_reference() {
+    let mut checked = 0;
+    let mut stops = HashMap::new();
+    for seed in 0..CASES {
+        let case = case(seed);
+        if check(&case) {
+            checked += 1;
+            let mut reference = Reference::new(&case.code);
+            let outcome = reference.run(&case.input).unwrap();
+            *stops.entry(format!("{:?}", outcome.stop)).or_insert(0) += 1;
+        }
+    }
+
+    // make sure the generator isn't just making programs that loop forever, or that all stop the
+    // same way
+    assert!(checked > CASES * 3 / 4, "only {} cases finished", checked);
+    for stop in &["Halted", "NeedsInput"] {
+        assert!(stops.get(*stop).copied().unwrap_or(0) > 10, "{:?}", stops);
+    }
+    assert!(
+        stops
+            .keys()
+            .filter(|stop| stop.starts_with("Fault"))
+            .count()
+            >= 3,
+        "{:?}",
+        stops
+    );
+}
+
+/// Decodes an opcode the way the puzzles describe: the last two digits are the opcode, and each
+/// digit before that is a parameter's mode. Returns the mnemonic and the modes.
+///
+/// Digits past the last mode are where the VM is lenient: instructions with two or three
+/// parameters ignore them, and so does halt, but instructions with one parameter treat everything
+/// before the opcode as its mode.
+fn reference_decode(code: i64) -> Option<(&'static str, Vec<i64>)> {
+    let (mnemonic, parameters) = match code % 100 {
+        1 => ("ADD", 3),
+        2 => ("MUL", 3),
+        3 => ("IN", 1),
+        4 => ("OUT", 1),
+        5 => ("JT", 2),
+        6 => ("JF", 2),
+        7 => ("LT", 3),
+        8 => ("EQ", 3),
+        9 => ("ARB", 1),
+        99 => ("HLT", 0),
+        _ => return None,
+    };
+    let mut digits = code / 100;
+    let mut modes = Vec::new();
+    for _ in 0..parameters {
+        modes.push(digits % 10);
+        digits /= 10;
+    }
+    if parameters == 1 {
+        modes[0] = code / 100;
+    }
+    if modes.iter().any(|&mode| !(0..=2).contains(&mode)) {
+        return None;
+    }
+    Some((mnemonic, modes))
+}
+
+#[test]
+fn decoding_never_panics() {
+    let mut rng = Rng::new(1);
+    let edges = vec![
+        i64::MIN,
+        i64::MIN + 1,
+        -1,
+        0,
+        1,
+        99,
+        100,
+        199,
+        22201,
+        i64::MAX,
+    ];
+    let digits = (0..100_000).map(|_| {
+        // a few random digits, then a mostly valid opcode
+        let modes = rng.range(0, 4000);
+        modes * 100 + [1, 2, 3, 4, 5, 6, 7, 8, 9, 99, 0, 10][rng.below(12)]
+    });
+    let codes: Vec<_> = edges
+        .into_iter()
+        .chain(digits.collect::<Vec<_>>())
+        .chain((0..100_000).map(|_| rng.next() as i64).collect::<Vec<_>>())
+        .chain(-1000..100_000)
+        .collect();
+
+    for code in codes {
+        let mode = ParameterMode::try_from(code);
+        assert_eq!(mode.is_ok(), (0..=2).contains(&code), "{}", code);
+
+        let opcode = Opcode::try_from(code);
+        match (opcode, reference_decode(code)) {
+            (Ok(opcode), Some((mnemonic, modes))) => {
+                assert_eq!(opcode.mnemonic(), mnemonic, "{}", code);
+                let decoded: Vec<_> = opcode.modes().into_iter().map(|&m| m as i64).collect();
+                assert_eq!(decoded, modes, "{}", code);
+                assert!(code > 0);
+            }
+            (Err(e), None) => {
+                // the error has to display without panicking, too
+                assert!(!e.to_string().is_empty());
+            }
+            (opcode, reference) => panic!("{}: {:?}, expected {:?}", code, opcode, reference),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::load;
+use intcode::{RunState, Snapshot, DEFAULT_LIMIT};
+
+// adds 1 to every input and outputs it, forever
+const INCREMENT: &str = "3,9,1001,9,1,9,4,9,1105,1,0";
+
+#[test]
+fn restore() {
+    let mut program = load(INCREMENT);
+    let (output, _) = program.run_batch(&[1]).unwrap();
+    assert_eq!(output, vec![2]);
+    let snapshot = program.snapshot();
+
+    let (output, _) = program.run_batch(&[10, 20]).unwrap();
+    assert_eq!(output, vec![11, 21]);
+    assert_eq!(program.code[9], 21);
+
+    program.restore(&snapshot);
+    assert_eq!(program.code[9], 2);
+    let (output, _) = program.run_batch(&[5]).unwrap();
+    assert_eq!(output, vec![6]);
+}
+
+#[test]
+fn branching_search() {
+    // every branch starts from the same snapshot without affecting the others
+    let mut program = load(INCREMENT);
+    program.run_batch(&[]).unwrap();
+    let start = program.snapshot();
+    for i in 0..10 {
+        let mut branch = program.clone();
+        branch.restore(&start);
+        let (output, _) = branch.run_batch(&[i]).unwrap();
+        assert_eq!(output, vec![i + 1]);
+    }
+}
+
+#[test]
+fn save_and_load() {
+    let mut program = load(INCREMENT);
+    program.code[199] = 0;
+    program.code[5000000] = 7;
+    program.relative_base = -3;
+    match program.run(&mut [41].iter().copied()) {
+        RunState::Output { value: 42, .. } => (),
+        state => panic!("unexpected {}", state),
+    }
+    let mut snapshot = program.snapshot();
+    snapshot.input = vec![1, 2];
+    snapshot.output = vec![42];
+
+    let mut file = Vec::new();
+    snapshot.save(&mut file).unwrap();
+    let text = String::from_utf8(file.clone()).unwrap();
+    assert_eq!(
+        text,
+        "intcode snapshot 1\n\
+         ip 8\n\
+         relative_base -3\n\
+         input 1,2\n\
+         output 42\n\
+         memory 3,9,1001,9,1,9,4,9,1105,42,0*190\n\
+         page 4999168 0*832,7\n"
+    );
+
+    let loaded = Snapshot::load(&file[..]).unwrap();
+    assert_eq!(loaded, snapshot);
+
+    let mut program = load("99");
+    program.restore(&loaded);
+    let (output, _) = program.run_batch(&loaded.input).unwrap();
+    assert_eq!(output, vec![2, 3]);
+}
+
+#[test]
+fn load_rejects_other_files() {
+    assert!(Snapshot::load(&b"1,2,3\n"[..]).is_err());
+    assert!(Snapshot::load(&b"intcode snapshot 1\nregister 4\n"[..]).is_err());
+    assert!(Snapshot::load(&b"intcode snapshot 1\nmemory 1,x\n"[..]).is_err());
+}
+
+#[test]
+fn load_rejects_values_past_the_limit() {
+    let error = |file: String| Snapshot::load(file.as_bytes()).unwrap_err().to_string();
+    let limit = DEFAULT_LIMIT;
+    assert_eq!(
+        error(format!("intcode snapshot 1\ninput 1*{}\n", limit + 1)),
+        "More than 16777216 values in a snapshot field."
+    );
+    assert_eq!(
+        error(format!("intcode snapshot 1\nmemory 1,0*{}\n", usize::MAX)),
+        "More than 16777216 values in a snapshot field."
+    );
+    assert_eq!(
+        error(format!("intcode snapshot 1\npage {} 1\n", usize::MAX)),
+        format!("Page {} is past the address limit.", usize::MAX)
+    );
+    assert_eq!(
+        error(format!("intcode snapshot 1\npage {} 1,2\n", limit - 1)),
+        "More than 1 values in a snapshot field."
+    );
+}
+
+#[test]
+fn load_rejects_huge_runs() {
+    let error = |file: &str| Snapshot::load(file.as_bytes()).unwrap_err().to_string();
+    for field in &["input", "output", "memory", "page 0"] {
+        let file = format!("intcode snapshot 1\n{} 0*1000000000\n", field);
+        assert_eq!(
+            error(&file),
+            "More than 16777216 values in a snapshot field."
+        );
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::load;
+use intcode::{Fault, RunState};
+use std::iter;
+
+#[test]
+fn stops_on_each_output() {
+    let mut program = load("104,1,104,2,99");
+    let mut input = iter::empty();
+    match program.run(&mut input) {
+        RunState::Output { ip: 0, value: 1 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    match program.run(&mut input) {
+        RunState::Output { ip: 2, value: 2 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    match program.run(&mut input) {
+        RunState::Halted { ip: 4 } => (),
+        state => panic!("unexpected {}", state),
+    }
+
+    // running a halted program halts again
+    assert!(program.run(&mut input).is_halted());
+    assert_eq!(program.ip, 4);
+}
+
+#[test]
+fn needs_input() {
+    let mut program = load("1101,1,2,10,3,11,4,11,99");
+    match program.run(&mut iter::empty()) {
+        RunState::NeedsInput { ip: 4 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert_eq!(program.ip, 4);
+    assert_eq!(program.code[10], 3);
+
+    match program.run(&mut iter::once(7)) {
+        RunState::Output { ip: 6, value: 7 } => (),
+        state => panic!("unexpected {}", state),
+    }
+}
+
+#[test]
+fn step_executes_one_instruction() {
+    let mut program = load("1101,1,2,0,1101,3,4,1,99");
+    assert!(program.step(&mut iter::empty()).is_none());
+    assert_eq!(program.ip, 4);
+    assert_eq!(program.code[0], 3);
+
+    assert!(program.step(&mut iter::empty()).is_none());
+    assert_eq!(program.ip, 8);
+    assert_eq!(program.code[1], 7);
+
+    assert!(program.step(&mut iter::empty()).unwrap().is_halted());
+}
+
+#[test]
+fn breakpoints() {
+    let mut program = load("1101,1,2,0,1101,3,4,1,99");
+    program.breakpoints.insert(4);
+    match program.run(&mut iter::empty()) {
+        RunState::Breakpoint { ip: 4 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert_eq!(program.code[0], 3);
+    assert_eq!(program.code[1], 1);
+
+    // resuming executes the instruction at the breakpoint
+    assert!(program.run(&mut iter::empty()).is_halted());
+    assert_eq!(program.code[1], 7);
+}
+
+#[test]
+fn breakpoint_in_a_loop() {
+    // counts down from 3, outputting each number
+    let mut program = load("4,12,1001,12,-1,12,1005,12,0,99,0,0,3");
+    program.breakpoints.insert(2);
+    let mut output = Vec::new();
+    let mut hits = 0;
+    loop {
+        match program.run_io(&mut (iter::empty(), |v: i64| output.push(v))) {
+            RunState::Breakpoint { ip: 2 } => hits += 1,
+            RunState::Halted { .. } => break,
+            state => panic!("unexpected {}", state),
+        }
+    }
+    assert_eq!(hits, 3);
+    assert_eq!(output, vec![3, 2, 1]);
+}
+
+#[test]
+fn faults_carry_ip() {
+    let mut program = load("1101,1,2,0,42");
+    match program.run(&mut iter::empty()) {
+        RunState::Fault(Fault { ip: 4, .. }) => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert_eq!(program.ip, 4);
+    assert!(program.run(&mut iter::empty()).into_result().is_err());
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::assembled;
+use intcode::{Assumption, Expr, RunState, Symbolic, Var};
+use std::rc::Rc;
+
+fn inputs(values: &'static [i64]) -> impl Fn(Var) -> Option<i64> {
+    move |var| match var {
+        Var::Input(n) => values.get(n).copied(),
+        Var::Cell(_) => None,
+    }
+}
+
+#[test]
+fn outputs_arithmetic_on_inputs() {
+    // (a + 2) * (a - 2) + 3 * b
+    let mut symbolic = Symbolic::new(assembled(
+        "
+                IN -> [a]
+                IN -> [b]
+                ADD [a], #2 -> [x]
+                ADD [a], #-2 -> [y]
+                MUL [x], [y] -> [x]
+                MUL [b], #3 -> [y]
+                ADD [x], [y] -> [x]
+                OUT [x]
+                HLT
+        a:      DATA 0
+        b:      DATA 0
+        x:      DATA 0
+        y:      DATA 0
+        ",
+    ));
+    let (outputs, state) = symbolic.run(&[5, 7]);
+    assert!(state.is_halted());
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0].to_string(), "in0 * in0 + 3 * in1 - 4");
+    assert!(outputs[0].assumptions.is_empty());
+    assert!(!outputs[0].expr.is_linear());
+    assert_eq!(outputs[0].expr.as_linear(), None);
+    for &(a, b) in &[(5, 7), (-3, 0), (100, -100)] {
+        let expected = (a + 2) * (a - 2) + 3 * b;
+        let vars = move |var| match var {
+            Var::Input(0) => Some(a),
+            Var::Input(1) => Some(b),
+            _ => None,
+        };
+        assert_eq!(outputs[0].expr.eval(&vars), Some(expected));
+    }
+}
+
+#[test]
+fn things_that_cancel_out_disappear() {
+    // a - a, and a comparison times itself
+    let mut symbolic = Symbolic::new(assembled(
+        "
+                IN -> [a]
+                MUL [a], #-1 -> [x]
+                ADD [a], [x] -> [x]
+                OUT [x]
+                LT #0, [a] -> [x]
+                MUL [x], [x] -> [x]
+                OUT [x]
+                HLT
+        a:      DATA 0
+        x:      DATA 0
+        ",
+    ));
+    let (outputs, _) = symbolic.run(&[5]);
+    assert_eq!(outputs[0].to_string(), "0");
+    assert_eq!(outputs[1].to_string(), "0 < in0");
+}
+
+#[test]
+fn comparisons_of_comparisons_are_parenthesized() {
+    let mut symbolic = Symbolic::new(assembled(
+        "
+                IN -> [a]
+                IN -> [b]
+                IN -> [c]
+                LT [a], [b] -> [x]
+                EQ [x], [c] -> [y]
+                OUT [y]
+                LT [c], [x] -> [y]
+                OUT [y]
+                HLT
+        a:      DATA 0
+        b:      DATA 0
+        c:      DATA 0
+        x:      DATA 0
+        y:      DATA 0
+        ",
+    ));
+    let (outputs, _) = symbolic.run(&[1, 2, 1]);
+    assert_eq!(outputs[0].to_string(), "(in0 < in1) == in2");
+    assert_eq!(outputs[1].to_string(), "in2 < (in0 < in1)");
+}
+
+#[test]
+fn day_2_style_memory_vars() {
+    // like day 2: the first instruction reads through the noun and verb, but what it writes is
+    // thrown away
+    let mut program = assembled(
+        "
+                ADD [0], [0] -> [3]
+                ADD [1], [2] -> [3]
+                MUL [3], [k] -> [0]
+                HLT
+        k:      DATA 5
+        ",
+    );
+    program.code[1] = 12;
+    program.code[2] = 2;
+    let mut symbolic = Symbolic::new(program);
+    symbolic.var(1);
+    symbolic.var(2);
+    let (outputs, state) = symbolic.run(&[]);
+    assert!(outputs.is_empty());
+    assert!(state.is_halted());
+
+    let result = symbolic.value_assuming(0);
+    assert_eq!(result.to_string(), "5 * [1] + 5 * [2]");
+    assert!(result.expr.is_linear());
+    let coefficients = vec![(Var::Cell(1), 5), (Var::Cell(2), 5)];
+    assert_eq!(
+        result.expr.as_linear(),
+        Some((0, coefficients.into_iter().collect()))
+    );
+    assert!(result.assumptions.is_empty());
+}
+
+#[test]
+fn follows_data_dependent_jumps() {
+    let program = assembled(
+        "
+                IN -> [a]
+                LT [a], #10 -> [t]
+                JT [t], #small
+                OUT #0
+                HLT
+        small:  ADD [a], [a] -> [a]
+                OUT [a]
+                HLT
+        a:      DATA 0
+        t:      DATA 0
+        ",
+    );
+
+    let (outputs, _) = Symbolic::new(program.clone()).run(&[3]);
+    assert_eq!(outputs[0].to_string(), "2 * in0, assuming in0 < 10");
+    assert_eq!(outputs[0].assumptions[0].holds(&inputs(&[3])), Some(true));
+    assert_eq!(outputs[0].assumptions[0].holds(&inputs(&[10])), Some(false));
+
+    let (outputs, _) = Symbolic::new(program).run(&[20]);
+    assert_eq!(outputs[0].to_string(), "0, assuming in0 >= 10");
+}
+
+#[test]
+fn symbolic_addresses() {
+    // reading through an address only matters to the value read, but writing through one matters
+    // to everything afterwards
+    let mut symbolic = Symbolic::new(assembled(
+        "
+                IN -> [p]
+                ARB [p]
+                OUT [rb+0]
+                IN -> [rb+1]
+                OUT #7
+                HLT
+        p:      DATA 0
+        ",
+    ));
+    let (outputs, state) = symbolic.run(&[2, 9]);
+    assert!(state.is_halted());
+    assert_eq!(outputs.len(), 2);
+
+    // address 2 is the ARB instruction
+    let in0 = Rc::new(Expr::Var(Var::Input(0)));
+    assert_eq!(outputs[0].expr.as_const(), Some(9));
+    assert_eq!(outputs[0].assumptions, vec![Assumption::Equals(in0, 2)]);
+    assert_eq!(outputs[1].to_string(), "7, assuming in0 + 1 == 3");
+}
+
+#[test]
+fn stops_when_input_runs_out() {
+    let mut symbolic = Symbolic::new(assembled("IN -> [0]\nOUT [0]\nIN -> [0]\nHLT"));
+    let (outputs, state) = symbolic.run(&[4]);
+    assert_eq!(outputs[0].to_string(), "in0");
+    assert_eq!(state, RunState::NeedsInput { ip: 4 });
+
+    // the next input is the next var
+    let (outputs, state) = symbolic.run(&[5]);
+    assert!(outputs.is_empty());
+    assert!(state.is_halted());
+    assert_eq!(symbolic.value(0).to_string(), "in1");
+}
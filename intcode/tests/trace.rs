@@ -0,0 +1,121 @@
+mod common;
+
+use common::load;
+use intcode::{Event, Intcode, Opcode, ParameterMode, Profile, RunState, TraceLog, Tracer};
+use std::iter;
+use std::sync::{Arc, Mutex};
+
+fn trace(program: &mut Intcode) -> Arc<Mutex<Vec<Event>>> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    program.tracer = Some(Box::new(events.clone()));
+    events
+}
+
+#[test]
+fn traces_each_instruction() {
+    let mut program = load("1101,2,3,9,4,9,99");
+    let events = trace(&mut program);
+    program.run_batch(&[]).unwrap();
+    use ParameterMode::*;
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            Event {
+                ip: 0,
+                opcode: Opcode::Add(Immediate, Immediate, Position),
+                operands: vec![2, 3, 9],
+                values: vec![2, 3],
+                write: Some((9, 5)),
+            },
+            Event {
+                ip: 4,
+                opcode: Opcode::Output(Position),
+                operands: vec![9],
+                values: vec![5],
+                write: None,
+            },
+            Event {
+                ip: 6,
+                opcode: Opcode::Halt,
+                operands: vec![],
+                values: vec![],
+                write: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn waiting_for_input_is_not_traced() {
+    let mut program = load("3,5,99");
+    let events = trace(&mut program);
+    match program.run(&mut iter::empty()) {
+        RunState::NeedsInput { ip: 0 } => (),
+        state => panic!("unexpected {}", state),
+    }
+    assert!(events.lock().unwrap().is_empty());
+
+    program.run(&mut iter::once(7));
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].write, Some((5, 7)));
+
+    // clones don't inherit the tracer
+    assert!(program.clone().tracer.is_none());
+}
+
+#[test]
+fn trace_log_is_json() {
+    let events: Vec<Event> = vec![
+        Event {
+            ip: 42,
+            opcode: Opcode::Add(
+                ParameterMode::Relative,
+                ParameterMode::Immediate,
+                ParameterMode::Position,
+            ),
+            operands: vec![3, -5, 100],
+            values: vec![7, -5],
+            write: Some((100, 2)),
+        },
+        Event {
+            ip: 46,
+            opcode: Opcode::Halt,
+            operands: vec![],
+            values: vec![],
+            write: None,
+        },
+    ];
+    let mut log = Vec::new();
+    let mut tracer = TraceLog::new(&mut log);
+    for event in &events {
+        tracer.trace(event);
+    }
+    assert_eq!(
+        String::from_utf8(log).unwrap(),
+        concat!(
+            r##"{"ip":42,"op":"ADD","operands":["[rb+3]","#-5","[100]"],"values":[7,-5],"write":[100,2]}"##,
+            "\n",
+            r#"{"ip":46,"op":"HLT","operands":[],"values":[],"write":null}"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn profile() {
+    // reads a number, then counts it down to zero, outputting each step
+    let mut program = load("3,12,4,12,1001,12,-1,12,1005,12,2,99");
+    let profile = Arc::new(Mutex::new(Profile::default()));
+    program.tracer = Some(Box::new(profile.clone()));
+    program.run(&mut iter::empty());
+    let (output, _) = program.run_batch(&[3]).unwrap();
+    assert_eq!(output, vec![3, 2, 1]);
+
+    let profile = profile.lock().unwrap();
+    assert_eq!(profile.instructions, 1 + 3 * 3 + 1);
+    assert_eq!((profile.inputs, profile.outputs), (1, 3));
+    assert_eq!(profile.opcodes["OUT"], 3);
+    assert_eq!(profile.hottest(2), vec![(2, 3), (4, 3)]);
+    assert_eq!(profile.between_inputs, vec![0, 11]);
+}